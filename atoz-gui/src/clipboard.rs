@@ -0,0 +1,32 @@
+use std::{cell::RefCell, rc::Rc};
+
+// text clipboard used by the text widgets. the platform clipboard is plugged in by the
+// application, without one the widgets share the clipboard of the process.
+pub trait Clipboard {
+    fn get_text(&mut self) -> Option<String>;
+    fn set_text(&mut self, text: String);
+}
+
+#[derive(Debug, Default)]
+pub struct LocalClipboard {
+    text: Option<String>,
+}
+
+impl Clipboard for LocalClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        return self.text.clone();
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
+
+thread_local! {
+    static SHARED: Rc<RefCell<LocalClipboard>> = Rc::new(RefCell::new(LocalClipboard::default()));
+}
+
+// the clipboard of the process, shared by all widgets of the thread.
+pub fn get_shared() -> Rc<RefCell<dyn Clipboard>> {
+    return SHARED.with(|clipboard| clipboard.clone());
+}
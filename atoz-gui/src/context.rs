@@ -1,39 +1,49 @@
 use atoz_renderer::{
-    layer::{Layer, LayerBuffer},
-    pipeline::{
-        circle::CirclePipeline, image::ImagePipeline, rect::RectPipeline,
-        triangle::TrianglePipeline,
-    },
-    viewport::Viewport,
-};
-use wgpu::{
-    InstanceDescriptor, InstanceFlags, RenderPassColorAttachment, RenderPassDescriptor,
-    SurfaceConfiguration, TextureViewDescriptor,
+    clip::Clip,
+    config::RendererConfig,
+    layer::Layer,
+    renderer::{RenderError, Renderer},
+    target::{OffscreenTarget, RenderTarget, SurfaceTarget},
+    transform::Transform,
 };
+use image::RgbaImage;
+use wgpu::{InstanceDescriptor, InstanceFlags};
 use winit::{
-    dpi::Size,
+    dpi::{PhysicalSize, Size},
     event_loop::EventLoop,
     window::{WindowBuilder, WindowId},
 };
 
+use crate::{layers::LayerCache, layout::Rect, widget::Widget};
+
+#[derive(Debug)]
+enum ContextTarget {
+    Surface(SurfaceTarget),
+    Offscreen(OffscreenTarget),
+}
+
 #[derive(Debug)]
 pub struct Context {
-    window: winit::window::Window,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    surface: wgpu::Surface,
-    config: wgpu::SurfaceConfiguration,
-    layers: Vec<Layer>,
-    rect_pipeline: RectPipeline,
-    triangle_pipeline: TrianglePipeline,
-    circle_pipeline: CirclePipeline,
-    image_pipeline: ImagePipeline,
+    window: Option<winit::window::Window>,
+    renderer: Renderer,
+    target: ContextTarget,
+    layers: LayerCache,
+    scale_factor: f64,
+    config: RendererConfig,
 }
 
 impl Context {
-    pub async fn init(event_loop: &EventLoop<()>) -> Self {
-        let window = WindowBuilder::new().build(event_loop).unwrap();
+    pub async fn init(
+        event_loop: &EventLoop<()>,
+        config: RendererConfig,
+    ) -> Result<Self, RenderError> {
+        let window = WindowBuilder::new()
+            .with_transparent(config.is_transparent())
+            .build(event_loop)?;
+        // input methods send their compositions as preedit events to the text widgets.
+        window.set_ime_allowed(true);
         let size = window.inner_size();
+        let scale_factor = window.scale_factor();
 
         let instance = wgpu::Instance::new(InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -41,7 +51,7 @@ impl Context {
             flags: InstanceFlags::all(),
             gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
         });
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+        let surface = unsafe { instance.create_surface(&window) }?;
 
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -50,19 +60,19 @@ impl Context {
                 force_fallback_adapter: false,
             })
             .await
-            .unwrap();
+            .ok_or(RenderError::AdapterNotFound)?;
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
+                    // timestamp queries for the gpu time of the profiler, which is skipped without them.
+                    features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
                     limits: wgpu::Limits::default(),
                     label: None,
                 },
                 None,
             )
-            .await
-            .unwrap();
+            .await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
 
@@ -71,150 +81,219 @@ impl Context {
             .iter()
             .copied()
             .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
-
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
-        };
-        surface.configure(&device, &config);
-
-        let rect_pipeline = RectPipeline::new(&device, config.format);
-        let triangle_pipeline = TrianglePipeline::new(&device, config.format);
-        let circle_pipeline = CirclePipeline::new(&device, config.format);
-        let image_pipeline = ImagePipeline::new(&device, config.format);
+            .or(surface_caps.formats.first().copied())
+            .ok_or(RenderError::UnsupportedSurface)?;
 
-        return Self {
-            window,
-            device,
-            queue,
+        let surface_config = config.get_surface_configuration(
+            &surface_caps,
+            surface_format,
+            size.width,
+            size.height,
+        );
+        let mut renderer = Renderer::new(&adapter, device, queue, surface_format);
+        renderer.set_scale_factor(scale_factor as f32);
+        renderer.set_partial_redraw(true);
+        renderer.set_sample_count(config.sample_count);
+        let target = SurfaceTarget::new(
             surface,
+            surface_config,
+            renderer.get_device(),
+            renderer.get_sample_count(),
+        );
+
+        return Ok(Self {
+            window: Some(window),
+            renderer,
+            target: ContextTarget::Surface(target),
+            layers: LayerCache::new(),
+            scale_factor,
             config,
-            layers: vec![],
-            rect_pipeline,
-            triangle_pipeline,
-            circle_pipeline,
-            image_pipeline,
-        };
+        });
+    }
+
+    // context rendering into an offscreen texture, used for snapshot tests.
+    // width and height are in physical pixels, the scale factor starts at 1.
+    pub async fn headless(width: u32, height: u32) -> Result<Self, RenderError> {
+        let mut renderer = Renderer::headless().await?;
+        renderer.set_partial_redraw(true);
+        let target = renderer.create_offscreen_target(width, height);
+
+        return Ok(Self {
+            window: None,
+            renderer,
+            target: ContextTarget::Offscreen(target),
+            layers: LayerCache::new(),
+            scale_factor: 1.0,
+            config: RendererConfig::default(),
+        });
     }
 
-    pub fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
-        if size.width > 0 && size.height > 0 {
-            self.config.width = size.width;
-            self.config.height = size.height;
-            self.surface.configure(&self.device, &self.config);
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.renderer.damage_all();
+        match &mut self.target {
+            ContextTarget::Surface(target) => {
+                target.resize(self.renderer.get_device(), size.width, size.height);
+            }
+            ContextTarget::Offscreen(target) => {
+                if size.width > 0 && size.height > 0 {
+                    *target = self
+                        .renderer
+                        .create_offscreen_target(size.width, size.height);
+                }
+            }
         }
     }
 
+    // keeps the logical size of an offscreen target. a window is resized by the platform,
+    // which sends a Resized event after ScaleFactorChanged.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        let (width, height) = self.get_size();
+        self.scale_factor = scale_factor;
+        self.renderer.set_scale_factor(scale_factor as f32);
+        if self.window.is_none() {
+            self.resize(PhysicalSize::new(
+                (width as f64 * scale_factor).round() as u32,
+                (height as f64 * scale_factor).round() as u32,
+            ));
+        }
+    }
+
+    pub fn get_scale_factor(&self) -> f64 {
+        return self.scale_factor;
+    }
+
+    pub fn get_config(&self) -> &RendererConfig {
+        return &self.config;
+    }
+
     pub fn set_min_size<S: Into<Size>>(&mut self, size: S) {
-        self.window.set_min_inner_size(Some(size));
+        if let Some(window) = &self.window {
+            window.set_min_inner_size(Some(size));
+        }
     }
 
     pub fn set_max_size<S: Into<Size>>(&mut self, size: S) {
-        self.window.set_max_inner_size(Some(size));
+        if let Some(window) = &self.window {
+            window.set_max_inner_size(Some(size));
+        }
     }
 
-    pub fn get_config(&self) -> SurfaceConfiguration {
-        return self.config.clone();
+    // size in logical pixels, which is what widgets lay out in.
+    pub fn get_size(&self) -> (u32, u32) {
+        let (width, height) = self.get_physical_size();
+        return (
+            (width as f64 / self.scale_factor).round() as u32,
+            (height as f64 / self.scale_factor).round() as u32,
+        );
     }
 
-    pub fn get_window_id(&self) -> WindowId {
-        return self.window.id();
+    pub fn get_physical_size(&self) -> (u32, u32) {
+        return match &self.target {
+            ContextTarget::Surface(target) => target.size(),
+            ContextTarget::Offscreen(target) => target.size(),
+        };
+    }
+
+    pub fn get_window_id(&self) -> Option<WindowId> {
+        return self.window.as_ref().map(|window| window.id());
     }
 
     pub fn request_redraw(&self) {
-        self.window.request_redraw();
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
     }
 
     pub fn push_layers(&mut self, layer: Layer) {
-        self.layers.push(layer);
+        self.layers.push_layer(layer);
     }
 
+    // the layer the widget being rendered draws into.
+    pub fn get_layer(&mut self) -> &mut Layer {
+        return self.layers.get_layer();
+    }
+
+    // renders a child into layers of its own. index is the position of the child in its parent.
+    // the layers of the last frame are reused when nothing inside of the child is damaged.
+    pub fn render_child(&mut self, index: usize, child: &dyn Widget) {
+        let (width, height) = self.get_size();
+        let damage = self
+            .renderer
+            .get_damage()
+            .get_bounds(width as f32, height as f32);
+        if self.layers.begin_widget(index, child.get_rect(), damage) {
+            child.render(self);
+        }
+        self.layers.end_widget();
+    }
+
+    // clips the rest of the widget and its children, given in the space of the current transform.
+    pub fn push_clip(&mut self, clip: Clip) {
+        self.layers.push_clip(clip);
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.layers.pop_clip();
+    }
+
+    pub fn push_transform(&mut self, transform: Transform) {
+        self.layers.push_transform(transform);
+    }
+
+    pub fn pop_transform(&mut self) {
+        self.layers.pop_transform();
+    }
+
+    // marks a rect in logical pixels to be redrawn by the next render.
+    pub fn invalidate(&mut self, rect: Rect) {
+        self.renderer.add_damage(
+            [rect.left() as _, rect.top() as _],
+            [rect.width() as _, rect.height() as _],
+        );
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.renderer.damage_all();
+    }
+
+    pub fn is_damaged(&self) -> bool {
+        return !self.renderer.get_damage().is_empty();
+    }
+
+    // starts a frame, keeping the layers of the last one for render_child.
+    pub fn begin_frame(&mut self) {
+        self.layers.begin_frame();
+    }
+
+    // drops the layers and the ones cached, so that every widget is rendered again.
     pub fn clear_layers(&mut self) {
         self.layers.clear();
     }
 
-    pub fn render(&self) {
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("context.render.encoder"),
-            });
-
-        let output = self.surface.get_current_texture().unwrap();
-        let view = output
-            .texture
-            .create_view(&TextureViewDescriptor::default());
+    pub fn get_renderer(&self) -> &Renderer {
+        return &self.renderer;
+    }
 
-        let viewport_group = Viewport::new(self.config.width as _, self.config.height as _)
-            .get_bind_group(&self.device);
+    pub fn get_renderer_mut(&mut self) -> &mut Renderer {
+        return &mut self.renderer;
+    }
 
-        let layer_buffers = self
-            .layers
-            .iter()
-            .map(|layer| layer.get_all_buffers(&self.device))
-            .collect::<Vec<LayerBuffer>>();
-
-        {
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("context.render.render_pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            layer_buffers.iter().for_each(|buffer| {
-                self.rect_pipeline.render(
-                    &mut render_pass,
-                    &buffer.rect_buffer,
-                    buffer.rect_count as _,
-                    &viewport_group,
-                );
-
-                self.triangle_pipeline.render(
-                    &mut render_pass,
-                    &buffer.triangle_buffer,
-                    buffer.triangle_count as _,
-                    &viewport_group,
-                );
-
-                self.circle_pipeline.render(
-                    &mut render_pass,
-                    &buffer.circle_buffer,
-                    buffer.circle_count as _,
-                    &viewport_group,
-                );
-
-                buffer.image_buffers.iter().for_each(
-                    |(texture, instance_buffer, instance_count)| {
-                        if instance_count > &0 {
-                            self.image_pipeline.render(
-                                &mut render_pass,
-                                instance_buffer,
-                                instance_count.clone(),
-                                texture,
-                                &viewport_group,
-                            );
-                        }
-                    },
-                );
-            });
-        }
+    pub fn render(&mut self) -> Result<(), RenderError> {
+        return match &self.target {
+            ContextTarget::Surface(target) => {
+                self.renderer.render(target, self.layers.get_layers())
+            }
+            ContextTarget::Offscreen(target) => {
+                self.renderer.render(target, self.layers.get_layers())
+            }
+        };
+    }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+    // returns the last rendered frame of a headless context.
+    pub fn read_image(&self) -> Option<RgbaImage> {
+        return match &self.target {
+            ContextTarget::Surface(_) => None,
+            ContextTarget::Offscreen(target) => Some(self.renderer.read_image(target)),
+        };
     }
 }
@@ -0,0 +1,554 @@
+use winit::{
+    event::{ElementState, Ime, MouseScrollDelta, WindowEvent},
+    keyboard::{Key, ModifiersState, NamedKey},
+};
+
+pub use winit::event::{MouseButton, TouchPhase};
+
+use crate::{focus::FocusManager, widget::Widget};
+
+// input delivered to widgets. positions are in logical pixels of the window.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    MouseMove {
+        position: [f32; 2],
+    },
+    MousePress {
+        position: [f32; 2],
+        button: MouseButton,
+        modifiers: ModifiersState,
+    },
+    MouseRelease {
+        position: [f32; 2],
+        button: MouseButton,
+    },
+    // touchpads report when the fingers start and stop scrolling, wheels only move.
+    MouseWheel {
+        position: [f32; 2],
+        delta: ScrollDelta,
+        phase: TouchPhase,
+    },
+    KeyPress {
+        key: Key,
+        modifiers: ModifiersState,
+        repeat: bool,
+    },
+    KeyRelease {
+        key: Key,
+        modifiers: ModifiersState,
+    },
+    // text typed or committed by an input method, sent after a key press nobody handled.
+    Text(String),
+    // text an input method is composing, with the selected byte range inside of it.
+    // an empty text ends the composition.
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+}
+
+impl Event {
+    // mouse events are delivered to the widget under their position.
+    pub fn get_position(&self) -> Option<[f32; 2]> {
+        return match self {
+            Event::MouseMove { position }
+            | Event::MousePress { position, .. }
+            | Event::MouseRelease { position, .. }
+            | Event::MouseWheel { position, .. } => Some(*position),
+            Event::KeyPress { .. }
+            | Event::KeyRelease { .. }
+            | Event::Text(_)
+            | Event::Preedit { .. } => None,
+        };
+    }
+
+    // the event with its position moved by offset, e.g. into scrolled content.
+    pub fn translate(&self, offset: [f32; 2]) -> Event {
+        let mut event = self.clone();
+        match &mut event {
+            Event::MouseMove { position }
+            | Event::MousePress { position, .. }
+            | Event::MouseRelease { position, .. }
+            | Event::MouseWheel { position, .. } => {
+                position[0] += offset[0];
+                position[1] += offset[1];
+            }
+            Event::KeyPress { .. }
+            | Event::KeyRelease { .. }
+            | Event::Text(_)
+            | Event::Preedit { .. } => {}
+        }
+        return event;
+    }
+}
+
+// wheels scroll by lines, touchpads by logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollDelta {
+    Lines([f32; 2]),
+    Pixels([f32; 2]),
+}
+
+// an event first travels from the root down to the target in the capture phase,
+// then the target gets it, then it bubbles back up to the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPhase {
+    Capture,
+    Target,
+    Bubble,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventResult {
+    Handled,
+    Ignored,
+}
+
+// child indices from the root to the deepest widget whose rect contains the position.
+// later children paint over earlier ones, so they are hit first.
+pub fn hit_test(widget: &dyn Widget, position: [f32; 2]) -> Vec<usize> {
+    let mut path = vec![];
+    let mut widget = widget;
+    let mut position = position;
+    loop {
+        let offset = widget.get_child_offset();
+        position = [position[0] + offset[0], position[1] + offset[1]];
+        let child = widget
+            .get_children()
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, child)| match child.get_rect() {
+                Some(rect) => rect.contains(position[0], position[1]),
+                None => false,
+            });
+        match child {
+            Some((index, child)) => {
+                path.push(index);
+                widget = child.as_ref();
+            }
+            None => return path,
+        }
+    }
+}
+
+// delivers an event along a path of child indices, stopping at the first widget handling it.
+// a path that no longer exists ends at the deepest widget still found.
+pub fn dispatch(widget: &mut dyn Widget, path: &[usize], event: &Event) -> EventResult {
+    let child = match path.split_first() {
+        Some((index, _)) if *index < widget.get_children().len() => Some(*index),
+        _ => None,
+    };
+    let Some(index) = child else {
+        return widget.event(event, EventPhase::Target);
+    };
+
+    if widget.event(event, EventPhase::Capture) == EventResult::Handled {
+        return EventResult::Handled;
+    }
+    let translated;
+    let child_event = match widget.get_child_offset() {
+        [0.0, 0.0] => event,
+        offset => {
+            translated = event.translate(offset);
+            &translated
+        }
+    };
+    let child = widget.get_children_mut()[index].as_mut();
+    if dispatch(child, &path[1..], child_event) == EventResult::Handled {
+        return EventResult::Handled;
+    }
+    return widget.event(event, EventPhase::Bubble);
+}
+
+// turns window events into widget events and tracks the state they need. while a mouse button
+// is held, mouse events go to the widget that was pressed, so that drags can leave it.
+#[derive(Debug, Default)]
+pub struct EventDispatcher {
+    cursor: Option<[f32; 2]>,
+    modifiers: ModifiersState,
+    buttons: Vec<MouseButton>,
+    capture: Option<Vec<usize>>,
+}
+
+impl EventDispatcher {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn get_cursor(&self) -> Option<[f32; 2]> {
+        return self.cursor;
+    }
+
+    pub fn get_modifiers(&self) -> ModifiersState {
+        return self.modifiers;
+    }
+
+    pub fn window_event(
+        &mut self,
+        root: &mut dyn Widget,
+        focus: &mut FocusManager,
+        event: &WindowEvent,
+        scale_factor: f64,
+    ) -> EventResult {
+        let scale = |x: f64, y: f64| [(x / scale_factor) as f32, (y / scale_factor) as f32];
+        let event = match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                return EventResult::Ignored;
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                return EventResult::Ignored;
+            }
+            // the releases of buttons held while the window lost focus never arrive.
+            WindowEvent::Focused(false) => {
+                self.buttons.clear();
+                self.capture = None;
+                return EventResult::Ignored;
+            }
+            WindowEvent::CursorMoved { position, .. } => Event::MouseMove {
+                position: scale(position.x, position.y),
+            },
+            WindowEvent::MouseInput { state, button, .. } => {
+                let Some(position) = self.cursor else {
+                    return EventResult::Ignored;
+                };
+                match state {
+                    ElementState::Pressed => Event::MousePress {
+                        position,
+                        button: *button,
+                        modifiers: self.modifiers,
+                    },
+                    ElementState::Released => Event::MouseRelease {
+                        position,
+                        button: *button,
+                    },
+                }
+            }
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                let Some(position) = self.cursor else {
+                    return EventResult::Ignored;
+                };
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => ScrollDelta::Lines([*x, *y]),
+                    MouseScrollDelta::PixelDelta(delta) => {
+                        ScrollDelta::Pixels(scale(delta.x, delta.y))
+                    }
+                };
+                Event::MouseWheel {
+                    position,
+                    delta,
+                    phase: *phase,
+                }
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                if event.state == ElementState::Released {
+                    return self.dispatch(
+                        root,
+                        focus,
+                        Event::KeyRelease {
+                            key: event.logical_key.clone(),
+                            modifiers: self.modifiers,
+                        },
+                    );
+                }
+                let result = self.dispatch(
+                    root,
+                    focus,
+                    Event::KeyPress {
+                        key: event.logical_key.clone(),
+                        modifiers: self.modifiers,
+                        repeat: event.repeat,
+                    },
+                );
+                // control characters like enter or backspace only come as keys.
+                return match &event.text {
+                    Some(text)
+                        if result == EventResult::Ignored
+                            && !text.chars().all(char::is_control) =>
+                    {
+                        self.dispatch(root, focus, Event::Text(text.to_string()))
+                    }
+                    _ => result,
+                };
+            }
+            WindowEvent::Ime(Ime::Commit(text)) => Event::Text(text.clone()),
+            WindowEvent::Ime(Ime::Preedit(text, cursor)) => Event::Preedit {
+                text: text.clone(),
+                cursor: *cursor,
+            },
+            _ => return EventResult::Ignored,
+        };
+        return self.dispatch(root, focus, event);
+    }
+
+    // mouse events go to the widget under their position, keyboard and text events to the
+    // focused widget, or to the root when nothing is focused. a press focuses the widget
+    // under the cursor first, an unhandled tab moves the focus.
+    pub fn dispatch(
+        &mut self,
+        root: &mut dyn Widget,
+        focus: &mut FocusManager,
+        event: Event,
+    ) -> EventResult {
+        let path = match event.get_position() {
+            Some(position) => {
+                self.cursor = Some(position);
+                match &self.capture {
+                    Some(capture) => capture.clone(),
+                    None => hit_test(root, position),
+                }
+            }
+            None => focus.get_focus().unwrap_or_default().to_vec(),
+        };
+
+        match &event {
+            Event::MousePress { button, .. } => {
+                if self.buttons.is_empty() {
+                    focus.focus_on(root, &path);
+                    self.capture = Some(path.clone());
+                }
+                self.buttons.push(*button);
+            }
+            Event::MouseRelease { button, .. } => {
+                self.buttons.retain(|pressed| pressed != button);
+                if self.buttons.is_empty() {
+                    self.capture = None;
+                }
+            }
+            _ => {}
+        }
+
+        let result = dispatch(root, &path, &event);
+        if let Event::KeyPress {
+            key: Key::Named(NamedKey::Tab),
+            modifiers,
+            ..
+        } = &event
+        {
+            if result == EventResult::Ignored {
+                let moved = match modifiers.shift_key() {
+                    true => focus.focus_previous(root),
+                    false => focus.focus_next(root),
+                };
+                if moved {
+                    return EventResult::Handled;
+                }
+            }
+        }
+        return result;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use winit::event::WindowEvent;
+
+    use super::{dispatch, hit_test, Event, EventDispatcher, EventPhase, EventResult, MouseButton};
+    use crate::{
+        context::Context,
+        focus::FocusManager,
+        layout::{Alignment, Constraint, Rect},
+        widget::Widget,
+    };
+
+    type Log = Rc<RefCell<Vec<(&'static str, EventPhase)>>>;
+
+    struct Recorder {
+        name: &'static str,
+        rect: Rect,
+        handles: Option<EventPhase>,
+        focusable: bool,
+        log: Log,
+        children: Vec<Box<dyn Widget>>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, rect: Rect, log: &Log) -> Self {
+            return Self {
+                name,
+                rect,
+                handles: None,
+                focusable: false,
+                log: log.clone(),
+                children: vec![],
+            };
+        }
+    }
+
+    impl Widget for Recorder {
+        fn constraint(&self) -> Constraint {
+            return Constraint::percent(100, 1000000, 0);
+        }
+
+        fn alignment(&self) -> Alignment {
+            return Alignment::Start;
+        }
+
+        fn layout(&mut self, _rect: Option<Rect>) {}
+
+        fn render(&self, _context: &mut Context) {}
+
+        fn get_rect(&self) -> Option<Rect> {
+            return Some(self.rect);
+        }
+
+        fn get_children(&self) -> &[Box<dyn Widget>] {
+            return &self.children;
+        }
+
+        fn get_children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+            return &mut self.children;
+        }
+
+        fn is_focusable(&self) -> bool {
+            return self.focusable;
+        }
+
+        fn event(&mut self, _event: &Event, phase: EventPhase) -> EventResult {
+            self.log.borrow_mut().push((self.name, phase));
+            return match self.handles == Some(phase) {
+                true => EventResult::Handled,
+                false => EventResult::Ignored,
+            };
+        }
+    }
+
+    #[test]
+    fn event_phases() {
+        let log = Log::default();
+        let mut root = Recorder::new("root", Rect::new(0, 0, 100, 100), &log);
+        let mut left = Recorder::new("left", Rect::new(0, 0, 50, 100), &log);
+        let mut button = Recorder::new("button", Rect::new(10, 10, 20, 20), &log);
+        button.focusable = true;
+        left.children = vec![Box::new(button)];
+        root.children = vec![
+            Box::new(left),
+            Box::new(Recorder::new("right", Rect::new(50, 0, 50, 100), &log)),
+        ];
+
+        assert_eq!(hit_test(&root, [15.0, 15.0]), vec![0, 0]);
+        assert_eq!(hit_test(&root, [40.0, 50.0]), vec![0]);
+        assert_eq!(hit_test(&root, [75.0, 50.0]), vec![1]);
+        assert_eq!(hit_test(&root, [150.0, 50.0]), Vec::<usize>::new());
+
+        let event = Event::MouseMove {
+            position: [15.0, 15.0],
+        };
+        assert_eq!(dispatch(&mut root, &[0, 0], &event), EventResult::Ignored);
+        assert_eq!(
+            log.take(),
+            vec![
+                ("root", EventPhase::Capture),
+                ("left", EventPhase::Capture),
+                ("button", EventPhase::Target),
+                ("left", EventPhase::Bubble),
+                ("root", EventPhase::Bubble),
+            ]
+        );
+
+        // a handled event stops travelling.
+        root.handles = Some(EventPhase::Capture);
+        assert_eq!(dispatch(&mut root, &[0, 0], &event), EventResult::Handled);
+        assert_eq!(log.take(), vec![("root", EventPhase::Capture)]);
+        root.handles = None;
+
+        // mouse events stay with the pressed widget until all buttons are released.
+        let mut dispatcher = EventDispatcher::new();
+        let mut focus = FocusManager::new();
+        let click = |dispatcher: &mut EventDispatcher,
+                     root: &mut Recorder,
+                     focus: &mut FocusManager,
+                     start,
+                     end| {
+            dispatcher.dispatch(
+                root,
+                focus,
+                Event::MousePress {
+                    position: start,
+                    button: MouseButton::Left,
+                    modifiers: Default::default(),
+                },
+            );
+            dispatcher.dispatch(root, focus, Event::MouseMove { position: end });
+            dispatcher.dispatch(
+                root,
+                focus,
+                Event::MouseRelease {
+                    position: end,
+                    button: MouseButton::Left,
+                },
+            );
+        };
+        let targets = || {
+            return log
+                .take()
+                .into_iter()
+                .filter(|(_, phase)| *phase == EventPhase::Target)
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+        };
+
+        // text goes to the root while nothing is focused, then to the clicked widget.
+        click(
+            &mut dispatcher,
+            &mut root,
+            &mut focus,
+            [75.0, 50.0],
+            [15.0, 15.0],
+        );
+        dispatcher.dispatch(&mut root, &mut focus, Event::Text("a".into()));
+        assert_eq!(targets(), vec!["right", "right", "right", "root"]);
+        click(
+            &mut dispatcher,
+            &mut root,
+            &mut focus,
+            [15.0, 15.0],
+            [15.0, 15.0],
+        );
+        assert_eq!(focus.get_focus(), Some(&[0, 0][..]));
+        dispatcher.dispatch(&mut root, &mut focus, Event::Text("a".into()));
+        assert_eq!(targets(), vec!["button", "button", "button", "button"]);
+    }
+
+    #[test]
+    fn focus_lost() {
+        let log = Log::default();
+        let mut root = Recorder::new("root", Rect::new(0, 0, 100, 100), &log);
+        let mut button = Recorder::new("button", Rect::new(10, 10, 20, 20), &log);
+        button.focusable = true;
+        root.children = vec![
+            Box::new(button),
+            Box::new(Recorder::new("right", Rect::new(50, 0, 50, 100), &log)),
+        ];
+        let mut dispatcher = EventDispatcher::new();
+        let mut focus = FocusManager::new();
+        let press = |dispatcher: &mut EventDispatcher,
+                     root: &mut Recorder,
+                     focus: &mut FocusManager,
+                     position| {
+            dispatcher.dispatch(
+                root,
+                focus,
+                Event::MousePress {
+                    position,
+                    button: MouseButton::Left,
+                    modifiers: Default::default(),
+                },
+            );
+        };
+
+        // the release of a press is lost with the focus, the next press is not captured.
+        press(&mut dispatcher, &mut root, &mut focus, [75.0, 50.0]);
+        let result =
+            dispatcher.window_event(&mut root, &mut focus, &WindowEvent::Focused(false), 1.0);
+        assert_eq!(result, EventResult::Ignored);
+        log.take();
+        press(&mut dispatcher, &mut root, &mut focus, [15.0, 15.0]);
+        assert_eq!(focus.get_focus(), Some(&[0][..]));
+        assert!(log.take().contains(&("button", EventPhase::Target)));
+    }
+}
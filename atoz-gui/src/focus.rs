@@ -0,0 +1,271 @@
+use crate::widget::{Focus, Widget};
+
+// keyboard focus of a widget tree, as the path of child indices to the focused widget.
+// widgets on the path are told when they gain or lose the focus, see Widget::focus_changed.
+#[derive(Debug, Default)]
+pub struct FocusManager {
+    path: Option<Vec<usize>>,
+}
+
+impl FocusManager {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn get_focus(&self) -> Option<&[usize]> {
+        return self.path.as_deref();
+    }
+
+    // focuses the widget at path, or nothing with None. returns whether the focus changed,
+    // a path to a widget that does not exist or is not focusable is ignored.
+    pub fn set_focus(&mut self, root: &mut dyn Widget, path: Option<Vec<usize>>) -> bool {
+        if let Some(path) = &path {
+            match get_widget(root, path) {
+                Some(widget) if widget.is_focusable() => {}
+                _ => return false,
+            }
+        }
+        if path == self.path {
+            return false;
+        }
+
+        let old = self.path.take();
+        let prefixes = old
+            .iter()
+            .chain(path.iter())
+            .flat_map(|path| (0..=path.len()).map(|len| &path[..len]))
+            .collect::<Vec<_>>();
+        for (index, prefix) in prefixes.iter().enumerate() {
+            // each widget once, the old and the new path share their ancestors.
+            if prefixes[..index].contains(prefix) {
+                continue;
+            }
+            let focus = get_state(path.as_deref(), prefix);
+            if get_state(old.as_deref(), prefix) != focus {
+                if let Some(widget) = get_widget_mut(root, prefix) {
+                    widget.focus_changed(focus);
+                }
+            }
+        }
+        self.path = path;
+        return true;
+    }
+
+    // tab order is the tree order, parents before their children. it wraps around.
+    pub fn focus_next(&mut self, root: &mut dyn Widget) -> bool {
+        let order = get_focus_order(root);
+        let next = match self.get_focus() {
+            Some(path) => match order.iter().position(|focusable| focusable == path) {
+                Some(index) => order.get((index + 1) % order.len()),
+                None => order.iter().find(|focusable| focusable.as_slice() > path),
+            },
+            None => order.first(),
+        };
+        return match next.or(order.first()).cloned() {
+            Some(next) => self.set_focus(root, Some(next)),
+            None => false,
+        };
+    }
+
+    pub fn focus_previous(&mut self, root: &mut dyn Widget) -> bool {
+        let order = get_focus_order(root);
+        let previous = match self.get_focus() {
+            Some(path) => match order.iter().position(|focusable| focusable == path) {
+                Some(index) => order.get((index + order.len() - 1) % order.len()),
+                None => order
+                    .iter()
+                    .rev()
+                    .find(|focusable| focusable.as_slice() < path),
+            },
+            None => order.last(),
+        };
+        return match previous.or(order.last()).cloned() {
+            Some(previous) => self.set_focus(root, Some(previous)),
+            None => false,
+        };
+    }
+
+    // focuses the deepest focusable widget on a path, e.g. the one under a click.
+    // nothing is focused when there is none.
+    pub fn focus_on(&mut self, root: &mut dyn Widget, path: &[usize]) -> bool {
+        let focusable = (0..=path.len())
+            .rev()
+            .map(|len| &path[..len])
+            .find(|prefix| match get_widget(root, prefix) {
+                Some(widget) => widget.is_focusable(),
+                None => false,
+            });
+        return self.set_focus(root, focusable.map(|prefix| prefix.to_vec()));
+    }
+}
+
+fn get_state(focus: Option<&[usize]>, path: &[usize]) -> Focus {
+    return match focus {
+        Some(focus) if focus == path => Focus::Focused,
+        Some(focus) if focus.starts_with(path) => Focus::ChildFocused,
+        _ => Focus::None,
+    };
+}
+
+pub fn get_widget<'a>(root: &'a dyn Widget, path: &[usize]) -> Option<&'a dyn Widget> {
+    let mut widget = root;
+    for index in path {
+        widget = widget.get_children().get(*index)?.as_ref();
+    }
+    return Some(widget);
+}
+
+pub fn get_widget_mut<'a>(root: &'a mut dyn Widget, path: &[usize]) -> Option<&'a mut dyn Widget> {
+    let mut widget = root;
+    for index in path {
+        widget = widget.get_children_mut().get_mut(*index)?.as_mut();
+    }
+    return Some(widget);
+}
+
+// paths of the shown focusable widgets in tree order.
+fn get_focus_order(root: &dyn Widget) -> Vec<Vec<usize>> {
+    fn visit(widget: &dyn Widget, path: &mut Vec<usize>, order: &mut Vec<Vec<usize>>) {
+        if widget.is_focusable() {
+            order.push(path.clone());
+        }
+        for (index, child) in widget.get_children().iter().enumerate() {
+            if child.get_rect().is_none() {
+                continue;
+            }
+            path.push(index);
+            visit(child.as_ref(), path, order);
+            path.pop();
+        }
+    }
+
+    let mut order = vec![];
+    visit(root, &mut vec![], &mut order);
+    return order;
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::FocusManager;
+    use crate::{
+        context::Context,
+        layout::{Alignment, Constraint, Rect},
+        widget::{Focus, Widget},
+    };
+
+    type Log = Rc<RefCell<Vec<(&'static str, Focus)>>>;
+
+    struct Focusable {
+        name: &'static str,
+        focusable: bool,
+        log: Log,
+        children: Vec<Box<dyn Widget>>,
+    }
+
+    impl Focusable {
+        fn new(name: &'static str, focusable: bool, log: &Log) -> Self {
+            return Self {
+                name,
+                focusable,
+                log: log.clone(),
+                children: vec![],
+            };
+        }
+    }
+
+    impl Widget for Focusable {
+        fn constraint(&self) -> Constraint {
+            return Constraint::percent(100, 1000000, 0);
+        }
+
+        fn alignment(&self) -> Alignment {
+            return Alignment::Start;
+        }
+
+        fn layout(&mut self, _rect: Option<Rect>) {}
+
+        fn render(&self, _context: &mut Context) {}
+
+        fn get_rect(&self) -> Option<Rect> {
+            return Some(Rect::default());
+        }
+
+        fn get_children(&self) -> &[Box<dyn Widget>] {
+            return &self.children;
+        }
+
+        fn get_children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+            return &mut self.children;
+        }
+
+        fn is_focusable(&self) -> bool {
+            return self.focusable;
+        }
+
+        fn focus_changed(&mut self, focus: Focus) {
+            self.log.borrow_mut().push((self.name, focus));
+        }
+    }
+
+    #[test]
+    fn focus_traversal() {
+        let log = Log::default();
+        let mut root = Focusable::new("root", false, &log);
+        let mut pane = Focusable::new("pane", true, &log);
+        pane.children = vec![
+            Box::new(Focusable::new("input", true, &log)),
+            Box::new(Focusable::new("label", false, &log)),
+        ];
+        root.children = vec![
+            Box::new(pane),
+            Box::new(Focusable::new("terminal", true, &log)),
+        ];
+
+        let mut focus = FocusManager::new();
+        assert!(focus.focus_next(&mut root));
+        assert_eq!(focus.get_focus(), Some(&[0][..]));
+        assert_eq!(
+            log.take(),
+            vec![("root", Focus::ChildFocused), ("pane", Focus::Focused)]
+        );
+
+        // moving into a child keeps the ancestors focused.
+        assert!(focus.focus_next(&mut root));
+        assert_eq!(focus.get_focus(), Some(&[0, 0][..]));
+        assert_eq!(
+            log.take(),
+            vec![("pane", Focus::ChildFocused), ("input", Focus::Focused)]
+        );
+
+        assert!(focus.focus_next(&mut root));
+        assert_eq!(focus.get_focus(), Some(&[1][..]));
+        assert_eq!(
+            log.take(),
+            vec![
+                ("pane", Focus::None),
+                ("input", Focus::None),
+                ("terminal", Focus::Focused)
+            ]
+        );
+
+        // the order wraps around in both directions.
+        assert!(focus.focus_next(&mut root));
+        assert_eq!(focus.get_focus(), Some(&[0][..]));
+        assert!(focus.focus_previous(&mut root));
+        assert_eq!(focus.get_focus(), Some(&[1][..]));
+
+        // clicks focus the deepest focusable widget, requests for others are ignored.
+        assert!(focus.focus_on(&mut root, &[0, 1]));
+        assert_eq!(focus.get_focus(), Some(&[0][..]));
+        assert!(!focus.set_focus(&mut root, Some(vec![0, 1])));
+        assert!(!focus.set_focus(&mut root, Some(vec![3])));
+        log.take();
+        assert!(focus.set_focus(&mut root, None));
+        assert_eq!(
+            log.take(),
+            vec![("root", Focus::None), ("pane", Focus::None)]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::{collections::HashMap, mem, ops::Range};
+
+use atoz_renderer::{
+    clip::{Clip, ClipStack},
+    layer::Layer,
+    transform::Transform,
+};
+
+use crate::layout::Rect;
+
+// the layers a widget and its children drew, with the clip and transform of its parents.
+#[derive(Debug, Clone)]
+struct CachedWidget {
+    path: Vec<usize>,
+    layers: Range<usize>,
+    clip: Clip,
+    transform: Transform,
+}
+
+// layers of a frame, split by widget. every widget draws into layers of its own, bounded by
+// its rect, which are kept for the next frame and reused while nothing inside of it is damaged.
+// a clip or transform change starts a new layer, so that it applies to the whole layer and
+// the layers of the children inherit it.
+#[derive(Debug)]
+pub struct LayerCache {
+    layers: Vec<Layer>,
+    widgets: Vec<CachedWidget>,
+    // the layers and widgets of the last frame, a layer is taken once reused.
+    cached_layers: Vec<Option<Layer>>,
+    cached_widgets: Vec<CachedWidget>,
+    cached_paths: HashMap<Vec<usize>, usize>,
+    // child indices from the root to the widget being rendered.
+    path: Vec<usize>,
+    // widget entry of every widget on the path, None when its layers were reused.
+    rendering: Vec<Option<usize>>,
+    bounds: Vec<Clip>,
+    clips: ClipStack,
+    transforms: Vec<Transform>,
+    // whether draws can go on in the last layer.
+    continued: bool,
+}
+
+impl Default for LayerCache {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl LayerCache {
+    pub fn new() -> Self {
+        return Self {
+            layers: vec![],
+            widgets: vec![],
+            cached_layers: vec![],
+            cached_widgets: vec![],
+            cached_paths: HashMap::new(),
+            path: vec![],
+            rendering: vec![],
+            bounds: vec![],
+            clips: ClipStack::new(Clip::unbounded()),
+            transforms: vec![Transform::identity()],
+            continued: false,
+        };
+    }
+
+    // keeps the layers of the last frame for begin_widget and starts an empty frame.
+    pub fn begin_frame(&mut self) {
+        self.cached_layers = mem::take(&mut self.layers).into_iter().map(Some).collect();
+        self.cached_widgets = mem::take(&mut self.widgets);
+        self.cached_paths = self
+            .cached_widgets
+            .iter()
+            .enumerate()
+            .map(|(index, widget)| (widget.path.clone(), index))
+            .collect();
+        self.reset();
+    }
+
+    // drops the layers and everything cached.
+    pub fn clear(&mut self) {
+        self.layers.clear();
+        self.widgets.clear();
+        self.cached_layers.clear();
+        self.cached_widgets.clear();
+        self.cached_paths.clear();
+        self.reset();
+    }
+
+    // starts the child at index of the widget being rendered, rect in its logical pixels and
+    // damage in screen pixels. returns false when the layers of the last frame were reused,
+    // then the child is not rendered. every call is closed by end_widget.
+    pub fn begin_widget(&mut self, index: usize, rect: Option<Rect>, damage: Option<Clip>) -> bool {
+        self.path.push(index);
+        self.continued = false;
+        let clip = self.clips.get_current();
+        let transform = *self.transforms.last().unwrap();
+        let bounds = match rect {
+            Some(rect) => Clip::new(
+                [rect.left() as _, rect.top() as _],
+                [rect.width() as _, rect.height() as _],
+            )
+            .transform(&transform)
+            .intersect(&clip),
+            None => Clip::new([0.0, 0.0], [0.0, 0.0]),
+        };
+
+        // hidden widgets are drawn again, their cached layers could belong to another widget
+        // which had the same path before the tree changed.
+        let damaged = match damage {
+            Some(damage) => !damage.intersect(&bounds).is_empty(),
+            None => false,
+        };
+        if !damaged && !bounds.is_empty() && self.reuse(clip, transform) {
+            self.rendering.push(None);
+            return false;
+        }
+
+        self.rendering.push(Some(self.widgets.len()));
+        self.widgets.push(CachedWidget {
+            path: self.path.clone(),
+            layers: self.layers.len()..self.layers.len(),
+            clip,
+            transform,
+        });
+        self.bounds.push(bounds);
+        return true;
+    }
+
+    pub fn end_widget(&mut self) {
+        if let Some(Some(index)) = self.rendering.pop() {
+            self.widgets[index].layers.end = self.layers.len();
+            self.bounds.pop();
+        }
+        self.path.pop();
+        self.continued = false;
+    }
+
+    // the layer the widget being rendered draws into.
+    pub fn get_layer(&mut self) -> &mut Layer {
+        if !self.continued {
+            let mut layer = Layer::default()
+                .set_clip(self.clips.get_current())
+                .set_transform(*self.transforms.last().unwrap());
+            if let Some(bounds) = self.bounds.last() {
+                layer = layer.set_bounds(*bounds);
+            }
+            self.layers.push(layer);
+            self.continued = true;
+        }
+        return self.layers.last_mut().unwrap();
+    }
+
+    pub fn push_layer(&mut self, layer: Layer) {
+        self.layers.push(layer);
+        self.continued = false;
+    }
+
+    // clip in the space of the current transform, for the rest of the widget and its children.
+    pub fn push_clip(&mut self, clip: Clip) {
+        self.clips
+            .push(clip.transform(self.transforms.last().unwrap()));
+        self.continued = false;
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+        self.continued = false;
+    }
+
+    pub fn push_transform(&mut self, transform: Transform) {
+        let transform = self.transforms.last().unwrap().multiply(&transform);
+        self.transforms.push(transform);
+        self.continued = false;
+    }
+
+    pub fn pop_transform(&mut self) {
+        if self.transforms.len() > 1 {
+            self.transforms.pop();
+        }
+        self.continued = false;
+    }
+
+    pub fn get_layers(&self) -> &[Layer] {
+        return &self.layers;
+    }
+
+    // moves the layers the widget on the path drew last frame into this frame, with the
+    // entries of its children. they are only valid under the same clip and transform.
+    fn reuse(&mut self, clip: Clip, transform: Transform) -> bool {
+        let Some(&index) = self.cached_paths.get(&self.path) else {
+            return false;
+        };
+        let cached = &self.cached_widgets[index];
+        let range = cached.layers.clone();
+        if cached.clip != clip
+            || cached.transform != transform
+            || range
+                .clone()
+                .any(|layer| self.cached_layers[layer].is_none())
+        {
+            return false;
+        }
+
+        let start = self.layers.len();
+        let mut end = index + 1;
+        while end < self.cached_widgets.len()
+            && self.cached_widgets[end].path.starts_with(&self.path)
+        {
+            end += 1;
+        }
+        for widget in &self.cached_widgets[index..end] {
+            let mut widget = widget.clone();
+            widget.layers =
+                widget.layers.start - range.start + start..widget.layers.end - range.start + start;
+            self.widgets.push(widget);
+        }
+        let cached_layers = &mut self.cached_layers;
+        self.layers
+            .extend(range.map(|layer| cached_layers[layer].take().unwrap()));
+        return true;
+    }
+
+    fn reset(&mut self) {
+        self.path.clear();
+        self.rendering.clear();
+        self.bounds.clear();
+        self.clips = ClipStack::new(Clip::unbounded());
+        self.transforms = vec![Transform::identity()];
+        self.continued = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use atoz_renderer::{clip::Clip, pipeline::rect::RectInstance, transform::Transform};
+
+    use super::LayerCache;
+    use crate::layout::Rect;
+
+    // a widget at index with two children, which draw a rect each.
+    fn render(cache: &mut LayerCache, damage: Option<Clip>, offset: f32) -> Vec<bool> {
+        let mut rendered = vec![];
+        cache.begin_frame();
+        rendered.push(cache.begin_widget(0, Some(Rect::new(0, 0, 100, 100)), damage));
+        if rendered[0] {
+            cache.push_clip(Clip::new([0.0, 0.0], [100.0, 100.0]));
+            cache.push_transform(Transform::translate(0.0, offset));
+            for index in 0..2 {
+                let rect = Rect::new(index * 50, 0, 50, 50);
+                rendered.push(cache.begin_widget(index, Some(rect), damage));
+                if *rendered.last().unwrap() {
+                    cache.get_layer().push_rect(RectInstance::fill(
+                        [rect.left() as _, 0.0],
+                        [50.0, 50.0],
+                        [0.0; 4],
+                        [1.0; 4],
+                    ));
+                }
+                cache.end_widget();
+            }
+            cache.pop_transform();
+            cache.pop_clip();
+        }
+        cache.end_widget();
+        return rendered;
+    }
+
+    #[test]
+    fn layer_reuse() {
+        let mut cache = LayerCache::new();
+        let all = Some(Clip::new([0.0, 0.0], [100.0, 100.0]));
+        assert_eq!(render(&mut cache, all, 0.0), [true, true, true]);
+        assert_eq!(cache.get_layers().len(), 2);
+        let layer = &cache.get_layers()[1];
+        assert_eq!(layer.bounds, Some(Clip::new([50.0, 0.0], [50.0, 50.0])));
+        assert_eq!(layer.clip, Some(Clip::new([0.0, 0.0], [100.0, 100.0])));
+
+        // only the child in the damage is drawn again, the other one keeps its layer.
+        let damage = Some(Clip::new([60.0, 10.0], [10.0, 10.0]));
+        assert_eq!(render(&mut cache, damage, 0.0), [true, false, true]);
+        assert_eq!(cache.get_layers().len(), 2);
+        assert_eq!(
+            cache.get_layers()[0].bounds,
+            Some(Clip::new([0.0, 0.0], [50.0, 50.0]))
+        );
+
+        // without damage the whole tree is reused, with its children for the next frame.
+        assert_eq!(render(&mut cache, None, 0.0), [false]);
+        assert_eq!(cache.get_layers().len(), 2);
+        assert_eq!(render(&mut cache, damage, 0.0), [true, false, true]);
+
+        // layers drawn under another transform are not reused.
+        assert_eq!(render(&mut cache, damage, 10.0), [true, true, true]);
+        assert_eq!(
+            cache.get_layers()[0].bounds,
+            Some(Clip::new([0.0, 10.0], [50.0, 50.0]))
+        );
+    }
+}
@@ -2,9 +2,10 @@
 // ## description
 //     simple, orignal, calculation method to solve UI layout problem
 // ## calc steps
-//     1. subtract all sizes of constant areas from parent size.
+//     1. subtract all sizes of constant and content sized areas from parent size.
 //     2. subtract all sizes of areas constrained by percentage from remained area.
-//     3. give permissions for rendering to widgets.
+//     3. split the rest between the filling areas by their weights.
+//     4. give permissions for rendering to widgets.
 // ## if the parent size is smaller than self.min
 //     return 0;
 
@@ -50,6 +51,14 @@ impl Rect {
         return self.height;
     }
 
+    // whether a point in logical pixels is inside, the right and bottom edges excluded.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        return x >= self.left as f32
+            && x < self.right() as f32
+            && y >= self.top as f32
+            && y < self.bottom() as f32;
+    }
+
     pub fn subtract_padding(&self, padding: Padding) -> Rect {
         let mut rect = self.clone();
 
@@ -94,6 +103,10 @@ impl Default for Rect {
 pub enum Unit {
     Pixel(usize),
     Percent(usize),
+    // a share of the space left by the other units, by weight.
+    Fill(usize),
+    // the intrinsic size of the widget, see Widget::get_intrinsic_size.
+    Auto,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -104,6 +117,7 @@ pub struct Constraint {
 }
 
 impl Constraint {
+    // size in logical pixels.
     pub fn pixel(size: usize, min: usize) -> Self {
         return Self {
             size: Unit::Pixel(size),
@@ -120,6 +134,28 @@ impl Constraint {
         };
     }
 
+    pub fn fill(weight: usize, max: usize, min: usize) -> Self {
+        return Self {
+            size: Unit::Fill(weight),
+            max,
+            min,
+        };
+    }
+
+    pub fn auto(max: usize, min: usize) -> Self {
+        return Self {
+            size: Unit::Auto,
+            max,
+            min,
+        };
+    }
+
+    pub fn get_unit(&self) -> Unit {
+        return self.size;
+    }
+
+    // a lone filling area takes the whole parent, content sized areas their minimum,
+    // see calculate_auto.
     pub fn calculate(&self, parent: usize, space: usize) -> usize {
         match self.size {
             Unit::Pixel(pixel) => {
@@ -131,25 +167,36 @@ impl Constraint {
                     return 0;
                 }
             }
-            Unit::Percent(percent) => {
-                let mut size = parent * percent / 100;
-                if size > self.max {
-                    size = self.max;
-                } else if size < self.min {
-                    size = self.min;
-                }
+            Unit::Percent(percent) => return self.fit(parent * percent / 100, space),
+            Unit::Fill(_) => return self.fit(parent, space),
+            Unit::Auto => return self.fit(self.min, space),
+        };
+    }
 
-                if size <= space {
-                    return size;
-                } else if self.min <= space {
-                    return space;
-                } else {
-                    return 0;
-                }
-            }
+    // size of a content sized area from the intrinsic size of its widget.
+    pub fn calculate_auto(&self, intrinsic: usize, space: usize) -> usize {
+        return self.fit(intrinsic, space);
+    }
+
+    // the size an area asks for without a parent, used to measure containers.
+    pub fn calculate_natural(&self, intrinsic: usize) -> usize {
+        return match self.size {
+            Unit::Auto => self.calculate_auto(intrinsic, usize::MAX),
+            _ => self.calculate(0, usize::MAX),
         };
     }
 
+    fn fit(&self, size: usize, space: usize) -> usize {
+        let size = size.min(self.max).max(self.min);
+        if size <= space {
+            return size;
+        } else if self.min <= space {
+            return space;
+        } else {
+            return 0;
+        }
+    }
+
     pub fn has_pixel(&self) -> bool {
         return match self.size {
             Unit::Pixel(_) => true,
@@ -171,6 +218,87 @@ pub enum Alignment {
     End,
 }
 
+// how children are sized and placed across the direction of their container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossAlignment {
+    // the children take the whole cross size.
+    Stretch,
+    // the children take their intrinsic cross size, placed at the start, center or end.
+    // children without an intrinsic size are stretched.
+    Start,
+    Center,
+    End,
+}
+
+// sizes along a container of children with their constraints and intrinsic sizes.
+// pixel and content sized children are sized first in order, percent children share the
+// space left by them, and filling children split the rest by weight. a gap is kept
+// between every two children.
+pub fn distribute(children: &[(Constraint, usize)], length: usize, gap: usize) -> Vec<usize> {
+    let mut sizes = vec![0; children.len()];
+    let mut space = length.saturating_sub(gap * children.len().saturating_sub(1));
+
+    for (index, (constraint, intrinsic)) in children.iter().enumerate() {
+        sizes[index] = match constraint.get_unit() {
+            Unit::Pixel(_) => constraint.calculate(length, space),
+            Unit::Auto => constraint.calculate_auto(*intrinsic, space),
+            _ => continue,
+        };
+        space -= sizes[index];
+    }
+
+    let percent_parent = space;
+    for (index, (constraint, _)) in children.iter().enumerate() {
+        if constraint.has_percent() {
+            sizes[index] = constraint.calculate(percent_parent, space);
+            space -= sizes[index];
+        }
+    }
+
+    // each share is rounded so that the shares add up to the space. a child limited by its
+    // max keeps it and the split is repeated, so the others share what it leaves.
+    let mut filling = children
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (constraint, _))| match constraint.get_unit() {
+            Unit::Fill(weight) if weight > 0 => Some((index, weight)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    while !filling.is_empty() {
+        let fill_space = space;
+        let total = filling.iter().map(|(_, weight)| weight).sum::<usize>();
+        let mut weights = 0;
+        let shares = filling
+            .iter()
+            .map(|(index, weight)| {
+                let share = fill_space * (weights + weight) / total - fill_space * weights / total;
+                weights += weight;
+                return (*index, share);
+            })
+            .collect::<Vec<_>>();
+
+        let limited = shares
+            .iter()
+            .filter(|(index, share)| *share > children[*index].0.max)
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+        if limited.is_empty() {
+            for (index, share) in shares {
+                sizes[index] = children[index].0.calculate(share, space);
+                space -= sizes[index];
+            }
+            break;
+        }
+        for index in limited {
+            sizes[index] = children[index].0.calculate(children[index].0.max, space);
+            space -= sizes[index];
+            filling.retain(|(filled, _)| *filled != index);
+        }
+    }
+    return sizes;
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Padding {
     pub left: Constraint,
@@ -179,6 +307,16 @@ pub struct Padding {
     pub bottom: Constraint,
 }
 
+impl Padding {
+    // width and height taken by the pixel sides, percent sides count with their minimum.
+    pub fn calculate_natural(&self) -> [usize; 2] {
+        return [
+            self.left.calculate_natural(0) + self.right.calculate_natural(0),
+            self.top.calculate_natural(0) + self.bottom.calculate_natural(0),
+        ];
+    }
+}
+
 impl Default for Padding {
     fn default() -> Self {
         return Self {
@@ -192,7 +330,7 @@ impl Default for Padding {
 
 #[cfg(test)]
 mod test {
-    use super::{Constraint, Padding, Rect};
+    use super::{distribute, Constraint, Padding, Rect};
 
     #[test]
     fn rect_reshape() {
@@ -204,4 +342,25 @@ mod test {
         });
         println!("{:?}", rect);
     }
+
+    #[test]
+    fn distribute_sizes() {
+        // a filling editor between a fixed sidebar and a content sized status bar.
+        let children = [
+            (Constraint::pixel(200, 0), 0),
+            (Constraint::fill(1, usize::MAX, 0), 0),
+            (Constraint::auto(usize::MAX, 0), 24),
+        ];
+        assert_eq!(distribute(&children, 1000, 0), vec![200, 776, 24]);
+        assert_eq!(distribute(&children, 1000, 10), vec![200, 756, 24]);
+
+        // weights split the rest after the percent areas, limited by their max.
+        let children = [
+            (Constraint::percent(50, usize::MAX, 0), 0),
+            (Constraint::fill(1, usize::MAX, 0), 0),
+            (Constraint::fill(2, 100, 0), 0),
+        ];
+        assert_eq!(distribute(&children, 301, 0), vec![150, 51, 100]);
+        assert_eq!(distribute(&children, 600, 0), vec![300, 200, 100]);
+    }
 }
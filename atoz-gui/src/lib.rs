@@ -1,32 +1,239 @@
+pub mod clipboard;
 pub mod context;
+pub mod event;
+pub mod focus;
+pub mod layers;
 pub mod layout;
+pub mod scheduler;
+pub mod snapshot;
 pub mod widget;
 pub mod window;
 
 #[cfg(test)]
 mod tests {
-    use std::{
-        process::exit,
-        time::{Duration, Instant},
-    };
+    use std::time::Duration;
 
+    use atoz_renderer::{config::RendererConfig, pipeline::text::Font, snapshot::Snapshot};
     use winit::{
-        error::EventLoopError,
-        event::{Event, WindowEvent},
-        event_loop::{ControlFlow, EventLoopBuilder},
+        error::EventLoopError, event_loop::EventLoopBuilder,
         platform::wayland::EventLoopBuilderExtWayland,
     };
 
     use crate::{
         context::Context,
-        layout::{Alignment, Constraint},
+        layout::{Alignment, Constraint, Padding, Rect},
+        snapshot::{check_widget, render_widget},
         widget::{
             container::{Horizontal, HorizontalDecoration, Vertical, VerticalDecoration},
-            Color,
+            label::{Label, TextAlign},
+            profiler::ProfilerOverlay,
+            text_input::TextInput,
+            Color, Focus, Widget,
         },
         window::{Window, WindowDecoration},
     };
 
+    // headless context for the tests, which fail instead of passing without a graphics adapter.
+    fn headless_context(width: u32, height: u32) -> Context {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        return match rt.block_on(Context::headless(width, height)) {
+            Ok(context) => context,
+            Err(error) => panic!("{}, the test needs a graphics adapter", error),
+        };
+    }
+
+    #[test]
+    fn container_snapshot_test() {
+        let mut context = headless_context(200, 120);
+
+        let mut widget = Vertical::new(Constraint::percent(100, 1000000, 0), Alignment::Start)
+            .set_padding(Padding {
+                left: Constraint::pixel(10, 0),
+                right: Constraint::pixel(10, 0),
+                top: Constraint::pixel(10, 0),
+                bottom: Constraint::pixel(10, 0),
+            })
+            .set_decoration(
+                VerticalDecoration::default()
+                    .set_background_color(Color::new(30, 30, 30, 255))
+                    .set_border_radius(8.0, 8.0, 8.0, 8.0),
+            )
+            .set_children(vec![
+                Box::new(
+                    Horizontal::new(Constraint::pixel(24, 0), Alignment::Start).set_decoration(
+                        HorizontalDecoration::default()
+                            .set_background_color(Color::new(200, 60, 60, 255))
+                            .set_border_radius(4.0, 4.0, 4.0, 4.0),
+                    ),
+                ),
+                Box::new(
+                    Horizontal::new(Constraint::percent(50, 1000000, 0), Alignment::End)
+                        .set_decoration(
+                            HorizontalDecoration::default()
+                                .set_background_color(Color::new(60, 60, 200, 255)),
+                        ),
+                ),
+            ]);
+
+        let snapshot = Snapshot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src/test/snapshots"));
+        check_widget(&snapshot, "container", &mut context, &mut widget).unwrap();
+    }
+
+    #[test]
+    fn layer_cache_test() {
+        let mut context = headless_context(200, 120);
+        let mut widget = Horizontal::new(Constraint::percent(100, 1000000, 0), Alignment::Start)
+            .set_children(vec![
+                Box::new(
+                    Horizontal::new(Constraint::pixel(50, 0), Alignment::Start).set_decoration(
+                        HorizontalDecoration::default()
+                            .set_background_color(Color::new(200, 60, 60, 255)),
+                    ),
+                ),
+                Box::new(
+                    Horizontal::new(Constraint::pixel(50, 0), Alignment::Start).set_decoration(
+                        HorizontalDecoration::default()
+                            .set_background_color(Color::new(60, 60, 200, 255)),
+                    ),
+                ),
+            ]);
+        let full = render_widget(&mut context, &mut widget).unwrap();
+
+        // the first child keeps its layers while the damaged second one is drawn again. the
+        // whole frame is drawn, so that the reused layers are drawn as well.
+        context.get_renderer_mut().set_partial_redraw(false);
+        context.invalidate(Rect::new(60, 10, 10, 10));
+        context.begin_frame();
+        context.render_child(0, &widget);
+        context.render().unwrap();
+        assert_eq!(context.read_image().unwrap(), full);
+    }
+
+    #[test]
+    fn headless_window_test() {
+        let context = headless_context(64, 32);
+        let mut window = Window::new(
+            context,
+            Box::new(Horizontal::new(
+                Constraint::percent(100, 1000000, 0),
+                Alignment::Start,
+            )),
+            None,
+        );
+        // a headless window has no id, but renders.
+        assert_eq!(window.get_window_id(), None);
+        window.render().unwrap();
+    }
+
+    #[test]
+    fn scale_factor_test() {
+        let mut context = headless_context(200, 120);
+        context.set_scale_factor(2.0);
+        assert_eq!(context.get_size(), (200, 120));
+        assert_eq!(context.get_physical_size(), (400, 240));
+
+        let mut widget = Vertical::new(Constraint::percent(100, 1000000, 0), Alignment::Start)
+            .set_padding(Padding {
+                left: Constraint::pixel(10, 0),
+                right: Constraint::pixel(10, 0),
+                top: Constraint::pixel(10, 0),
+                bottom: Constraint::pixel(10, 0),
+            })
+            .set_children(vec![Box::new(
+                Horizontal::new(Constraint::pixel(24, 0), Alignment::Start).set_decoration(
+                    HorizontalDecoration::default()
+                        .set_background_color(Color::new(255, 0, 0, 255)),
+                ),
+            )]);
+
+        // the logical layout is drawn at twice the resolution.
+        let image = render_widget(&mut context, &mut widget).unwrap();
+        assert_eq!(image.dimensions(), (400, 240));
+        assert_eq!(image.get_pixel(20, 20).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(379, 67).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(19, 19).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(200, 68).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn profiler_overlay_test() {
+        let mut context = headless_context(240, 40);
+        let mut widget =
+            ProfilerOverlay::new(Constraint::percent(100, 1000000, 0), Alignment::Start)
+                .set_scale(Duration::from_nanos(1))
+                .set_background_color(Color::new(0, 0, 255, 255));
+
+        // the first render enables the profiler, the second one graphs the first frame.
+        render_widget(&mut context, &mut widget).unwrap();
+        assert!(context.get_renderer().get_profiler().is_enabled());
+        let image = render_widget(&mut context, &mut widget).unwrap();
+        let stats = context.get_renderer().get_profiler().get_last().unwrap();
+        assert!(stats.get_draw_calls() > 0);
+        assert_eq!(image.get_pixel(4, 20).0, [0, 0, 255, 255]);
+        assert_ne!(image.get_pixel(239, 38).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn label_test() {
+        let mut context = headless_context(120, 40);
+        let font = Font::from_bytes(include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../atoz-renderer/src/test/assets/DejaVuSansMono.ttf"
+        )))
+        .unwrap();
+        let mut widget = Label::new(
+            Constraint::percent(100, 1000000, 0),
+            Alignment::Start,
+            font,
+            "right",
+        )
+        .set_size(16.0)
+        .set_color(Color::new(255, 255, 255, 255))
+        .set_text_align(TextAlign::End)
+        .set_vertical_align(TextAlign::Center);
+
+        // the text is drawn at the right edge, centered vertically.
+        let image = render_widget(&mut context, &mut widget).unwrap();
+        let lit = |x_range: std::ops::Range<u32>, y_range: std::ops::Range<u32>| {
+            return x_range
+                .flat_map(|x| y_range.clone().map(move |y| (x, y)))
+                .any(|(x, y)| image.get_pixel(x, y).0[0] > 128);
+        };
+        assert!(lit(70..120, 10..30));
+        assert!(!lit(0..60, 0..40));
+        assert!(!lit(60..120, 0..8));
+    }
+
+    #[test]
+    fn text_input_test() {
+        let mut context = headless_context(120, 40);
+        let font = Font::from_bytes(include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../atoz-renderer/src/test/assets/DejaVuSansMono.ttf"
+        )))
+        .unwrap();
+        let mut widget = TextInput::new(
+            Constraint::percent(100, 1000000, 0),
+            Alignment::Start,
+            font,
+            "hello",
+        )
+        .set_size(16.0)
+        .set_selection_color(Color::new(0, 0, 255, 255));
+        widget.get_editor_mut().select_all();
+        widget.focus_changed(Focus::Focused);
+
+        // the selection is highlighted behind the text only.
+        let image = render_widget(&mut context, &mut widget).unwrap();
+        let blue = |x: u32, y: u32| {
+            let pixel = image.get_pixel(x, y).0;
+            return pixel[2] > 200 && pixel[0] < 50;
+        };
+        assert!((4..40).any(|x| blue(x, 20)));
+        assert!(!blue(100, 20));
+        assert!(!blue(20, 2));
+    }
+
     #[test]
     fn container_layout_test() -> Result<(), EventLoopError> {
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -36,8 +243,10 @@ mod tests {
             .with_any_thread(true)
             .build()
             .unwrap();
-        let context = rt.block_on(Context::init(&event_loop));
-        let mut window = Window::new(
+        let context = rt
+            .block_on(Context::init(&event_loop, RendererConfig::default()))
+            .unwrap();
+        let window = Window::new(
             context,
             Box::new(
                 Vertical::new(Constraint::percent(100, 1000000, 0), Alignment::Start)
@@ -177,38 +386,6 @@ mod tests {
             ),
         );
 
-        let frame_rate = Duration::from_secs_f64(1.0 / 60.0);
-        let mut ticker = Instant::now();
-
-        return event_loop.run(move |event, elwt| match event {
-            Event::WindowEvent { window_id, event } if window_id == window.get_window_id() => {
-                match event {
-                    WindowEvent::CloseRequested => {
-                        elwt.exit();
-                    }
-                    WindowEvent::Resized(physical_size) => {
-                        window.resize(physical_size);
-                    }
-                    WindowEvent::RedrawRequested => {
-                        let i = Instant::now();
-                        window.render();
-                        println!("{}", i.elapsed().as_millis());
-                    }
-                    _ => {}
-                }
-            }
-            Event::AboutToWait => {
-                if frame_rate <= ticker.elapsed() {
-                    window.render_request();
-                    println!("ticker : {}", ticker.elapsed().as_micros());
-                    ticker = Instant::now();
-                } else {
-                    elwt.set_control_flow(ControlFlow::WaitUntil(
-                        Instant::now().checked_sub(ticker.elapsed()).unwrap() + frame_rate,
-                    ));
-                }
-            }
-            _ => (),
-        });
+        return window.run(event_loop);
     }
 }
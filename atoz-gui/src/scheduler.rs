@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+// what the event loop should do before the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    // request a redraw now.
+    Redraw,
+    // something is pending, but the frame rate allows the next frame only at this instant.
+    WaitUntil(Instant),
+    // nothing to draw, sleep until the next event.
+    Wait,
+}
+
+// paces redraws to a maximum frame rate and only wakes the event loop when a frame was requested.
+#[derive(Debug)]
+pub struct FrameScheduler {
+    frame_time: Option<Duration>,
+    last_frame: Option<Instant>,
+    pending: bool,
+}
+
+impl FrameScheduler {
+    // frame_rate: maximum frames per second, None redraws as soon as a frame is requested.
+    pub fn new(frame_rate: Option<f64>) -> Self {
+        return Self {
+            frame_time: frame_rate
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            last_frame: None,
+            pending: true,
+        };
+    }
+
+    // something changed and has to be drawn by the next frame.
+    pub fn request_frame(&mut self) {
+        self.pending = true;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        return self.pending;
+    }
+
+    pub fn schedule(&self, now: Instant) -> Schedule {
+        if !self.pending {
+            return Schedule::Wait;
+        }
+        return match (self.frame_time, self.last_frame) {
+            (Some(frame_time), Some(last_frame)) if now < last_frame + frame_time => {
+                Schedule::WaitUntil(last_frame + frame_time)
+            }
+            _ => Schedule::Redraw,
+        };
+    }
+
+    pub fn frame_rendered(&mut self, now: Instant) {
+        self.pending = false;
+        self.last_frame = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{FrameScheduler, Schedule};
+
+    #[test]
+    fn frame_pacing() {
+        let start = Instant::now();
+        let mut scheduler = FrameScheduler::new(Some(50.0));
+
+        // the first frame is drawn right away.
+        assert_eq!(scheduler.schedule(start), Schedule::Redraw);
+        scheduler.frame_rendered(start);
+        assert_eq!(scheduler.schedule(start), Schedule::Wait);
+
+        // a request during the frame time waits for the next frame.
+        scheduler.request_frame();
+        assert_eq!(
+            scheduler.schedule(start + Duration::from_millis(5)),
+            Schedule::WaitUntil(start + Duration::from_millis(20))
+        );
+        assert_eq!(
+            scheduler.schedule(start + Duration::from_millis(20)),
+            Schedule::Redraw
+        );
+
+        let mut scheduler = FrameScheduler::new(None);
+        scheduler.frame_rendered(start);
+        scheduler.request_frame();
+        assert_eq!(scheduler.schedule(start), Schedule::Redraw);
+    }
+}
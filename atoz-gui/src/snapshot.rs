@@ -0,0 +1,27 @@
+use atoz_renderer::snapshot::{Snapshot, SnapshotError};
+use image::RgbaImage;
+
+use crate::{context::Context, layout::Rect, widget::Widget};
+
+// lays out and renders a widget tree into a headless context.
+pub fn render_widget(context: &mut Context, widget: &mut dyn Widget) -> Option<RgbaImage> {
+    let (width, height) = context.get_size();
+
+    context.invalidate_all();
+    context.clear_layers();
+    widget.layout(Some(Rect::new(0, 0, width as _, height as _)));
+    context.render_child(0, widget);
+    context.render().expect("snapshot : render failed");
+
+    return context.read_image();
+}
+
+pub fn check_widget(
+    snapshot: &Snapshot,
+    name: &str,
+    context: &mut Context,
+    widget: &mut dyn Widget,
+) -> Result<(), SnapshotError> {
+    let image = render_widget(context, widget).expect("snapshot : context must be headless");
+    return snapshot.check(name, &image);
+}
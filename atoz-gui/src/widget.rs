@@ -1,17 +1,73 @@
 use crate::{
     context::Context,
+    event::{Event, EventPhase, EventResult},
     layout::{Alignment, Constraint, Rect},
 };
 
 pub mod container;
+pub mod label;
+pub mod profiler;
+pub mod scroll;
+pub mod text_input;
 
 pub trait Widget {
     fn constraint(&self) -> Constraint;
     fn alignment(&self) -> Alignment;
     fn layout(&mut self, rect: Option<Rect>);
     fn render(&self, context: &mut Context);
+
+    // width and height of the content in logical pixels, the size of Unit::Auto constraints
+    // and of children not stretched across their container.
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        return [0, 0];
+    }
+
+    // raised when the intrinsic size changed, the window then lays the tree out again.
+    fn needs_layout(&self) -> bool {
+        return self.get_children().iter().any(|child| child.needs_layout());
+    }
+
+    // rects in logical pixels that changed since the last frame and have to be redrawn.
+    fn collect_damage(&mut self, _damage: &mut Vec<Rect>) {}
+
+    // the laid out rect events are hit tested against, None when the widget is not shown.
+    fn get_rect(&self) -> Option<Rect> {
+        return None;
+    }
+
+    fn get_children(&self) -> &[Box<dyn Widget>] {
+        return &[];
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        return &mut [];
+    }
+
+    // added to event positions for the children, whose content is drawn moved by its negation.
+    fn get_child_offset(&self) -> [f32; 2] {
+        return [0.0, 0.0];
+    }
+
+    // while true the window keeps drawing frames, collect_damage advances the animation.
+    fn is_animating(&self) -> bool {
+        return self.get_children().iter().any(|child| child.is_animating());
+    }
+
+    // called for each phase the event passes the widget in, see event::dispatch.
+    fn event(&mut self, _event: &Event, _phase: EventPhase) -> EventResult {
+        return EventResult::Ignored;
+    }
+
+    // focusable widgets get the keyboard events once focused by a click, tab or the window.
+    fn is_focusable(&self) -> bool {
+        return false;
+    }
+
+    // the focused widget gets Focused, its ancestors ChildFocused, and None once it moved away.
+    fn focus_changed(&mut self, _focus: Focus) {}
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Focus {
     Focused,
     ChildFocused,
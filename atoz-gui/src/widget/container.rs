@@ -1,12 +1,17 @@
-use atoz_renderer::{layer::Layer, pipeline::rect::RectInstance};
+use atoz_renderer::{clip::Clip, pipeline::rect::RectInstance};
 
 use super::{Alignment, Color, Constraint, Rect, Widget};
-use crate::{context::Context, layout::Padding};
+use crate::{
+    context::Context,
+    layout::{distribute, CrossAlignment, Padding},
+};
 
 pub struct Horizontal {
     constraint: Constraint,
     alignment: Alignment,
     padding: Padding,
+    gap: usize,
+    cross_alignment: CrossAlignment,
     decoration: HorizontalDecoration,
     rect: Option<Rect>,
     children: Vec<Box<dyn Widget>>,
@@ -18,6 +23,8 @@ impl Horizontal {
             constraint,
             alignment,
             padding: Padding::default(),
+            gap: 0,
+            cross_alignment: CrossAlignment::Stretch,
             decoration: HorizontalDecoration::default(),
             rect: Some(Rect::default()),
             children: vec![],
@@ -29,6 +36,17 @@ impl Horizontal {
         return self;
     }
 
+    // space between two children in logical pixels.
+    pub fn set_gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        return self;
+    }
+
+    pub fn set_cross_alignment(mut self, cross_alignment: CrossAlignment) -> Self {
+        self.cross_alignment = cross_alignment;
+        return self;
+    }
+
     pub fn set_children(mut self, widgets: Vec<Box<dyn Widget>>) -> Self {
         self.children = widgets;
         return self;
@@ -51,66 +69,40 @@ impl Widget for Horizontal {
 
     fn layout(&mut self, rect: Option<Rect>) {
         self.rect = rect;
-        if rect.is_none() {
+        let Some(rect) = rect else {
             return;
-        }
-        let inner_rect = rect.unwrap().subtract_padding(self.padding);
-        let mut child_rect: Rect;
-
-        let mut start = inner_rect.left();
-        let mut space = inner_rect.width();
-        let mut size: usize;
-
-        for widget in &mut self.children {
-            if widget.constraint().has_pixel() {
-                child_rect = inner_rect;
-                size = widget.constraint().calculate(inner_rect.width(), space);
-                if size > 0 {
-                    match widget.alignment() {
-                        Alignment::Start => {
-                            child_rect.left = start;
-                            child_rect.width = size;
-                            start += size;
-                            space -= size;
-                        }
-                        Alignment::End => {
-                            child_rect.left = start + space - size;
-                            child_rect.width = size;
-                            space -= size;
-                        }
-                    }
-                    widget.layout(Some(child_rect));
-                } else {
-                    widget.layout(None);
-                }
-            }
-        }
+        };
+        layout_children(
+            &mut self.children,
+            rect.subtract_padding(self.padding),
+            Axis::Horizontal,
+            self.gap,
+            self.cross_alignment,
+        );
+    }
 
-        let percent_parent = space;
-        for widget in &mut self.children {
-            if widget.constraint().has_percent() {
-                child_rect = inner_rect;
-                size = widget.constraint().calculate(percent_parent, space);
-                if size > 0 {
-                    match widget.alignment() {
-                        Alignment::Start => {
-                            child_rect.left = start;
-                            child_rect.width = size;
-                            start += size;
-                            space -= size;
-                        }
-                        Alignment::End => {
-                            child_rect.left = start + space - size;
-                            child_rect.width = size;
-                            space -= size;
-                        }
-                    }
-                    widget.layout(Some(child_rect));
-                } else {
-                    widget.layout(None)
-                }
-            }
-        }
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        let [width, height] = measure_children(&self.children, Axis::Horizontal, self.gap);
+        let [padding_width, padding_height] = self.padding.calculate_natural();
+        return [width + padding_width, height + padding_height];
+    }
+
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        self.children
+            .iter_mut()
+            .for_each(|widget| widget.collect_damage(damage));
+    }
+
+    fn get_rect(&self) -> Option<Rect> {
+        return self.rect;
+    }
+
+    fn get_children(&self) -> &[Box<dyn Widget>] {
+        return &self.children;
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        return &mut self.children;
     }
 
     fn render(&self, context: &mut Context) {
@@ -118,18 +110,24 @@ impl Widget for Horizontal {
             return;
         }
         let rect = self.rect.unwrap();
-        let mut layer = Layer::default();
-        layer.push_rect(RectInstance::fill(
+        context.get_layer().push_rect(RectInstance::fill(
             [rect.left() as _, rect.top() as _],
             [rect.width() as _, rect.height() as _],
             self.decoration.border_radius,
             self.decoration.background_color,
         ));
-        context.push_layers(layer);
 
+        // children never paint outside of the container.
+        context.push_clip(Clip::rounded(
+            [rect.left() as _, rect.top() as _],
+            [rect.width() as _, rect.height() as _],
+            self.decoration.border_radius,
+        ));
         self.children
             .iter()
-            .for_each(|widget| widget.render(context));
+            .enumerate()
+            .for_each(|(index, widget)| context.render_child(index, widget.as_ref()));
+        context.pop_clip();
     }
 }
 
@@ -166,6 +164,8 @@ pub struct Vertical {
     constraint: Constraint,
     alignment: Alignment,
     padding: Padding,
+    gap: usize,
+    cross_alignment: CrossAlignment,
     decoration: VerticalDecoration,
     rect: Option<Rect>,
     children: Vec<Box<dyn Widget>>,
@@ -177,6 +177,8 @@ impl Vertical {
             constraint,
             alignment,
             padding: Padding::default(),
+            gap: 0,
+            cross_alignment: CrossAlignment::Stretch,
             decoration: VerticalDecoration::default(),
             rect: Some(Rect::default()),
             children: vec![],
@@ -188,6 +190,17 @@ impl Vertical {
         return self;
     }
 
+    // space between two children in logical pixels.
+    pub fn set_gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        return self;
+    }
+
+    pub fn set_cross_alignment(mut self, cross_alignment: CrossAlignment) -> Self {
+        self.cross_alignment = cross_alignment;
+        return self;
+    }
+
     pub fn set_children(mut self, widgets: Vec<Box<dyn Widget>>) -> Self {
         self.children = widgets;
         return self;
@@ -210,66 +223,40 @@ impl Widget for Vertical {
 
     fn layout(&mut self, rect: Option<Rect>) {
         self.rect = rect;
-        if self.rect.is_none() {
+        let Some(rect) = rect else {
             return;
-        }
-        let inner_rect = rect.unwrap().subtract_padding(self.padding);
-        let mut child_rect: Rect;
-
-        let mut start = inner_rect.top();
-        let mut space = inner_rect.height();
-        let mut size: usize;
-
-        for widget in &mut self.children {
-            if widget.constraint().has_pixel() {
-                child_rect = inner_rect;
-                size = widget.constraint().calculate(inner_rect.height(), space);
-                if size > 0 {
-                    match widget.alignment() {
-                        Alignment::Start => {
-                            child_rect.top = start;
-                            child_rect.height = size;
-                            start += size;
-                            space -= size;
-                        }
-                        Alignment::End => {
-                            child_rect.top = start + space - size;
-                            child_rect.height = size;
-                            space -= size;
-                        }
-                    }
-                    widget.layout(Some(child_rect));
-                } else {
-                    widget.layout(None);
-                }
-            }
-        }
+        };
+        layout_children(
+            &mut self.children,
+            rect.subtract_padding(self.padding),
+            Axis::Vertical,
+            self.gap,
+            self.cross_alignment,
+        );
+    }
 
-        let percent_parent = space;
-        for widget in &mut self.children {
-            if widget.constraint().has_percent() {
-                child_rect = inner_rect;
-                size = widget.constraint().calculate(percent_parent, space);
-                if size > 0 {
-                    match widget.alignment() {
-                        Alignment::Start => {
-                            child_rect.top = start;
-                            child_rect.height = size;
-                            start += size;
-                            space -= size;
-                        }
-                        Alignment::End => {
-                            child_rect.top = start + space - size;
-                            child_rect.height = size;
-                            space -= size;
-                        }
-                    }
-                    widget.layout(Some(child_rect));
-                } else {
-                    widget.layout(None);
-                }
-            }
-        }
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        let [width, height] = measure_children(&self.children, Axis::Vertical, self.gap);
+        let [padding_width, padding_height] = self.padding.calculate_natural();
+        return [width + padding_width, height + padding_height];
+    }
+
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        self.children
+            .iter_mut()
+            .for_each(|widget| widget.collect_damage(damage));
+    }
+
+    fn get_rect(&self) -> Option<Rect> {
+        return self.rect;
+    }
+
+    fn get_children(&self) -> &[Box<dyn Widget>] {
+        return &self.children;
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        return &mut self.children;
     }
 
     fn render(&self, context: &mut Context) {
@@ -277,18 +264,24 @@ impl Widget for Vertical {
             return;
         }
         let rect = self.rect.unwrap();
-        let mut layer = Layer::default();
-        layer.push_rect(RectInstance::fill(
+        context.get_layer().push_rect(RectInstance::fill(
             [rect.left() as _, rect.top() as _],
             [rect.width() as _, rect.height() as _],
             self.decoration.border_radius,
             self.decoration.background_color,
         ));
-        context.push_layers(layer);
 
+        // children never paint outside of the container.
+        context.push_clip(Clip::rounded(
+            [rect.left() as _, rect.top() as _],
+            [rect.width() as _, rect.height() as _],
+            self.decoration.border_radius,
+        ));
         self.children
             .iter()
-            .for_each(|widget| widget.render(context));
+            .enumerate()
+            .for_each(|(index, widget)| context.render_child(index, widget.as_ref()));
+        context.pop_clip();
     }
 }
 
@@ -321,15 +314,123 @@ impl Default for VerticalDecoration {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    // indices of the main and the cross size in a [width, height] pair.
+    fn get_indices(&self) -> (usize, usize) {
+        return match self {
+            Axis::Horizontal => (0, 1),
+            Axis::Vertical => (1, 0),
+        };
+    }
+}
+
+// lays the children out one after another along the axis of the inner rect. start aligned
+// children are stacked from the start in order, end aligned ones from the end.
+fn layout_children(
+    children: &mut [Box<dyn Widget>],
+    inner_rect: Rect,
+    axis: Axis,
+    gap: usize,
+    cross_alignment: CrossAlignment,
+) {
+    let (main, cross) = axis.get_indices();
+    let (start, length, cross_start, cross_length) = match axis {
+        Axis::Horizontal => (
+            inner_rect.left(),
+            inner_rect.width(),
+            inner_rect.top(),
+            inner_rect.height(),
+        ),
+        Axis::Vertical => (
+            inner_rect.top(),
+            inner_rect.height(),
+            inner_rect.left(),
+            inner_rect.width(),
+        ),
+    };
+    let intrinsic = children
+        .iter()
+        .map(|widget| widget.get_intrinsic_size())
+        .collect::<Vec<_>>();
+    let constraints = children
+        .iter()
+        .zip(&intrinsic)
+        .map(|(widget, size)| (widget.constraint(), size[main]))
+        .collect::<Vec<_>>();
+    let sizes = distribute(&constraints, length, gap);
+
+    let mut front = start;
+    let mut back = start + length;
+    for ((widget, size), intrinsic) in children.iter_mut().zip(sizes).zip(intrinsic) {
+        if size == 0 {
+            widget.layout(None);
+            continue;
+        }
+        let position = match widget.alignment() {
+            Alignment::Start => {
+                let position = front;
+                front += size + gap;
+                position
+            }
+            Alignment::End => {
+                back = back.saturating_sub(size);
+                let position = back;
+                back = back.saturating_sub(gap);
+                position
+            }
+        };
+        let (cross_position, cross_size) = match cross_alignment {
+            CrossAlignment::Stretch => (cross_start, cross_length),
+            // a widget without a content size would collapse, so it is stretched.
+            _ if intrinsic[cross] == 0 => (cross_start, cross_length),
+            _ => {
+                let cross_size = intrinsic[cross].min(cross_length);
+                let free = cross_length - cross_size;
+                let offset = match cross_alignment {
+                    CrossAlignment::Center => free / 2,
+                    CrossAlignment::End => free,
+                    _ => 0,
+                };
+                (cross_start + offset, cross_size)
+            }
+        };
+        widget.layout(Some(match axis {
+            Axis::Horizontal => Rect::new(position, cross_position, size, cross_size),
+            Axis::Vertical => Rect::new(cross_position, position, cross_size, size),
+        }));
+    }
+}
+
+// size of children laid out along the axis, their natural sizes and gaps along it,
+// the largest intrinsic size across it.
+fn measure_children(children: &[Box<dyn Widget>], axis: Axis, gap: usize) -> [usize; 2] {
+    let (main, cross) = axis.get_indices();
+    let mut size = [0, 0];
+    for widget in children {
+        let intrinsic = widget.get_intrinsic_size();
+        size[main] += widget.constraint().calculate_natural(intrinsic[main]);
+        size[cross] = size[cross].max(intrinsic[cross]);
+    }
+    size[main] += gap * children.len().saturating_sub(1);
+    return size;
+}
+
 #[cfg(test)]
 mod test {
+    use atoz_renderer::pipeline::text::Font;
 
     use crate::{
-        layout::Padding,
-        widget::{Alignment, Constraint},
+        layout::{CrossAlignment, Padding, Rect},
+        widget::{label::Label, Alignment, Constraint, Widget},
     };
 
-    use super::Horizontal;
+    use super::{Horizontal, Vertical};
 
     #[test]
     fn container_test() {
@@ -337,4 +438,47 @@ mod test {
             .set_children(vec![])
             .set_padding(Padding::default());
     }
+
+    #[test]
+    fn container_fill_layout() {
+        let font = Font::from_bytes(include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../atoz-renderer/src/test/assets/DejaVuSansMono.ttf"
+        )))
+        .unwrap();
+        let status = Label::new(
+            Constraint::auto(usize::MAX, 0),
+            Alignment::End,
+            font,
+            "status",
+        )
+        .set_size(10.0);
+        let [width, height] = status.get_intrinsic_size();
+
+        // the editor fills the space between the sidebar and the status.
+        let mut container = Horizontal::new(Constraint::percent(100, 1000000, 0), Alignment::Start)
+            .set_gap(10)
+            .set_cross_alignment(CrossAlignment::Center)
+            .set_children(vec![
+                Box::new(Vertical::new(Constraint::pixel(200, 0), Alignment::Start)),
+                Box::new(Vertical::new(
+                    Constraint::fill(1, usize::MAX, 0),
+                    Alignment::Start,
+                )),
+                Box::new(status),
+            ]);
+        container.layout(Some(Rect::new(0, 0, 1000, 100)));
+        let rects = container
+            .get_children()
+            .iter()
+            .map(|child| child.get_rect().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rects[0], Rect::new(0, 0, 200, 100));
+        assert_eq!(rects[1], Rect::new(210, 0, 780 - width, 100));
+        assert_eq!(
+            rects[2],
+            Rect::new(1000 - width, (100 - height) / 2, width, height)
+        );
+        assert_eq!(container.get_intrinsic_size(), [200 + width + 20, height]);
+    }
 }
@@ -0,0 +1,308 @@
+use atoz_renderer::{clip::Clip, pipeline::text::Font, pipeline::text::Text};
+
+use super::{Alignment, Color, Constraint, Rect, Widget};
+use crate::context::Context;
+
+const ELLIPSIS: &str = "…";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Start,
+    Center,
+    End,
+}
+
+// what happens to lines wider than the label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    Clip,
+    // the end of the line is replaced by an ellipsis.
+    Ellipsis,
+    // lines break between words, words wider than the label between characters.
+    Wrap,
+}
+
+pub struct Label {
+    constraint: Constraint,
+    alignment: Alignment,
+    rect: Option<Rect>,
+    font: Font,
+    text: String,
+    size: f32,
+    color: [f32; 4],
+    text_align: TextAlign,
+    vertical_align: TextAlign,
+    overflow: Overflow,
+    lines: Vec<String>,
+    damaged: bool,
+    needs_layout: bool,
+}
+
+impl Label {
+    pub fn new(constraint: Constraint, alignment: Alignment, font: Font, text: &str) -> Self {
+        return Self {
+            constraint,
+            alignment,
+            rect: Some(Rect::default()),
+            font,
+            text: text.to_string(),
+            size: 14.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            text_align: TextAlign::Start,
+            vertical_align: TextAlign::Start,
+            overflow: Overflow::Clip,
+            lines: vec![],
+            damaged: true,
+            needs_layout: false,
+        };
+    }
+
+    // font size in logical pixels.
+    pub fn set_size(mut self, size: f32) -> Self {
+        self.size = size;
+        return self;
+    }
+
+    pub fn set_color(mut self, color: Color) -> Self {
+        self.color = color.to_float();
+        return self;
+    }
+
+    // horizontal alignment of each line.
+    pub fn set_text_align(mut self, text_align: TextAlign) -> Self {
+        self.text_align = text_align;
+        return self;
+    }
+
+    // vertical alignment of all lines together.
+    pub fn set_vertical_align(mut self, vertical_align: TextAlign) -> Self {
+        self.vertical_align = vertical_align;
+        return self;
+    }
+
+    pub fn set_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        return self;
+    }
+
+    pub fn get_text(&self) -> &str {
+        return &self.text;
+    }
+
+    // changes the text of a label in a laid out tree, it is redrawn with the next frame.
+    pub fn replace_text(&mut self, text: &str) {
+        if self.text != text {
+            // a text of another size can change the rect of the label and its siblings.
+            let size = self.get_intrinsic_size();
+            self.text = text.to_string();
+            self.needs_layout |= self.get_intrinsic_size() != size;
+            self.update_lines();
+            self.damaged = true;
+        }
+    }
+
+    // size of the text without wrapping or truncation, in logical pixels.
+    pub fn measure(&self) -> [f32; 2] {
+        return self.font.measure(&self.text, self.size);
+    }
+
+    // the lines drawn for the current rect.
+    pub fn get_lines(&self) -> &[String] {
+        return &self.lines;
+    }
+
+    fn update_lines(&mut self) {
+        let width = match self.rect {
+            Some(rect) => rect.width() as f32,
+            None => 0.0,
+        };
+        self.lines = self
+            .text
+            .split('\n')
+            .flat_map(|line| match self.overflow {
+                Overflow::Clip => vec![line.to_string()],
+                Overflow::Ellipsis => vec![self.truncate(line, width)],
+                Overflow::Wrap => self.wrap(line, width),
+            })
+            .collect();
+    }
+
+    fn get_width(&self, text: &str) -> f32 {
+        return self.font.measure(text, self.size)[0];
+    }
+
+    fn truncate(&self, line: &str, width: f32) -> String {
+        if self.get_width(line) <= width {
+            return line.to_string();
+        }
+        let mut truncated = String::new();
+        for character in line.chars() {
+            truncated.push(character);
+            if self.get_width(&format!("{}{}", truncated.trim_end(), ELLIPSIS)) > width {
+                truncated.pop();
+                break;
+            }
+        }
+        if truncated.is_empty() && self.get_width(ELLIPSIS) > width {
+            return String::new();
+        }
+        return format!("{}{}", truncated.trim_end(), ELLIPSIS);
+    }
+
+    // greedy, trailing spaces of a line do not count to its width.
+    fn wrap(&self, line: &str, width: f32) -> Vec<String> {
+        let mut lines = vec![];
+        let mut current = String::new();
+        for word in line.split_inclusive(' ') {
+            let candidate = format!("{}{}", current, word);
+            if current.is_empty() || self.get_width(candidate.trim_end()) <= width {
+                current = candidate;
+            } else {
+                lines.push(current.trim_end().to_string());
+                current = word.to_string();
+            }
+
+            // a word longer than the line is split between characters.
+            while self.get_width(current.trim_end()) > width {
+                let mut split = String::new();
+                for character in current.chars() {
+                    split.push(character);
+                    if split.chars().count() > 1 && self.get_width(&split) > width {
+                        split.pop();
+                        break;
+                    }
+                }
+                current = current[split.len()..].to_string();
+                lines.push(split);
+            }
+        }
+        if !current.is_empty() || lines.is_empty() {
+            lines.push(current.trim_end().to_string());
+        }
+        return lines;
+    }
+}
+
+impl Widget for Label {
+    fn constraint(&self) -> Constraint {
+        return self.constraint;
+    }
+
+    fn alignment(&self) -> Alignment {
+        return self.alignment;
+    }
+
+    fn layout(&mut self, rect: Option<Rect>) {
+        self.rect = rect;
+        self.needs_layout = false;
+        self.update_lines();
+    }
+
+    fn needs_layout(&self) -> bool {
+        return self.needs_layout;
+    }
+
+    fn get_rect(&self) -> Option<Rect> {
+        return self.rect;
+    }
+
+    // the unwrapped text.
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        let [width, height] = self.measure();
+        return [width.ceil() as usize, height.ceil() as usize];
+    }
+
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        if let (true, Some(rect)) = (self.damaged, self.rect) {
+            damage.push(rect);
+        }
+        self.damaged = false;
+    }
+
+    fn render(&self, context: &mut Context) {
+        let Some(rect) = self.rect else {
+            return;
+        };
+        let (left, top) = (rect.left() as f32, rect.top() as f32);
+        let (width, height) = (rect.width() as f32, rect.height() as f32);
+        let line_height = self.font.line_height(self.size);
+        let offset = |space: f32, align: TextAlign| match align {
+            TextAlign::Start => 0.0,
+            TextAlign::Center => (space / 2.0).round(),
+            TextAlign::End => space.round(),
+        };
+        let mut y = top
+            + offset(
+                height - line_height * self.lines.len() as f32,
+                self.vertical_align,
+            );
+
+        let layer = context.get_layer();
+        layer.push_clip(Clip::new([left, top], [width, height]));
+        for line in &self.lines {
+            if !line.is_empty() && y + line_height > top && y < top + height {
+                let x = left + offset(width - self.get_width(line), self.text_align);
+                layer.push_text(Text::new(
+                    self.font.clone(),
+                    line,
+                    [x, y],
+                    self.size,
+                    self.color,
+                ));
+            }
+            y += line_height;
+        }
+        layer.pop_clip();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use atoz_renderer::pipeline::text::Font;
+
+    use super::{Label, Overflow};
+    use crate::{
+        layout::{Alignment, Constraint, Rect},
+        widget::Widget,
+    };
+
+    #[test]
+    fn label_lines() {
+        let font = Font::from_bytes(include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../atoz-renderer/src/test/assets/DejaVuSansMono.ttf"
+        )))
+        .unwrap();
+        // a monospace character is about 6 pixels wide at size 10.
+        let mut label = Label::new(
+            Constraint::percent(100, 1000000, 0),
+            Alignment::Start,
+            font,
+            "hello world foo\nbar",
+        )
+        .set_size(10.0)
+        .set_overflow(Overflow::Wrap);
+
+        label.layout(Some(Rect::new(0, 0, 70, 40)));
+        assert_eq!(label.get_lines(), ["hello world", "foo", "bar"]);
+        label.layout(Some(Rect::new(0, 0, 20, 40)));
+        assert_eq!(label.get_lines(), ["hel", "lo", "wor", "ld", "foo", "bar"]);
+
+        let mut label = label.set_overflow(Overflow::Ellipsis);
+        label.layout(Some(Rect::new(0, 0, 40, 40)));
+        assert_eq!(label.get_lines(), ["hello…", "bar"]);
+
+        label.replace_text("abc");
+        assert_eq!(label.get_lines(), ["abc"]);
+        let mut damage = vec![];
+        label.collect_damage(&mut damage);
+        assert_eq!(damage, vec![Rect::new(0, 0, 40, 40)]);
+
+        // a longer text asks for a layout, which clears it. the same size does not.
+        assert!(label.needs_layout());
+        label.layout(Some(Rect::new(0, 0, 40, 40)));
+        assert!(!label.needs_layout());
+        label.replace_text("xyz");
+        assert!(!label.needs_layout());
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use atoz_renderer::{
+    pipeline::{line::LineStyle, rect::RectInstance},
+    profiler::{Phase, HISTORY_SIZE, PHASE_COUNT},
+};
+
+use super::{Alignment, Color, Constraint, Rect, Widget};
+use crate::context::Context;
+
+const PHASE_COLORS: [[f32; 4]; PHASE_COUNT] = [
+    [0.35, 0.55, 0.95, 0.9],
+    [0.55, 0.35, 0.95, 0.9],
+    [0.95, 0.65, 0.25, 0.9],
+    [0.35, 0.85, 0.45, 0.9],
+    [0.95, 0.35, 0.35, 0.9],
+];
+const GPU_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const DRAW_CALL_COLOR: [f32; 4] = [0.95, 0.9, 0.3, 1.0];
+const BUDGET_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.4];
+
+// graph of the last frames of the renderer profiler, which it enables while rendered.
+// a bar per frame stacks the cpu phases, the gpu time is drawn as a white line and the
+// draw calls relative to the busiest frame as a yellow line. the dashed line is the frame budget.
+pub struct ProfilerOverlay {
+    constraint: Constraint,
+    alignment: Alignment,
+    rect: Option<Rect>,
+    scale: Duration,
+    background_color: [f32; 4],
+}
+
+impl ProfilerOverlay {
+    pub fn new(constraint: Constraint, alignment: Alignment) -> Self {
+        return Self {
+            constraint,
+            alignment,
+            rect: Some(Rect::default()),
+            scale: Duration::from_secs_f64(1.0 / 30.0),
+            background_color: [0.0, 0.0, 0.0, 0.6],
+        };
+    }
+
+    // frame time at the full height of the graph, longer frames are cut off.
+    pub fn set_scale(mut self, scale: Duration) -> Self {
+        self.scale = scale;
+        return self;
+    }
+
+    pub fn set_background_color(mut self, color: Color) -> Self {
+        self.background_color = color.to_float();
+        return self;
+    }
+}
+
+impl Widget for ProfilerOverlay {
+    fn constraint(&self) -> Constraint {
+        return self.constraint;
+    }
+
+    fn alignment(&self) -> Alignment {
+        return self.alignment;
+    }
+
+    fn layout(&mut self, rect: Option<Rect>) {
+        self.rect = rect;
+    }
+
+    fn get_rect(&self) -> Option<Rect> {
+        return self.rect;
+    }
+
+    // the graph changes with every frame.
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        if let Some(rect) = self.rect {
+            damage.push(rect);
+        }
+    }
+
+    // the graph keeps requesting frames while it is shown, so it never freezes.
+    fn is_animating(&self) -> bool {
+        return self.rect.is_some();
+    }
+
+    fn render(&self, context: &mut Context) {
+        let Some(rect) = self.rect else {
+            return;
+        };
+        context
+            .get_renderer_mut()
+            .get_profiler_mut()
+            .set_enabled(true);
+        let frame_rate = context.get_config().frame_rate;
+
+        let (left, top) = (rect.left() as f32, rect.top() as f32);
+        let (width, height) = (rect.width() as f32, rect.height() as f32);
+        let bottom = top + height;
+        let bar_width = width / HISTORY_SIZE as f32;
+        // height of a duration, clamped to the graph.
+        let scale = self.scale.as_secs_f32().max(f32::EPSILON);
+        let to_height = |duration: Duration| (duration.as_secs_f32() / scale).min(1.0) * height;
+
+        let mut bars = vec![RectInstance::fill(
+            [left, top],
+            [width, height],
+            [0.0; 4],
+            self.background_color,
+        )];
+        let mut gpu = vec![];
+        let mut draw_calls = vec![];
+
+        // the newest frame is at the right edge.
+        let history = context.get_renderer().get_profiler().get_history();
+        let max_draw_calls = history
+            .iter()
+            .map(|stats| stats.get_draw_calls())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let start = left + (HISTORY_SIZE - history.len()) as f32 * bar_width;
+        for (index, stats) in history.iter().enumerate() {
+            let x = start + index as f32 * bar_width;
+            let mut y = bottom;
+            for phase in Phase::ALL {
+                let bar_height = to_height(stats.get_cpu(phase)).min(y - top);
+                if bar_height > 0.0 {
+                    y -= bar_height;
+                    bars.push(RectInstance::fill(
+                        [x, y],
+                        [bar_width, bar_height],
+                        [0.0; 4],
+                        PHASE_COLORS[phase as usize],
+                    ));
+                }
+            }
+            let center = x + bar_width / 2.0;
+            if let Some(duration) = stats.gpu {
+                gpu.push([center, bottom - to_height(duration)]);
+            }
+            let calls = stats.get_draw_calls() as f32 / max_draw_calls as f32;
+            draw_calls.push([center, bottom - calls * height]);
+        }
+
+        let layer = context.get_layer();
+        bars.into_iter().for_each(|bar| layer.push_rect(bar));
+        if let Some(frame_rate) = frame_rate.filter(|rate| *rate > 0.0) {
+            let y = bottom - to_height(Duration::from_secs_f64(1.0 / frame_rate));
+            layer.push_line(
+                [left, y],
+                [left + width, y],
+                &LineStyle::new(1.0, BUDGET_COLOR).set_dash(4.0, 4.0, 0.0),
+            );
+        }
+        if gpu.len() > 1 {
+            layer.push_polyline(&gpu, &LineStyle::new(1.5, GPU_COLOR));
+        }
+        if draw_calls.len() > 1 {
+            layer.push_polyline(&draw_calls, &LineStyle::new(1.0, DRAW_CALL_COLOR));
+        }
+    }
+}
@@ -0,0 +1,571 @@
+use std::time::Instant;
+
+use atoz_renderer::{clip::Clip, pipeline::rect::RectInstance, transform::Transform};
+
+use super::{Alignment, Color, Constraint, Rect, Widget};
+use crate::{
+    context::Context,
+    event::{Event, EventPhase, EventResult, MouseButton, ScrollDelta, TouchPhase},
+    layout::Unit,
+};
+
+// logical pixels scrolled by a wheel line.
+const LINE_SIZE: f32 = 40.0;
+// per second, how fast the offset approaches the target of a wheel scroll.
+const SMOOTHING: f32 = 18.0;
+// per second, how fast a fling slows down.
+const FRICTION: f32 = 4.0;
+// flings slower than this in logical pixels per second stop.
+const MIN_VELOCITY: f32 = 30.0;
+// touchpad deltas further apart than this in seconds do not belong to the same swipe.
+const MAX_SAMPLE_GAP: f32 = 0.1;
+const THUMB_THICKNESS: f32 = 6.0;
+const THUMB_MARGIN: f32 = 2.0;
+const MIN_THUMB_LENGTH: f32 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Vertical,
+    Horizontal,
+}
+
+// viewport on content longer than itself. the content is laid out with its constraint along
+// the direction, Unit::Auto for its intrinsic length, and fills the view across it. wheels
+// scroll smoothly, touchpad swipes fling once the fingers are lifted, and the overlay thumb
+// can be dragged.
+pub struct ScrollView {
+    constraint: Constraint,
+    alignment: Alignment,
+    direction: ScrollDirection,
+    thumb_color: [f32; 4],
+    rect: Option<Rect>,
+    // the content, a single widget.
+    children: Vec<Box<dyn Widget>>,
+    // length of the content along the direction.
+    length: f32,
+    offset: f32,
+    target: f32,
+    velocity: f32,
+    // velocity of the current touchpad swipe, the fling starts with it.
+    swipe_velocity: f32,
+    last_swipe: Option<Instant>,
+    last_step: Option<Instant>,
+    // where the thumb was grabbed, from its start.
+    drag: Option<f32>,
+    damaged: bool,
+}
+
+impl ScrollView {
+    pub fn new(constraint: Constraint, alignment: Alignment, content: Box<dyn Widget>) -> Self {
+        return Self {
+            constraint,
+            alignment,
+            direction: ScrollDirection::Vertical,
+            thumb_color: [1.0, 1.0, 1.0, 0.4],
+            rect: Some(Rect::default()),
+            children: vec![content],
+            length: 0.0,
+            offset: 0.0,
+            target: 0.0,
+            velocity: 0.0,
+            swipe_velocity: 0.0,
+            last_swipe: None,
+            last_step: None,
+            drag: None,
+            damaged: true,
+        };
+    }
+
+    pub fn set_direction(mut self, direction: ScrollDirection) -> Self {
+        self.direction = direction;
+        return self;
+    }
+
+    pub fn set_thumb_color(mut self, color: Color) -> Self {
+        self.thumb_color = color.to_float();
+        return self;
+    }
+
+    // how far the content is scrolled, in logical pixels.
+    pub fn get_offset(&self) -> f32 {
+        return self.offset;
+    }
+
+    pub fn get_max_offset(&self) -> f32 {
+        return (self.length - self.get_view_length()).max(0.0);
+    }
+
+    // animate moves there like a wheel scroll, otherwise it jumps.
+    pub fn scroll_to(&mut self, offset: f32, animate: bool) {
+        self.target = self.clamp(offset);
+        self.velocity = 0.0;
+        if !animate {
+            self.offset = self.target;
+        }
+        self.start_animation();
+        self.damaged = true;
+    }
+
+    fn get_view_length(&self) -> f32 {
+        return match (self.rect, self.direction) {
+            (Some(rect), ScrollDirection::Vertical) => rect.height() as f32,
+            (Some(rect), ScrollDirection::Horizontal) => rect.width() as f32,
+            (None, _) => 0.0,
+        };
+    }
+
+    fn clamp(&self, offset: f32) -> f32 {
+        return offset.clamp(0.0, self.get_max_offset());
+    }
+
+    fn is_moving(&self) -> bool {
+        return self.velocity != 0.0 || self.offset != self.target;
+    }
+
+    fn start_animation(&mut self) {
+        if self.last_step.is_none() {
+            self.last_step = Some(Instant::now());
+        }
+    }
+
+    // advances the fling or the wheel scroll by dt seconds.
+    fn step(&mut self, dt: f32) {
+        if self.velocity != 0.0 {
+            let offset = self.offset + self.velocity * dt;
+            self.offset = self.clamp(offset);
+            self.target = self.offset;
+            self.velocity *= (-FRICTION * dt).exp();
+            if self.offset != offset || self.velocity.abs() < MIN_VELOCITY {
+                self.velocity = 0.0;
+            }
+            return;
+        }
+        self.offset += (self.target - self.offset) * (1.0 - (-SMOOTHING * dt).exp());
+        if (self.target - self.offset).abs() < 0.5 {
+            self.offset = self.target;
+        }
+    }
+
+    // the delta along the direction, a horizontal view also scrolls with a vertical wheel.
+    fn get_delta(&self, delta: [f32; 2]) -> f32 {
+        return match self.direction {
+            ScrollDirection::Vertical => delta[1],
+            ScrollDirection::Horizontal if delta[0] != 0.0 => delta[0],
+            ScrollDirection::Horizontal => delta[1],
+        };
+    }
+
+    fn wheel(&mut self, delta: &ScrollDelta, phase: TouchPhase) -> EventResult {
+        let now = Instant::now();
+        let before = (self.offset, self.target, self.velocity);
+        match delta {
+            ScrollDelta::Lines(delta) => {
+                self.velocity = 0.0;
+                self.target = self.clamp(self.target - self.get_delta(*delta) * LINE_SIZE);
+            }
+            ScrollDelta::Pixels(delta) => {
+                let delta = self.get_delta(*delta);
+                let elapsed = match self.last_swipe {
+                    Some(last) => now.duration_since(last).as_secs_f32(),
+                    None => f32::MAX,
+                };
+                if phase == TouchPhase::Started || elapsed > MAX_SAMPLE_GAP {
+                    self.swipe_velocity = 0.0;
+                } else if delta != 0.0 && elapsed > 0.0 {
+                    self.swipe_velocity = self.swipe_velocity * 0.4 - delta / elapsed * 0.6;
+                }
+                self.last_swipe = Some(now);
+
+                // the content follows the fingers, and keeps going once they are lifted.
+                self.offset = self.clamp(self.offset - delta);
+                self.target = self.offset;
+                self.velocity = 0.0;
+                if phase == TouchPhase::Ended && self.swipe_velocity.abs() >= MIN_VELOCITY {
+                    self.velocity = self.swipe_velocity;
+                }
+            }
+        }
+        if (self.offset, self.target, self.velocity) == before {
+            return EventResult::Ignored;
+        }
+        self.start_animation();
+        self.damaged = true;
+        return EventResult::Handled;
+    }
+
+    // start and length of the thumb along the direction, from the start of the view.
+    fn get_thumb(&self) -> Option<(f32, f32)> {
+        let max_offset = self.get_max_offset();
+        if max_offset <= 0.0 {
+            return None;
+        }
+        let view = self.get_view_length();
+        let track = view - 2.0 * THUMB_MARGIN;
+        let length = (view / self.length * track)
+            .max(MIN_THUMB_LENGTH)
+            .min(track);
+        let start = THUMB_MARGIN + self.offset / max_offset * (track - length);
+        return Some((start, length));
+    }
+
+    // position along the direction from the start of the view, None outside of the track.
+    fn get_track_position(&self, position: [f32; 2]) -> Option<f32> {
+        let rect = self.rect?;
+        let (left, top) = (rect.left() as f32, rect.top() as f32);
+        let across = THUMB_THICKNESS + 2.0 * THUMB_MARGIN;
+        return match self.direction {
+            ScrollDirection::Vertical if position[0] >= rect.right() as f32 - across => {
+                Some(position[1] - top)
+            }
+            ScrollDirection::Horizontal if position[1] >= rect.bottom() as f32 - across => {
+                Some(position[0] - left)
+            }
+            _ => None,
+        };
+    }
+
+    fn get_position(&self, position: [f32; 2]) -> f32 {
+        let Some(rect) = self.rect else {
+            return 0.0;
+        };
+        return match self.direction {
+            ScrollDirection::Vertical => position[1] - rect.top() as f32,
+            ScrollDirection::Horizontal => position[0] - rect.left() as f32,
+        };
+    }
+
+    // moves the thumb so that the grabbed point is at position.
+    fn drag_to(&mut self, position: f32, grab: f32) {
+        let Some((_, length)) = self.get_thumb() else {
+            return;
+        };
+        let track = self.get_view_length() - 2.0 * THUMB_MARGIN - length;
+        let ratio = (position - grab - THUMB_MARGIN) / track.max(1.0);
+        self.offset = self.clamp(ratio * self.get_max_offset());
+        self.target = self.offset;
+        self.velocity = 0.0;
+        self.damaged = true;
+    }
+}
+
+impl Widget for ScrollView {
+    fn constraint(&self) -> Constraint {
+        return self.constraint;
+    }
+
+    fn alignment(&self) -> Alignment {
+        return self.alignment;
+    }
+
+    fn layout(&mut self, rect: Option<Rect>) {
+        self.rect = rect;
+        let content = &mut self.children[0];
+        let Some(rect) = rect else {
+            content.layout(None);
+            return;
+        };
+        let mut content_rect = rect;
+        let length = match self.direction {
+            ScrollDirection::Vertical => {
+                content_rect.height = get_length(content.as_ref(), rect.height(), 1);
+                content_rect.height
+            }
+            ScrollDirection::Horizontal => {
+                content_rect.width = get_length(content.as_ref(), rect.width(), 0);
+                content_rect.width
+            }
+        };
+        match length {
+            0 => content.layout(None),
+            _ => content.layout(Some(content_rect)),
+        }
+        self.length = length as f32;
+        self.offset = self.clamp(self.offset);
+        self.target = self.clamp(self.target);
+        self.damaged = true;
+    }
+
+    fn get_rect(&self) -> Option<Rect> {
+        return self.rect;
+    }
+
+    // the whole content, which the view is allowed to cut.
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        let content = &self.children[0];
+        let mut size = content.get_intrinsic_size();
+        let main = match self.direction {
+            ScrollDirection::Vertical => 1,
+            ScrollDirection::Horizontal => 0,
+        };
+        size[main] = content.constraint().calculate_natural(size[main]);
+        return size;
+    }
+
+    fn get_children(&self) -> &[Box<dyn Widget>] {
+        return &self.children;
+    }
+
+    fn get_children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        return &mut self.children;
+    }
+
+    // rounded like the drawn content, which keeps text on the pixel grid.
+    fn get_child_offset(&self) -> [f32; 2] {
+        return match self.direction {
+            ScrollDirection::Vertical => [0.0, self.offset.round()],
+            ScrollDirection::Horizontal => [self.offset.round(), 0.0],
+        };
+    }
+
+    fn is_animating(&self) -> bool {
+        return self.is_moving() || self.children[0].is_animating();
+    }
+
+    // content damage is moved by the offset and cut to the view. scrolling redraws the view.
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        if self.is_moving() {
+            let now = Instant::now();
+            let dt = match self.last_step {
+                Some(last) => now.duration_since(last).as_secs_f32(),
+                None => 0.0,
+            };
+            self.step(dt);
+            self.last_step = Some(now);
+            self.damaged = true;
+        } else {
+            self.last_step = None;
+        }
+
+        let mut content_damage = vec![];
+        self.children[0].collect_damage(&mut content_damage);
+        let Some(rect) = self.rect else {
+            return;
+        };
+        if self.damaged {
+            damage.push(rect);
+            self.damaged = false;
+            return;
+        }
+        let [x, y] = self.get_child_offset();
+        for content in content_damage {
+            let left = (content.left() as f32 - x).max(rect.left() as f32);
+            let top = (content.top() as f32 - y).max(rect.top() as f32);
+            let right = (content.right() as f32 - x).min(rect.right() as f32);
+            let bottom = (content.bottom() as f32 - y).min(rect.bottom() as f32);
+            if right > left && bottom > top {
+                damage.push(Rect::new(
+                    left as usize,
+                    top as usize,
+                    (right - left) as usize,
+                    (bottom - top) as usize,
+                ));
+            }
+        }
+    }
+
+    fn event(&mut self, event: &Event, phase: EventPhase) -> EventResult {
+        // the thumb is above the content, so it gets the mouse before it.
+        match (event, phase) {
+            (
+                Event::MousePress {
+                    position,
+                    button: MouseButton::Left,
+                    ..
+                },
+                EventPhase::Capture | EventPhase::Target,
+            ) => {
+                let (Some(position), Some((start, length))) =
+                    (self.get_track_position(*position), self.get_thumb())
+                else {
+                    return EventResult::Ignored;
+                };
+                // a press beside the thumb centers it there.
+                let grab = match position >= start && position < start + length {
+                    true => position - start,
+                    false => length / 2.0,
+                };
+                self.drag = Some(grab);
+                self.drag_to(position, grab);
+                return EventResult::Handled;
+            }
+            (Event::MouseMove { position }, EventPhase::Capture | EventPhase::Target) => {
+                let Some(grab) = self.drag else {
+                    return EventResult::Ignored;
+                };
+                self.drag_to(self.get_position(*position), grab);
+                return EventResult::Handled;
+            }
+            (
+                Event::MouseRelease {
+                    button: MouseButton::Left,
+                    ..
+                },
+                EventPhase::Capture | EventPhase::Target,
+            ) if self.drag.is_some() => {
+                self.drag = None;
+                self.damaged = true;
+                return EventResult::Handled;
+            }
+            // nested views scroll first, the outer one once they reached their end.
+            (Event::MouseWheel { delta, phase, .. }, EventPhase::Target | EventPhase::Bubble) => {
+                return self.wheel(delta, *phase);
+            }
+            _ => return EventResult::Ignored,
+        }
+    }
+
+    fn render(&self, context: &mut Context) {
+        let Some(rect) = self.rect else {
+            return;
+        };
+        let (left, top) = (rect.left() as f32, rect.top() as f32);
+        let (width, height) = (rect.width() as f32, rect.height() as f32);
+        let [x, y] = self.get_child_offset();
+
+        context.push_clip(Clip::new([left, top], [width, height]));
+        context.push_transform(Transform::translate(-x, -y));
+        context.render_child(0, self.children[0].as_ref());
+        context.pop_transform();
+        context.pop_clip();
+
+        let Some((start, length)) = self.get_thumb() else {
+            return;
+        };
+        let (position, size) = match self.direction {
+            ScrollDirection::Vertical => (
+                [left + width - THUMB_MARGIN - THUMB_THICKNESS, top + start],
+                [THUMB_THICKNESS, length],
+            ),
+            ScrollDirection::Horizontal => (
+                [left + start, top + height - THUMB_MARGIN - THUMB_THICKNESS],
+                [length, THUMB_THICKNESS],
+            ),
+        };
+        // the dragged thumb is drawn more opaque.
+        let mut color = self.thumb_color;
+        if self.drag.is_some() {
+            color[3] = (color[3] * 1.5).min(1.0);
+        }
+        context.get_layer().push_rect(RectInstance::fill(
+            position,
+            size,
+            [THUMB_THICKNESS / 2.0; 4],
+            color,
+        ));
+    }
+}
+
+// length of the content along the direction, index of a [width, height] pair.
+// filling and percent content is sized relative to the view.
+fn get_length(content: &dyn Widget, view: usize, index: usize) -> usize {
+    let constraint = content.constraint();
+    return match constraint.get_unit() {
+        Unit::Auto => constraint.calculate_auto(content.get_intrinsic_size()[index], usize::MAX),
+        _ => constraint.calculate(view, usize::MAX),
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use winit::keyboard::ModifiersState;
+
+    use super::ScrollView;
+    use crate::{
+        event::{hit_test, Event, EventPhase, EventResult, MouseButton, ScrollDelta, TouchPhase},
+        layout::{Alignment, Constraint, Rect},
+        widget::{container::Vertical, Widget},
+    };
+
+    fn wheel(view: &mut ScrollView, delta: ScrollDelta, phase: TouchPhase) -> EventResult {
+        let event = Event::MouseWheel {
+            position: [50.0, 50.0],
+            delta,
+            phase,
+        };
+        return view.event(&event, EventPhase::Target);
+    }
+
+    #[test]
+    fn scroll_view() {
+        let content = Vertical::new(Constraint::pixel(1000, 0), Alignment::Start);
+        let mut view = ScrollView::new(
+            Constraint::percent(100, 1000000, 0),
+            Alignment::Start,
+            Box::new(content),
+        );
+        view.layout(Some(Rect::new(0, 0, 100, 200)));
+        assert_eq!(
+            view.get_children()[0].get_rect(),
+            Some(Rect::new(0, 0, 100, 1000))
+        );
+        assert_eq!(view.get_max_offset(), 800.0);
+
+        // wheel lines move smoothly towards their target.
+        let down = ScrollDelta::Lines([0.0, -1.0]);
+        assert_eq!(
+            wheel(&mut view, down, TouchPhase::Moved),
+            EventResult::Handled
+        );
+        assert_eq!(view.get_offset(), 0.0);
+        assert!(view.is_animating());
+        view.step(0.05);
+        assert!(view.get_offset() > 20.0 && view.get_offset() < 40.0);
+        view.step(1.0);
+        assert_eq!(view.get_offset(), 40.0);
+        assert!(!view.is_animating());
+
+        // touchpad pixels follow the fingers, positions are moved into the content.
+        let pixels = ScrollDelta::Pixels([0.0, -60.0]);
+        wheel(&mut view, pixels, TouchPhase::Moved);
+        assert_eq!(view.get_offset(), 100.0);
+        assert_eq!(view.get_child_offset(), [0.0, 100.0]);
+        assert_eq!(hit_test(&view, [50.0, 150.0]), vec![0]);
+
+        // lifting the fingers flings until friction stops it.
+        view.swipe_velocity = 600.0;
+        let lift = ScrollDelta::Pixels([0.0, 0.0]);
+        assert_eq!(
+            wheel(&mut view, lift, TouchPhase::Ended),
+            EventResult::Handled
+        );
+        view.step(0.1);
+        assert!(view.get_offset() > 150.0);
+        (0..100).for_each(|_| view.step(0.1));
+        assert!(!view.is_animating());
+        assert!(view.get_offset() < 300.0);
+
+        // at the start the wheel is left to the parents.
+        view.scroll_to(0.0, false);
+        let up = ScrollDelta::Lines([0.0, 1.0]);
+        assert_eq!(
+            wheel(&mut view, up, TouchPhase::Moved),
+            EventResult::Ignored
+        );
+
+        // the thumb is about 40 pixels long at the right edge, dragging it to the end
+        // scrolls there.
+        let press = Event::MousePress {
+            position: [95.0, 10.0],
+            button: MouseButton::Left,
+            modifiers: ModifiersState::empty(),
+        };
+        assert_eq!(
+            view.event(&press, EventPhase::Capture),
+            EventResult::Handled
+        );
+        let drag = Event::MouseMove {
+            position: [95.0, 300.0],
+        };
+        assert_eq!(view.event(&drag, EventPhase::Capture), EventResult::Handled);
+        assert_eq!(view.get_offset(), 800.0);
+        let click = Event::MousePress {
+            position: [50.0, 10.0],
+            button: MouseButton::Left,
+            modifiers: ModifiersState::empty(),
+        };
+        assert_eq!(
+            view.event(&click, EventPhase::Capture),
+            EventResult::Ignored
+        );
+    }
+}
@@ -0,0 +1,752 @@
+use std::{cell::RefCell, ops::Range, rc::Rc};
+
+use atoz_renderer::{
+    clip::Clip,
+    pipeline::{
+        rect::RectInstance,
+        text::{Font, Text},
+    },
+};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use super::{Alignment, Color, Constraint, Focus, Rect, Widget};
+use crate::{
+    clipboard::{self, Clipboard},
+    context::Context,
+    event::{Event, EventPhase, EventResult, MouseButton, ScrollDelta},
+};
+
+pub mod editor;
+
+use editor::TextEditor;
+
+const PADDING: f32 = 4.0;
+const CARET_WIDTH: f32 = 1.0;
+const WHEEL_LINES: f32 = 3.0;
+
+// editable single line of text, e.g. for a find bar or a rename box.
+// enter and tab are left to the parents.
+pub struct TextInput {
+    field: TextField,
+}
+
+impl TextInput {
+    pub fn new(constraint: Constraint, alignment: Alignment, font: Font, text: &str) -> Self {
+        return Self {
+            field: TextField::new(constraint, alignment, font, text, false),
+        };
+    }
+
+    // font size in logical pixels.
+    pub fn set_size(mut self, size: f32) -> Self {
+        self.field.size = size;
+        return self;
+    }
+
+    // color of the text and the caret.
+    pub fn set_color(mut self, color: Color) -> Self {
+        self.field.color = color.to_float();
+        return self;
+    }
+
+    pub fn set_selection_color(mut self, color: Color) -> Self {
+        self.field.selection_color = color.to_float();
+        return self;
+    }
+
+    pub fn set_background_color(mut self, color: Color) -> Self {
+        self.field.background_color = color.to_float();
+        return self;
+    }
+
+    // defaults to the clipboard shared by the widgets of the thread.
+    pub fn set_clipboard(mut self, clipboard: Rc<RefCell<dyn Clipboard>>) -> Self {
+        self.field.clipboard = clipboard;
+        return self;
+    }
+
+    pub fn get_text(&self) -> &str {
+        return self.field.editor.get_text();
+    }
+
+    // replaces the text and its undo history, the caret moves to the end.
+    pub fn replace_text(&mut self, text: &str) {
+        self.field.editor = TextEditor::new(text, false);
+        self.field.changed();
+    }
+
+    pub fn get_editor(&self) -> &TextEditor {
+        return &self.field.editor;
+    }
+
+    // the widget is redrawn with the next frame.
+    pub fn get_editor_mut(&mut self) -> &mut TextEditor {
+        self.field.damaged = true;
+        return &mut self.field.editor;
+    }
+}
+
+impl Widget for TextInput {
+    fn constraint(&self) -> Constraint {
+        return self.field.constraint;
+    }
+
+    fn alignment(&self) -> Alignment {
+        return self.field.alignment;
+    }
+
+    fn layout(&mut self, rect: Option<Rect>) {
+        self.field.layout(rect);
+    }
+
+    fn get_rect(&self) -> Option<Rect> {
+        return self.field.rect;
+    }
+
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        return self.field.get_intrinsic_size();
+    }
+
+    fn needs_layout(&self) -> bool {
+        return self.field.needs_layout;
+    }
+
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        self.field.collect_damage(damage);
+    }
+
+    fn render(&self, context: &mut Context) {
+        self.field.render(context);
+    }
+
+    fn event(&mut self, event: &Event, phase: EventPhase) -> EventResult {
+        return self.field.event(event, phase);
+    }
+
+    fn is_focusable(&self) -> bool {
+        return true;
+    }
+
+    fn focus_changed(&mut self, focus: Focus) {
+        self.field.focus_changed(focus);
+    }
+}
+
+// editable multi-line text. enter breaks the line, the wheel scrolls the text.
+pub struct TextArea {
+    field: TextField,
+}
+
+impl TextArea {
+    pub fn new(constraint: Constraint, alignment: Alignment, font: Font, text: &str) -> Self {
+        return Self {
+            field: TextField::new(constraint, alignment, font, text, true),
+        };
+    }
+
+    // font size in logical pixels.
+    pub fn set_size(mut self, size: f32) -> Self {
+        self.field.size = size;
+        return self;
+    }
+
+    // color of the text and the caret.
+    pub fn set_color(mut self, color: Color) -> Self {
+        self.field.color = color.to_float();
+        return self;
+    }
+
+    pub fn set_selection_color(mut self, color: Color) -> Self {
+        self.field.selection_color = color.to_float();
+        return self;
+    }
+
+    pub fn set_background_color(mut self, color: Color) -> Self {
+        self.field.background_color = color.to_float();
+        return self;
+    }
+
+    // defaults to the clipboard shared by the widgets of the thread.
+    pub fn set_clipboard(mut self, clipboard: Rc<RefCell<dyn Clipboard>>) -> Self {
+        self.field.clipboard = clipboard;
+        return self;
+    }
+
+    pub fn get_text(&self) -> &str {
+        return self.field.editor.get_text();
+    }
+
+    // replaces the text and its undo history, the caret moves to the end.
+    pub fn replace_text(&mut self, text: &str) {
+        self.field.editor = TextEditor::new(text, true);
+        self.field.changed();
+    }
+
+    pub fn get_editor(&self) -> &TextEditor {
+        return &self.field.editor;
+    }
+
+    // the widget is redrawn with the next frame.
+    pub fn get_editor_mut(&mut self) -> &mut TextEditor {
+        self.field.damaged = true;
+        return &mut self.field.editor;
+    }
+}
+
+impl Widget for TextArea {
+    fn constraint(&self) -> Constraint {
+        return self.field.constraint;
+    }
+
+    fn alignment(&self) -> Alignment {
+        return self.field.alignment;
+    }
+
+    fn layout(&mut self, rect: Option<Rect>) {
+        self.field.layout(rect);
+    }
+
+    fn get_rect(&self) -> Option<Rect> {
+        return self.field.rect;
+    }
+
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        return self.field.get_intrinsic_size();
+    }
+
+    fn needs_layout(&self) -> bool {
+        return self.field.needs_layout;
+    }
+
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        self.field.collect_damage(damage);
+    }
+
+    fn render(&self, context: &mut Context) {
+        self.field.render(context);
+    }
+
+    fn event(&mut self, event: &Event, phase: EventPhase) -> EventResult {
+        return self.field.event(event, phase);
+    }
+
+    fn is_focusable(&self) -> bool {
+        return true;
+    }
+
+    fn focus_changed(&mut self, focus: Focus) {
+        self.field.focus_changed(focus);
+    }
+}
+
+// the editing, scrolling and drawing shared by TextInput and TextArea.
+struct TextField {
+    constraint: Constraint,
+    alignment: Alignment,
+    rect: Option<Rect>,
+    font: Font,
+    size: f32,
+    color: [f32; 4],
+    selection_color: [f32; 4],
+    background_color: [f32; 4],
+    multiline: bool,
+    editor: TextEditor,
+    clipboard: Rc<RefCell<dyn Clipboard>>,
+    focused: bool,
+    // a press selects until the release.
+    dragging: bool,
+    // offset of the text that keeps the caret visible, in logical pixels.
+    scroll: [f32; 2],
+    damaged: bool,
+    // intrinsic size at the last layout, an edit that changes it asks for a layout.
+    intrinsic_size: [usize; 2],
+    needs_layout: bool,
+}
+
+impl TextField {
+    fn new(
+        constraint: Constraint,
+        alignment: Alignment,
+        font: Font,
+        text: &str,
+        multiline: bool,
+    ) -> Self {
+        return Self {
+            constraint,
+            alignment,
+            rect: Some(Rect::default()),
+            font,
+            size: 14.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            selection_color: [0.25, 0.45, 0.85, 0.6],
+            background_color: [0.0, 0.0, 0.0, 0.0],
+            multiline,
+            editor: TextEditor::new(text, multiline),
+            clipboard: clipboard::get_shared(),
+            focused: false,
+            dragging: false,
+            scroll: [0.0, 0.0],
+            damaged: true,
+            intrinsic_size: [0, 0],
+            needs_layout: false,
+        };
+    }
+
+    fn layout(&mut self, rect: Option<Rect>) {
+        self.rect = rect;
+        self.intrinsic_size = self.get_intrinsic_size();
+        self.needs_layout = false;
+        self.scroll_to_caret();
+    }
+
+    // the whole text with the padding and the caret.
+    fn get_intrinsic_size(&self) -> [usize; 2] {
+        let [width, height] = self.font.measure(self.editor.get_text(), self.size);
+        let height = height.max(self.get_line_height());
+        return [
+            (width + 2.0 * PADDING + CARET_WIDTH).ceil() as usize,
+            (height + 2.0 * PADDING).ceil() as usize,
+        ];
+    }
+
+    fn collect_damage(&mut self, damage: &mut Vec<Rect>) {
+        if let (true, Some(rect)) = (self.damaged, self.rect) {
+            damage.push(rect);
+        }
+        self.damaged = false;
+    }
+
+    fn focus_changed(&mut self, focus: Focus) {
+        self.focused = focus == Focus::Focused;
+        if !self.focused {
+            self.editor.set_preedit(None);
+            self.dragging = false;
+        }
+        self.damaged = true;
+    }
+
+    fn changed(&mut self) {
+        self.needs_layout |= self.get_intrinsic_size() != self.intrinsic_size;
+        self.scroll_to_caret();
+        self.damaged = true;
+    }
+
+    fn event(&mut self, event: &Event, phase: EventPhase) -> EventResult {
+        if phase != EventPhase::Target {
+            return EventResult::Ignored;
+        }
+        match event {
+            Event::MousePress {
+                position,
+                button: MouseButton::Left,
+                modifiers,
+            } => {
+                let offset = self.get_offset_at(*position);
+                self.editor.set_caret(offset, modifiers.shift_key());
+                self.dragging = true;
+            }
+            Event::MouseMove { position } if self.dragging => {
+                let offset = self.get_offset_at(*position);
+                self.editor.set_caret(offset, true);
+            }
+            Event::MouseRelease {
+                button: MouseButton::Left,
+                ..
+            } => self.dragging = false,
+            Event::MouseWheel { delta, .. } if self.multiline => {
+                let delta = match delta {
+                    ScrollDelta::Lines(delta) => delta[1] * WHEEL_LINES * self.get_line_height(),
+                    ScrollDelta::Pixels(delta) => delta[1],
+                };
+                let scroll = (self.scroll[1] - delta).clamp(0.0, self.get_max_scroll());
+                if scroll == self.scroll[1] {
+                    return EventResult::Ignored;
+                }
+                self.scroll[1] = scroll;
+                self.damaged = true;
+                return EventResult::Handled;
+            }
+            Event::KeyPress { key, modifiers, .. } if self.focused => {
+                return self.key_press(key, *modifiers);
+            }
+            Event::Text(text) if self.focused => self.editor.insert(text),
+            Event::Preedit { text, cursor } if self.focused => {
+                self.editor.set_preedit(Some((text.clone(), *cursor)));
+            }
+            _ => return EventResult::Ignored,
+        }
+        self.changed();
+        return EventResult::Handled;
+    }
+
+    // keys with text are ignored, they come back as a text event.
+    fn key_press(&mut self, key: &Key, modifiers: ModifiersState) -> EventResult {
+        let word = modifiers.control_key();
+        let command = modifiers.control_key() || modifiers.super_key();
+        let extend = modifiers.shift_key();
+        match key {
+            Key::Named(NamedKey::ArrowLeft) => self.editor.move_left(word, extend),
+            Key::Named(NamedKey::ArrowRight) => self.editor.move_right(word, extend),
+            Key::Named(NamedKey::ArrowUp) if self.multiline => self.move_vertical(-1, extend),
+            Key::Named(NamedKey::ArrowDown) if self.multiline => self.move_vertical(1, extend),
+            Key::Named(NamedKey::Home) => self.editor.move_home(word, extend),
+            Key::Named(NamedKey::End) => self.editor.move_end(word, extend),
+            Key::Named(NamedKey::Backspace) => self.editor.delete_backward(word),
+            Key::Named(NamedKey::Delete) => self.editor.delete_forward(word),
+            Key::Named(NamedKey::Enter) if self.multiline => self.editor.insert("\n"),
+            Key::Character(character) if command => match character.to_lowercase().as_str() {
+                "a" => self.editor.select_all(),
+                "c" if self.editor.has_selection() => {
+                    let text = self.editor.get_selected_text().to_string();
+                    self.clipboard.borrow_mut().set_text(text);
+                }
+                "x" => {
+                    if let Some(text) = self.editor.cut() {
+                        self.clipboard.borrow_mut().set_text(text);
+                    }
+                }
+                "v" => {
+                    let text = self.clipboard.borrow_mut().get_text();
+                    if let Some(text) = text {
+                        self.editor.paste(&text);
+                    }
+                }
+                "z" if extend => {
+                    self.editor.redo();
+                }
+                "z" => {
+                    self.editor.undo();
+                }
+                "y" => {
+                    self.editor.redo();
+                }
+                _ => return EventResult::Ignored,
+            },
+            _ => return EventResult::Ignored,
+        }
+        self.changed();
+        return EventResult::Handled;
+    }
+
+    // keeps the x position of the caret, past the first or last line it moves to the start or end.
+    fn move_vertical(&mut self, lines: isize, extend: bool) {
+        let caret = self.editor.get_caret();
+        let (line, x) = self.get_position(self.editor.get_text(), caret);
+        let line = line as isize + lines;
+        let offset = match line {
+            line if line < 0 => 0,
+            line if line as usize >= self.get_line_count() => self.editor.get_text().len(),
+            line => self.get_offset_in_line(line as usize, x),
+        };
+        self.editor.set_caret(offset, extend);
+    }
+
+    fn get_line_height(&self) -> f32 {
+        return self.font.line_height(self.size);
+    }
+
+    fn get_line_count(&self) -> usize {
+        return self.editor.get_text().split('\n').count();
+    }
+
+    // top-left corner of the first line, without the scroll offset.
+    fn get_origin(&self, rect: Rect) -> [f32; 2] {
+        let top = match self.multiline {
+            true => rect.top() as f32 + PADDING,
+            false => {
+                rect.top() as f32 + ((rect.height() as f32 - self.get_line_height()) / 2.0).round()
+            }
+        };
+        return [rect.left() as f32 + PADDING, top];
+    }
+
+    fn get_max_scroll(&self) -> f32 {
+        let Some(rect) = self.rect else {
+            return 0.0;
+        };
+        let height = self.get_line_count() as f32 * self.get_line_height();
+        return (height - rect.height() as f32 + 2.0 * PADDING).max(0.0);
+    }
+
+    // line and x position of an offset of the text.
+    fn get_position(&self, text: &str, offset: usize) -> (usize, f32) {
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let x = self.font.measure(&before[line_start..], self.size)[0];
+        return (before.matches('\n').count(), x);
+    }
+
+    // offset of the character boundary closest to x in a line.
+    fn get_offset_in_line(&self, line: usize, x: f32) -> usize {
+        let text = self.editor.get_text();
+        let start = text
+            .split_inclusive('\n')
+            .take(line)
+            .map(|line| line.len())
+            .sum::<usize>();
+        let content = text[start..].split('\n').next().unwrap_or("");
+        let glyphs = self.font.layout(content, self.size);
+        let width = self.font.measure(content, self.size)[0];
+        for (index, (offset, _)) in content.char_indices().enumerate() {
+            let left = glyphs[index].x;
+            let right = glyphs.get(index + 1).map_or(width, |glyph| glyph.x);
+            if x < (left + right) / 2.0 {
+                return start + offset;
+            }
+        }
+        return start + content.len();
+    }
+
+    fn get_offset_at(&self, position: [f32; 2]) -> usize {
+        let Some(rect) = self.rect else {
+            return 0;
+        };
+        let origin = self.get_origin(rect);
+        let y = position[1] - origin[1] + self.scroll[1];
+        let line = (y / self.get_line_height()).max(0.0) as usize;
+        let line = line.min(self.get_line_count() - 1);
+        return self.get_offset_in_line(line, position[0] - origin[0] + self.scroll[0]);
+    }
+
+    fn scroll_to_caret(&mut self) {
+        let Some(rect) = self.rect else {
+            return;
+        };
+        let (text, caret, _) = self.get_display();
+        let (line, x) = self.get_position(&text, caret);
+        let line_height = self.get_line_height();
+        let width = (rect.width() as f32 - 2.0 * PADDING - CARET_WIDTH).max(0.0);
+        self.scroll[0] = self.scroll[0].clamp((x - width).max(0.0), x);
+        if self.multiline {
+            let height = (rect.height() as f32 - 2.0 * PADDING - line_height).max(0.0);
+            let y = line as f32 * line_height;
+            self.scroll[1] = self.scroll[1].clamp((y - height).max(0.0), y);
+        }
+    }
+
+    // the text with the preedit at the caret, the caret in it and the range of the preedit.
+    fn get_display(&self) -> (String, usize, Option<Range<usize>>) {
+        let text = self.editor.get_text();
+        let caret = self.editor.get_caret();
+        return match self.editor.get_preedit() {
+            Some((preedit, cursor)) => {
+                let display = format!("{}{}{}", &text[..caret], preedit, &text[caret..]);
+                let cursor = cursor.map_or(preedit.len(), |(_, end)| end.min(preedit.len()));
+                (display, caret + cursor, Some(caret..caret + preedit.len()))
+            }
+            None => (text.to_string(), caret, None),
+        };
+    }
+
+    fn render(&self, context: &mut Context) {
+        let Some(rect) = self.rect else {
+            return;
+        };
+        let (left, top) = (rect.left() as f32, rect.top() as f32);
+        let (width, height) = (rect.width() as f32, rect.height() as f32);
+        let line_height = self.get_line_height();
+        let origin = self.get_origin(rect);
+        let [x, y] = [origin[0] - self.scroll[0], origin[1] - self.scroll[1]];
+
+        let (text, caret, preedit) = self.get_display();
+        // the selection is hidden while composing, the composition replaces it.
+        let selection = match preedit {
+            None if self.editor.has_selection() => Some(self.editor.get_selection()),
+            _ => None,
+        };
+        let get_width = |text: &str| self.font.measure(text, self.size)[0];
+
+        let layer = context.get_layer();
+        if self.background_color[3] > 0.0 {
+            layer.push_rect(RectInstance::fill(
+                [left, top],
+                [width, height],
+                [0.0; 4],
+                self.background_color,
+            ));
+        }
+        layer.push_clip(Clip::new([left, top], [width, height]));
+        let mut start = 0;
+        for (index, line) in text.split('\n').enumerate() {
+            let end = start + line.len();
+            let line_y = y + index as f32 * line_height;
+            if line_y + line_height > top && line_y < top + height {
+                // a selected line break is drawn as a space.
+                if let Some(selection) = &selection {
+                    if selection.start <= end && selection.end > start {
+                        let from = selection.start.max(start) - start;
+                        let to = selection.end.min(end) - start;
+                        let mut right = get_width(&line[..to]);
+                        if selection.end > end {
+                            right += get_width(" ");
+                        }
+                        let left = get_width(&line[..from]);
+                        layer.push_rect(RectInstance::fill(
+                            [x + left, line_y],
+                            [right - left, line_height],
+                            [0.0; 4],
+                            self.selection_color,
+                        ));
+                    }
+                }
+                if !line.is_empty() {
+                    layer.push_text(Text::new(
+                        self.font.clone(),
+                        line,
+                        [x, line_y],
+                        self.size,
+                        self.color,
+                    ));
+                }
+                // the composed text is underlined.
+                if let Some(preedit) = &preedit {
+                    if preedit.start < end && preedit.end > start {
+                        let from = get_width(&line[..preedit.start.max(start) - start]);
+                        let to = get_width(&line[..preedit.end.min(end) - start]);
+                        layer.push_rect(RectInstance::fill(
+                            [x + from, line_y + line_height - 1.0],
+                            [to - from, 1.0],
+                            [0.0; 4],
+                            self.color,
+                        ));
+                    }
+                }
+            }
+            start = end + 1;
+        }
+        if self.focused {
+            let (line, caret_x) = self.get_position(&text, caret);
+            layer.push_rect(RectInstance::fill(
+                [x + caret_x, y + line as f32 * line_height],
+                [CARET_WIDTH, line_height],
+                [0.0; 4],
+                self.color,
+            ));
+        }
+        layer.pop_clip();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use atoz_renderer::pipeline::text::Font;
+    use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+    use super::{TextArea, TextInput};
+    use crate::{
+        clipboard::LocalClipboard,
+        event::{Event, EventPhase, EventResult, MouseButton},
+        layout::{Alignment, Constraint, Rect},
+        widget::{Focus, Widget},
+    };
+
+    fn press(widget: &mut dyn Widget, key: Key, modifiers: ModifiersState) -> EventResult {
+        let event = Event::KeyPress {
+            key,
+            modifiers,
+            repeat: false,
+        };
+        return widget.event(&event, EventPhase::Target);
+    }
+
+    fn character(text: &str) -> Key {
+        return Key::Character(text.into());
+    }
+
+    #[test]
+    fn text_input_events() {
+        let font = Font::from_bytes(include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../atoz-renderer/src/test/assets/DejaVuSansMono.ttf"
+        )))
+        .unwrap();
+        let clipboard = Rc::new(RefCell::new(LocalClipboard::default()));
+        // a monospace character is about 6 pixels wide at size 10.
+        let mut input = TextInput::new(
+            Constraint::percent(100, 1000000, 0),
+            Alignment::Start,
+            font.clone(),
+            "",
+        )
+        .set_size(10.0)
+        .set_clipboard(clipboard.clone());
+        input.layout(Some(Rect::new(0, 0, 200, 20)));
+
+        // keyboard events are ignored until focused.
+        let text = Event::Text("hello".into());
+        assert_eq!(input.event(&text, EventPhase::Target), EventResult::Ignored);
+        input.focus_changed(Focus::Focused);
+        assert_eq!(input.event(&text, EventPhase::Target), EventResult::Handled);
+        // the longer text asks for a layout.
+        assert!(input.needs_layout());
+        input.layout(Some(Rect::new(0, 0, 200, 20)));
+        assert!(!input.needs_layout());
+
+        let control = ModifiersState::CONTROL;
+        let word = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        press(&mut input, Key::Named(NamedKey::ArrowLeft), word);
+        assert_eq!(input.get_editor().get_selected_text(), "hello");
+        press(&mut input, character("c"), control);
+        press(
+            &mut input,
+            Key::Named(NamedKey::End),
+            ModifiersState::empty(),
+        );
+        input.event(&Event::Text(" ".into()), EventPhase::Target);
+        press(&mut input, character("v"), control);
+        assert_eq!(input.get_text(), "hello hello");
+        press(&mut input, character("z"), control);
+        assert_eq!(input.get_text(), "hello ");
+
+        // enter is left to the parents, a composition is inserted once committed.
+        let enter = Key::Named(NamedKey::Enter);
+        assert_eq!(
+            press(&mut input, enter, ModifiersState::empty()),
+            EventResult::Ignored
+        );
+        let preedit = Event::Preedit {
+            text: "wo".into(),
+            cursor: Some((2, 2)),
+        };
+        input.event(&preedit, EventPhase::Target);
+        assert_eq!(input.get_text(), "hello ");
+        input.event(&Event::Text("world".into()), EventPhase::Target);
+        assert_eq!(input.get_text(), "hello world");
+        assert!(input.get_editor().get_preedit().is_none());
+
+        // a press places the caret, dragging selects.
+        let click = Event::MousePress {
+            position: [4.0 + 6.0 * 6.0 + 1.0, 10.0],
+            button: MouseButton::Left,
+            modifiers: ModifiersState::empty(),
+        };
+        input.event(&click, EventPhase::Target);
+        assert_eq!(input.get_editor().get_caret(), 6);
+        let drag = Event::MouseMove {
+            position: [190.0, 10.0],
+        };
+        input.event(&drag, EventPhase::Target);
+        assert_eq!(input.get_editor().get_selected_text(), "world");
+
+        let mut area = TextArea::new(
+            Constraint::percent(100, 1000000, 0),
+            Alignment::Start,
+            font,
+            "first line\nsecond",
+        )
+        .set_size(10.0)
+        .set_clipboard(clipboard);
+        area.layout(Some(Rect::new(0, 0, 200, 100)));
+        area.focus_changed(Focus::Focused);
+
+        // up keeps the column, enter breaks the line.
+        let none = ModifiersState::empty();
+        press(&mut area, Key::Named(NamedKey::ArrowUp), none);
+        assert_eq!(area.get_editor().get_caret(), 6);
+        press(&mut area, Key::Named(NamedKey::ArrowLeft), none);
+        press(&mut area, Key::Named(NamedKey::Enter), none);
+        assert_eq!(area.get_text(), "first\n line\nsecond");
+    }
+}
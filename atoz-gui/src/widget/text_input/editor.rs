@@ -0,0 +1,414 @@
+use std::ops::Range;
+
+const UNDO_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    text: String,
+    caret: usize,
+    anchor: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+// text being edited, with a caret and a selection between the anchor and the caret.
+// offsets are byte offsets on character boundaries.
+#[derive(Debug)]
+pub struct TextEditor {
+    text: String,
+    caret: usize,
+    anchor: usize,
+    multiline: bool,
+    // text an input method is composing at the caret, and the cursor inside of it.
+    preedit: Option<(String, Option<(usize, usize)>)>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: EditKind,
+}
+
+impl TextEditor {
+    // a single line editor drops the line breaks of inserted text.
+    pub fn new(text: &str, multiline: bool) -> Self {
+        let text = match multiline {
+            true => text.to_string(),
+            false => text.replace(['\r', '\n'], ""),
+        };
+        return Self {
+            caret: text.len(),
+            anchor: text.len(),
+            text,
+            multiline,
+            preedit: None,
+            undo: vec![],
+            redo: vec![],
+            last_edit: EditKind::Other,
+        };
+    }
+
+    pub fn get_text(&self) -> &str {
+        return &self.text;
+    }
+
+    pub fn get_caret(&self) -> usize {
+        return self.caret;
+    }
+
+    pub fn get_selection(&self) -> Range<usize> {
+        return self.caret.min(self.anchor)..self.caret.max(self.anchor);
+    }
+
+    pub fn has_selection(&self) -> bool {
+        return self.caret != self.anchor;
+    }
+
+    pub fn get_selected_text(&self) -> &str {
+        return &self.text[self.get_selection()];
+    }
+
+    pub fn get_preedit(&self) -> Option<&(String, Option<(usize, usize)>)> {
+        return self.preedit.as_ref();
+    }
+
+    pub fn set_preedit(&mut self, preedit: Option<(String, Option<(usize, usize)>)>) {
+        self.preedit = preedit.filter(|(text, _)| !text.is_empty());
+    }
+
+    // extend keeps the anchor, which selects from it to the new caret.
+    pub fn set_caret(&mut self, offset: usize, extend: bool) {
+        self.caret = self.clamp(offset);
+        if !extend {
+            self.anchor = self.caret;
+        }
+        self.last_edit = EditKind::Other;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.text.len();
+        self.last_edit = EditKind::Other;
+    }
+
+    // moves one character, or collapses the selection to its side.
+    pub fn move_left(&mut self, word: bool, extend: bool) {
+        let offset = match (self.has_selection() && !extend, word) {
+            (true, _) => self.get_selection().start,
+            (false, true) => self.get_word_start(self.caret),
+            (false, false) => self.get_previous(self.caret),
+        };
+        self.set_caret(offset, extend);
+    }
+
+    pub fn move_right(&mut self, word: bool, extend: bool) {
+        let offset = match (self.has_selection() && !extend, word) {
+            (true, _) => self.get_selection().end,
+            (false, true) => self.get_word_end(self.caret),
+            (false, false) => self.get_next(self.caret),
+        };
+        self.set_caret(offset, extend);
+    }
+
+    pub fn move_home(&mut self, document: bool, extend: bool) {
+        let offset = match document {
+            true => 0,
+            false => self.get_line_start(self.caret),
+        };
+        self.set_caret(offset, extend);
+    }
+
+    pub fn move_end(&mut self, document: bool, extend: bool) {
+        let offset = match document {
+            true => self.text.len(),
+            false => self.get_line_end(self.caret),
+        };
+        self.set_caret(offset, extend);
+    }
+
+    // replaces the selection. characters typed one by one are undone a word at once.
+    pub fn insert(&mut self, text: &str) {
+        let text = match self.multiline {
+            true => text.replace("\r\n", "\n"),
+            false => text.replace(['\r', '\n'], ""),
+        };
+        if text.is_empty() && !self.has_selection() {
+            return;
+        }
+        let mut chars = text.chars();
+        let typed = match (chars.next(), chars.next()) {
+            (Some(character), None) => !character.is_whitespace(),
+            _ => false,
+        };
+        let coalesce = self.last_edit == EditKind::Insert && !self.has_selection() && typed;
+        self.push_undo(coalesce);
+
+        let selection = self.get_selection();
+        self.text.replace_range(selection.clone(), &text);
+        self.caret = selection.start + text.len();
+        self.anchor = self.caret;
+        self.preedit = None;
+        self.last_edit = EditKind::Insert;
+    }
+
+    // deletes the selection, or the character or word before the caret.
+    pub fn delete_backward(&mut self, word: bool) {
+        let start = match word {
+            true => self.get_word_start(self.caret),
+            false => self.get_previous(self.caret),
+        };
+        self.delete(start..self.caret);
+    }
+
+    pub fn delete_forward(&mut self, word: bool) {
+        let end = match word {
+            true => self.get_word_end(self.caret),
+            false => self.get_next(self.caret),
+        };
+        self.delete(self.caret..end);
+    }
+
+    // removes and returns the selection. clipboard edits are their own undo step, edits
+    // around them are not merged into it.
+    pub fn cut(&mut self) -> Option<String> {
+        if !self.has_selection() {
+            return None;
+        }
+        let text = self.get_selected_text().to_string();
+        self.last_edit = EditKind::Other;
+        self.delete(self.get_selection());
+        self.last_edit = EditKind::Other;
+        return Some(text);
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        self.last_edit = EditKind::Other;
+        self.insert(text);
+        self.last_edit = EditKind::Other;
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(self.get_snapshot());
+        self.restore(snapshot);
+        return true;
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(self.get_snapshot());
+        self.restore(snapshot);
+        return true;
+    }
+
+    pub fn get_line_start(&self, offset: usize) -> usize {
+        return match self.text[..offset].rfind('\n') {
+            Some(index) => index + 1,
+            None => 0,
+        };
+    }
+
+    pub fn get_line_end(&self, offset: usize) -> usize {
+        return match self.text[offset..].find('\n') {
+            Some(index) => offset + index,
+            None => self.text.len(),
+        };
+    }
+
+    // start of the word before the offset, skipping the spaces in between.
+    pub fn get_word_start(&self, offset: usize) -> usize {
+        let mut chars = self.text[..offset].char_indices().rev().peekable();
+        while let Some((_, character)) = chars.peek() {
+            if !character.is_whitespace() {
+                break;
+            }
+            chars.next();
+        }
+        let Some((mut start, first)) = chars.next() else {
+            return 0;
+        };
+        for (index, character) in chars {
+            if get_class(character) != get_class(first) {
+                break;
+            }
+            start = index;
+        }
+        return start;
+    }
+
+    // end of the word after the offset, skipping the spaces in between.
+    pub fn get_word_end(&self, offset: usize) -> usize {
+        let mut chars = self.text[offset..]
+            .char_indices()
+            .map(|(index, character)| (offset + index, character))
+            .peekable();
+        while let Some((_, character)) = chars.peek() {
+            if !character.is_whitespace() {
+                break;
+            }
+            chars.next();
+        }
+        let Some((index, first)) = chars.next() else {
+            return self.text.len();
+        };
+        let mut end = index + first.len_utf8();
+        for (index, character) in chars {
+            if get_class(character) != get_class(first) {
+                break;
+            }
+            end = index + character.len_utf8();
+        }
+        return end;
+    }
+
+    fn delete(&mut self, range: Range<usize>) {
+        let range = match self.has_selection() {
+            true => self.get_selection(),
+            false => range,
+        };
+        if range.is_empty() {
+            return;
+        }
+        self.push_undo(self.last_edit == EditKind::Delete);
+        self.text.replace_range(range.clone(), "");
+        self.caret = range.start;
+        self.anchor = range.start;
+        self.last_edit = EditKind::Delete;
+    }
+
+    fn get_previous(&self, offset: usize) -> usize {
+        return match self.text[..offset].char_indices().next_back() {
+            Some((index, _)) => index,
+            None => 0,
+        };
+    }
+
+    fn get_next(&self, offset: usize) -> usize {
+        return match self.text[offset..].chars().next() {
+            Some(character) => offset + character.len_utf8(),
+            None => offset,
+        };
+    }
+
+    fn clamp(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        return offset;
+    }
+
+    fn get_snapshot(&self) -> Snapshot {
+        return Snapshot {
+            text: self.text.clone(),
+            caret: self.caret,
+            anchor: self.anchor,
+        };
+    }
+
+    fn push_undo(&mut self, coalesce: bool) {
+        self.redo.clear();
+        if coalesce && !self.undo.is_empty() {
+            return;
+        }
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(self.get_snapshot());
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.text = snapshot.text;
+        self.caret = snapshot.caret;
+        self.anchor = snapshot.anchor;
+        self.preedit = None;
+        self.last_edit = EditKind::Other;
+    }
+}
+
+// words are runs of alphanumeric characters or of punctuation.
+fn get_class(character: char) -> u8 {
+    return match character {
+        character if character.is_whitespace() => 0,
+        character if character.is_alphanumeric() || character == '_' => 1,
+        _ => 2,
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::TextEditor;
+
+    #[test]
+    fn text_editing() {
+        let mut editor = TextEditor::new("let value = 1;", false);
+        editor.move_left(true, false);
+        assert_eq!(editor.get_caret(), 13);
+        editor.move_left(true, false);
+        editor.move_left(true, true);
+        assert_eq!(editor.get_selected_text(), "= ");
+        editor.move_home(false, false);
+        editor.move_right(true, true);
+        assert_eq!(editor.get_selected_text(), "let");
+
+        // typing replaces the selection and is undone at once.
+        for character in ["c", "o", "n", "s", "t"] {
+            editor.insert(character);
+        }
+        assert_eq!(editor.get_text(), "const value = 1;");
+        editor.delete_forward(true);
+        assert_eq!(editor.get_text(), "const = 1;");
+        assert!(editor.undo());
+        assert_eq!(editor.get_text(), "const value = 1;");
+        assert!(editor.undo());
+        assert_eq!(editor.get_text(), "let value = 1;");
+        assert_eq!(editor.get_selected_text(), "let");
+        assert!(editor.redo());
+        assert_eq!(editor.get_text(), "const value = 1;");
+
+        editor.select_all();
+        assert_eq!(editor.cut().as_deref(), Some("const value = 1;"));
+        editor.insert("a\nb é");
+        assert_eq!(editor.get_text(), "ab é");
+        editor.delete_backward(false);
+        assert_eq!(editor.get_text(), "ab ");
+
+        // a cut and a paste are undone on their own, not with the edits around them.
+        let mut editor = TextEditor::new("abc", false);
+        editor.set_caret(3, false);
+        editor.delete_backward(false);
+        editor.set_caret(0, false);
+        editor.set_caret(1, true);
+        assert_eq!(editor.cut().as_deref(), Some("a"));
+        editor.delete_forward(false);
+        assert_eq!(editor.get_text(), "");
+        assert!(editor.undo());
+        assert_eq!(editor.get_text(), "b");
+        assert!(editor.undo());
+        assert_eq!(editor.get_text(), "ab");
+        editor.set_caret(2, false);
+        editor.insert("c");
+        editor.paste("d");
+        editor.insert("e");
+        assert_eq!(editor.get_text(), "abcde");
+        assert!(editor.undo());
+        assert_eq!(editor.get_text(), "abcd");
+        assert!(editor.undo());
+        assert_eq!(editor.get_text(), "abc");
+
+        let mut editor = TextEditor::new("one\ntwo three", true);
+        editor.move_home(false, false);
+        assert_eq!(editor.get_caret(), 4);
+        editor.move_end(false, true);
+        assert_eq!(editor.get_selected_text(), "two three");
+        editor.set_caret(6, false);
+        editor.delete_backward(true);
+        assert_eq!(editor.get_text(), "one\no three");
+    }
+}
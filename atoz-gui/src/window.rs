@@ -1,10 +1,33 @@
-use winit::{dpi::PhysicalSize, window::WindowId};
+use std::time::Instant;
 
-use crate::{context::Context, layout::Rect, widget::Widget};
+use atoz_renderer::{
+    profiler::{Phase, Profiler},
+    renderer::RenderError,
+};
+use winit::{
+    dpi::{LogicalSize, PhysicalSize},
+    error::EventLoopError,
+    event::WindowEvent,
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowId,
+};
+
+use crate::{
+    context::Context,
+    event::{Event, EventDispatcher, EventResult},
+    focus::FocusManager,
+    layout::Rect,
+    scheduler::{FrameScheduler, Schedule},
+    widget::Widget,
+};
 
 pub struct Window {
     context: Context,
     child: Box<dyn Widget>,
+    layout_size: Option<(u32, u32)>,
+    scheduler: FrameScheduler,
+    events: EventDispatcher,
+    focus: FocusManager,
 }
 
 impl Window {
@@ -15,46 +38,215 @@ impl Window {
     ) -> Self {
         if let Some(decoration) = decoration {
             // TODO : add hide frame
-            context.resize(PhysicalSize::new(decoration.size.0, decoration.size.1));
-            context.set_min_size(PhysicalSize::new(
+            let scale_factor = context.get_scale_factor();
+            context.resize(
+                LogicalSize::new(decoration.size.0, decoration.size.1).to_physical(scale_factor),
+            );
+            context.set_min_size(LogicalSize::new(
                 decoration.min_size.0,
                 decoration.min_size.1,
             ));
-            context.set_max_size(PhysicalSize::new(
+            context.set_max_size(LogicalSize::new(
                 decoration.max_size.0,
                 decoration.max_size.1,
             ));
         }
-        return Self { context, child };
+        let scheduler = FrameScheduler::new(context.get_config().frame_rate);
+        return Self {
+            context,
+            child,
+            layout_size: None,
+            scheduler,
+            events: EventDispatcher::new(),
+            focus: FocusManager::new(),
+        };
     }
 
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
         self.context.resize(size);
+        self.scheduler.request_frame();
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.context.set_scale_factor(scale_factor);
+        self.scheduler.request_frame();
     }
 
-    pub fn get_window_id(&self) -> WindowId {
+    // None for a headless context.
+    pub fn get_window_id(&self) -> Option<WindowId> {
         return self.context.get_window_id();
     }
 
-    pub fn render_request(&self) {
-        self.context.request_redraw();
+    // input from the platform, delivered through the widget tree. a handled event or a focus
+    // change requests a frame, the widgets report what changed with collect_damage.
+    pub fn window_event(&mut self, event: &WindowEvent) -> EventResult {
+        let scale_factor = self.context.get_scale_factor();
+        let focus = self.focus.get_focus().map(|path| path.to_vec());
+        let result =
+            self.events
+                .window_event(self.child.as_mut(), &mut self.focus, event, scale_factor);
+        if result == EventResult::Handled || self.focus.get_focus() != focus.as_deref() {
+            self.scheduler.request_frame();
+        }
+        return result;
+    }
+
+    // delivers an event that did not come from the platform, like a synthesized click.
+    pub fn dispatch(&mut self, event: Event) -> EventResult {
+        let focus = self.focus.get_focus().map(|path| path.to_vec());
+        let result = self
+            .events
+            .dispatch(self.child.as_mut(), &mut self.focus, event);
+        if result == EventResult::Handled || self.focus.get_focus() != focus.as_deref() {
+            self.scheduler.request_frame();
+        }
+        return result;
+    }
+
+    // path of child indices to the focused widget, None when nothing is focused.
+    pub fn get_focus(&self) -> Option<&[usize]> {
+        return self.focus.get_focus();
+    }
+
+    // returns false when the path does not lead to a focusable widget.
+    pub fn set_focus(&mut self, path: Option<Vec<usize>>) -> bool {
+        let changed = self.focus.set_focus(self.child.as_mut(), path);
+        if changed {
+            self.scheduler.request_frame();
+        }
+        return changed;
+    }
+
+    pub fn focus_next(&mut self) -> bool {
+        let changed = self.focus.focus_next(self.child.as_mut());
+        if changed {
+            self.scheduler.request_frame();
+        }
+        return changed;
+    }
+
+    pub fn focus_previous(&mut self) -> bool {
+        let changed = self.focus.focus_previous(self.child.as_mut());
+        if changed {
+            self.scheduler.request_frame();
+        }
+        return changed;
+    }
+
+    // asks for a frame, drawn once the frame rate of the context config allows it.
+    pub fn render_request(&mut self) {
+        self.scheduler.request_frame();
+    }
+
+    // control flow for the event loop when it is about to wait. a redraw is requested from
+    // the window when a frame is due, otherwise the loop sleeps until the next frame or event.
+    pub fn schedule(&mut self, now: Instant) -> ControlFlow {
+        return match self.scheduler.schedule(now) {
+            Schedule::Redraw => {
+                self.context.request_redraw();
+                ControlFlow::Wait
+            }
+            Schedule::WaitUntil(instant) => ControlFlow::WaitUntil(instant),
+            Schedule::Wait => ControlFlow::Wait,
+        };
     }
 
-    pub fn render(&mut self) {
-        let width = self.context.get_config().width;
-        let height = self.context.get_config().height;
+    // runs the event loop of a single window until it is closed. render errors are logged
+    // and the frame is requested again.
+    pub fn run(mut self, event_loop: EventLoop<()>) -> Result<(), EventLoopError> {
+        return event_loop.run(move |event, elwt| match event {
+            winit::event::Event::WindowEvent { window_id, event }
+                if Some(window_id) == self.get_window_id() =>
+            {
+                match event {
+                    WindowEvent::CloseRequested => elwt.exit(),
+                    WindowEvent::Resized(size) => self.resize(size),
+                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        self.set_scale_factor(scale_factor)
+                    }
+                    WindowEvent::RedrawRequested => {
+                        if let Err(error) = self.render() {
+                            log::error!("{}", error);
+                            self.render_request();
+                        }
+                    }
+                    event => {
+                        self.window_event(&event);
+                    }
+                }
+            }
+            winit::event::Event::AboutToWait => {
+                elwt.set_control_flow(self.schedule(Instant::now()))
+            }
+            _ => {}
+        });
+    }
 
-        self.context.clear_layers();
+    // the tree is laid out again when the size changed or a widget needs it, and nothing is
+    // drawn without damage. widgets outside of the damage keep the layers of the last frame.
+    pub fn render(&mut self) -> Result<(), RenderError> {
+        let start = Instant::now();
+        let (width, height) = self.context.get_size();
+        let rect = Rect::new(0, 0, width as _, height as _);
+        if self.layout_size != Some((width, height)) {
+            self.child.layout(Some(rect));
+            self.layout_size = Some((width, height));
+            self.context.invalidate_all();
+        } else if self.child.needs_layout() {
+            // only the widgets that moved or resized are redrawn.
+            let mut before = vec![];
+            collect_rects(self.child.as_ref(), &mut before);
+            self.child.layout(Some(rect));
+            let mut after = vec![];
+            collect_rects(self.child.as_ref(), &mut after);
+            if before.len() != after.len() {
+                self.context.invalidate_all();
+            }
+            before
+                .into_iter()
+                .zip(after)
+                .filter(|(before, after)| before != after)
+                .flat_map(|(before, after)| [before, after])
+                .flatten()
+                .for_each(|rect| self.context.invalidate(rect));
+        }
 
-        self.child
-            .layout(Some(Rect::new(0, 0, width as _, height as _)));
+        let mut damage = vec![];
+        self.child.collect_damage(&mut damage);
+        damage
+            .into_iter()
+            .for_each(|rect| self.context.invalidate(rect));
+        self.scheduler.frame_rendered(Instant::now());
+        if self.child.is_animating() {
+            self.scheduler.request_frame();
+        }
+        if !self.context.is_damaged() {
+            return Ok(());
+        }
+        self.get_profiler_mut().record(Phase::Layout, start);
 
-        self.child.render(&mut self.context);
+        let start = Instant::now();
+        self.context.begin_frame();
+        self.context.render_child(0, self.child.as_ref());
+        self.get_profiler_mut().record(Phase::LayerBuild, start);
+        return self.context.render();
+    }
 
-        self.context.render();
+    fn get_profiler_mut(&mut self) -> &mut Profiler {
+        return self.context.get_renderer_mut().get_profiler_mut();
     }
 }
 
+// laid out rects of the tree in preorder. scroll views damage their view on layout, so the
+// rects of their content are not moved by the offset.
+fn collect_rects(widget: &dyn Widget, rects: &mut Vec<Option<Rect>>) {
+    rects.push(widget.get_rect());
+    widget
+        .get_children()
+        .iter()
+        .for_each(|child| collect_rects(child.as_ref(), rects));
+}
+
 pub struct WindowDecoration {
     pub frame: bool,
     pub size: (u32, u32),
@@ -0,0 +1,346 @@
+use std::mem;
+
+use bytemuck::Pod;
+use wgpu::{BindGroup, Buffer, Device, Queue};
+
+use crate::{
+    layer::{Layer, LayerBuffer},
+    layouts::BindGroupLayouts,
+    paint::{Gradient, GradientData},
+    pipeline::text::GlyphAtlas,
+    state::DrawState,
+    viewport::Viewport,
+};
+
+// vertex buffer that lives across frames and only reallocates when the data outgrows it.
+#[derive(Debug)]
+pub struct InstanceBuffer {
+    label: &'static str,
+    buffer: Buffer,
+    count: u32,
+}
+
+impl InstanceBuffer {
+    const MIN_CAPACITY: wgpu::BufferAddress = 1024;
+
+    pub fn new(device: &Device, label: &'static str) -> Self {
+        return Self {
+            label,
+            buffer: Self::create_buffer(device, label, Self::MIN_CAPACITY),
+            count: 0,
+        };
+    }
+
+    fn create_buffer(device: &Device, label: &'static str, size: wgpu::BufferAddress) -> Buffer {
+        return device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+    }
+
+    pub fn write<T: Pod>(&mut self, device: &Device, queue: &Queue, data: &[T]) {
+        self.count = data.len() as u32;
+        if data.is_empty() {
+            return;
+        }
+
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        let size = bytes.len() as wgpu::BufferAddress;
+        if size > self.buffer.size() {
+            self.buffer = Self::create_buffer(
+                device,
+                self.label,
+                size.next_power_of_two().max(Self::MIN_CAPACITY),
+            );
+        }
+        queue.write_buffer(&self.buffer, 0, bytes);
+    }
+
+    pub fn get_buffer(&self) -> &Buffer {
+        return &self.buffer;
+    }
+
+    pub fn get_count(&self) -> u32 {
+        return self.count;
+    }
+
+    pub fn get_capacity(&self) -> wgpu::BufferAddress {
+        return self.buffer.size();
+    }
+}
+
+// uniform buffer holding one DrawState per slot, aligned for dynamic offsets.
+#[derive(Debug)]
+pub struct StateBuffer {
+    buffer: Buffer,
+    stride: wgpu::BufferAddress,
+    states: Vec<DrawState>,
+}
+
+impl StateBuffer {
+    const MIN_SLOTS: wgpu::BufferAddress = 16;
+
+    pub fn new(device: &Device) -> Self {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress;
+        let stride =
+            (mem::size_of::<DrawState>() as wgpu::BufferAddress).div_ceil(alignment) * alignment;
+        return Self {
+            buffer: Self::create_buffer(device, stride * Self::MIN_SLOTS),
+            stride,
+            states: vec![],
+        };
+    }
+
+    fn create_buffer(device: &Device, size: wgpu::BufferAddress) -> Buffer {
+        return device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("state.buffer"),
+            size,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    // returns the dynamic offset of the state. repeated states share a slot.
+    pub fn push(&mut self, state: DrawState) -> u32 {
+        if self.states.last() != Some(&state) {
+            self.states.push(state);
+        }
+        return ((self.states.len() - 1) as wgpu::BufferAddress * self.stride) as u32;
+    }
+
+    // returns true when the buffer was reallocated and bind groups need to be recreated.
+    pub fn write(&mut self, device: &Device, queue: &Queue) -> bool {
+        let size = self.states.len() as wgpu::BufferAddress * self.stride;
+        let grown = size > self.buffer.size();
+        if grown {
+            self.buffer = Self::create_buffer(device, size.next_power_of_two());
+        }
+
+        let mut data = vec![0u8; size as usize];
+        self.states.iter().enumerate().for_each(|(index, state)| {
+            let offset = index * self.stride as usize;
+            data[offset..offset + mem::size_of::<DrawState>()]
+                .copy_from_slice(bytemuck::bytes_of(state));
+        });
+        if !data.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &data);
+        }
+        return grown;
+    }
+
+    pub fn get_buffer(&self) -> &Buffer {
+        return &self.buffer;
+    }
+}
+
+// storage buffer holding the gradients of every layer of a frame.
+#[derive(Debug)]
+pub struct PaintBuffer {
+    buffer: Buffer,
+    gradients: Vec<GradientData>,
+}
+
+impl PaintBuffer {
+    const MIN_GRADIENTS: wgpu::BufferAddress = 16;
+
+    pub fn new(device: &Device) -> Self {
+        return Self {
+            buffer: Self::create_buffer(
+                device,
+                mem::size_of::<GradientData>() as wgpu::BufferAddress * Self::MIN_GRADIENTS,
+            ),
+            gradients: vec![],
+        };
+    }
+
+    fn create_buffer(device: &Device, size: wgpu::BufferAddress) -> Buffer {
+        return device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("paint.buffer"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.gradients.clear();
+    }
+
+    // returns the index of the first pushed gradient.
+    pub fn push(&mut self, gradients: &[Gradient]) -> u32 {
+        let offset = self.gradients.len() as u32;
+        self.gradients
+            .extend(gradients.iter().map(|gradient| gradient.to_data()));
+        return offset;
+    }
+
+    // returns true when the buffer was reallocated and bind groups need to be recreated.
+    pub fn write(&mut self, device: &Device, queue: &Queue) -> bool {
+        let bytes: &[u8] = bytemuck::cast_slice(&self.gradients);
+        let size = bytes.len() as wgpu::BufferAddress;
+        let grown = size > self.buffer.size();
+        if grown {
+            self.buffer = Self::create_buffer(device, size.next_power_of_two());
+        }
+        if !bytes.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytes);
+        }
+        return grown;
+    }
+
+    pub fn get_buffer(&self) -> &Buffer {
+        return &self.buffer;
+    }
+}
+
+// gpu buffers retained by the renderer, one LayerBuffer per layer index.
+#[derive(Debug)]
+pub struct BufferManager {
+    viewport: Buffer,
+    states: StateBuffer,
+    paints: PaintBuffer,
+    viewport_group: BindGroup,
+    layouts: BindGroupLayouts,
+    layers: Vec<LayerBuffer>,
+    layer_count: usize,
+}
+
+impl BufferManager {
+    pub fn new(device: &Device, layouts: &BindGroupLayouts) -> Self {
+        let viewport = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("viewport.buffer"),
+            size: mem::size_of::<Viewport>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let states = StateBuffer::new(device);
+        let paints = PaintBuffer::new(device);
+        let viewport_group =
+            Self::create_viewport_group(device, layouts, &viewport, &states, &paints);
+        return Self {
+            viewport,
+            states,
+            paints,
+            viewport_group,
+            layouts: layouts.clone(),
+            layers: vec![],
+            layer_count: 0,
+        };
+    }
+
+    fn create_viewport_group(
+        device: &Device,
+        layouts: &BindGroupLayouts,
+        viewport: &Buffer,
+        states: &StateBuffer,
+        paints: &PaintBuffer,
+    ) -> BindGroup {
+        return device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("viewport.group"),
+            layout: &layouts.viewport,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: viewport.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: states.get_buffer(),
+                        offset: 0,
+                        size: wgpu::BufferSize::new(
+                            mem::size_of::<DrawState>() as wgpu::BufferAddress
+                        ),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: paints.get_buffer().as_entire_binding(),
+                },
+            ],
+        });
+    }
+
+    pub fn update(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        viewport: Viewport,
+        layers: &[&Layer],
+        atlas: &mut GlyphAtlas,
+    ) {
+        queue.write_buffer(&self.viewport, 0, bytemuck::cast_slice(&[viewport]));
+
+        while self.layers.len() < layers.len() {
+            self.layers.push(LayerBuffer::new(device));
+        }
+        self.layer_count = layers.len();
+
+        self.states.clear();
+        self.paints.clear();
+        layers
+            .iter()
+            .zip(self.layers.iter_mut())
+            .for_each(|(layer, buffer)| {
+                layer.write_buffers(
+                    device,
+                    queue,
+                    buffer,
+                    atlas,
+                    &mut self.states,
+                    &mut self.paints,
+                )
+            });
+
+        let states_grown = self.states.write(device, queue);
+        let paints_grown = self.paints.write(device, queue);
+        if states_grown || paints_grown {
+            self.viewport_group = Self::create_viewport_group(
+                device,
+                &self.layouts,
+                &self.viewport,
+                &self.states,
+                &self.paints,
+            );
+        }
+    }
+
+    pub fn get_viewport_group(&self) -> &BindGroup {
+        return &self.viewport_group;
+    }
+
+    pub fn get_layer_buffers(&self) -> &[LayerBuffer] {
+        return &self.layers[..self.layer_count];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::InstanceBuffer;
+    use crate::tests::headless_renderer;
+
+    #[test]
+    fn instance_buffer_grow() {
+        let renderer = headless_renderer();
+        let (device, queue) = (renderer.get_device(), renderer.get_queue());
+
+        let mut buffer = InstanceBuffer::new(device, "test.buffer");
+        let capacity = buffer.get_capacity();
+        buffer.write(device, queue, &[0.0f32; 16]);
+        assert_eq!(buffer.get_capacity(), capacity);
+        assert_eq!(buffer.get_count(), 16);
+
+        buffer.write(device, queue, &[0.0f32; 1000]);
+        assert_eq!(buffer.get_capacity(), 4096);
+
+        buffer.write::<f32>(device, queue, &[]);
+        assert_eq!(buffer.get_capacity(), 4096);
+        assert_eq!(buffer.get_count(), 0);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::transform::Transform;
+
+// clip region in pixels.
+// radius: [top-left, bottom-left, bottom-right, top-right]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clip {
+    pub start: [f32; 2],
+    pub size: [f32; 2],
+    pub radius: [f32; 4],
+}
+
+impl Clip {
+    const UNBOUNDED: f32 = 1.0e5;
+
+    pub fn new(start: [f32; 2], size: [f32; 2]) -> Self {
+        return Self {
+            start,
+            size,
+            radius: [0.0, 0.0, 0.0, 0.0],
+        };
+    }
+
+    // clips with rounded corners, evaluated as a signed distance in the fragment shaders.
+    pub fn rounded(start: [f32; 2], size: [f32; 2], radius: [f32; 4]) -> Self {
+        return Self {
+            start,
+            size,
+            radius,
+        };
+    }
+
+    pub fn unbounded() -> Self {
+        return Self::new(
+            [-Self::UNBOUNDED, -Self::UNBOUNDED],
+            [Self::UNBOUNDED * 2.0, Self::UNBOUNDED * 2.0],
+        );
+    }
+
+    pub fn is_rounded(&self) -> bool {
+        return self.radius.iter().any(|radius| *radius > 0.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.size[0] <= 0.0 || self.size[1] <= 0.0;
+    }
+
+    // a rounded clip cannot be intersected exactly, so only the innermost rounded corners are kept.
+    pub fn intersect(&self, other: &Clip) -> Clip {
+        let left = self.start[0].max(other.start[0]);
+        let top = self.start[1].max(other.start[1]);
+        let right = (self.start[0] + self.size[0]).min(other.start[0] + other.size[0]);
+        let bottom = (self.start[1] + self.size[1]).min(other.start[1] + other.size[1]);
+        let radius = if other.is_rounded() {
+            other.radius
+        } else {
+            self.radius
+        };
+        return Clip::rounded(
+            [left, top],
+            [(right - left).max(0.0), (bottom - top).max(0.0)],
+            radius,
+        );
+    }
+
+    // screen space bounds of a clip pushed under a transform. rotated clips become their bounding box.
+    pub fn transform(&self, transform: &Transform) -> Clip {
+        let (start, size) = transform.apply_rect(self.start, self.size);
+        let scale = transform.get_determinant().abs().sqrt();
+        return Clip::rounded(start, size, self.radius.map(|radius| radius * scale));
+    }
+
+    // logical to physical pixels.
+    pub fn scale(&self, scale_factor: f32) -> Clip {
+        return Clip::rounded(
+            self.start.map(|value| value * scale_factor),
+            self.size.map(|value| value * scale_factor),
+            self.radius.map(|value| value * scale_factor),
+        );
+    }
+
+    // [x, y, width, height] of the scissor rect inside a target, None when nothing is visible.
+    pub fn get_scissor(&self, width: u32, height: u32) -> Option<[u32; 4]> {
+        let left = self.start[0].max(0.0).floor() as u32;
+        let top = self.start[1].max(0.0).floor() as u32;
+        let right = (self.start[0] + self.size[0])
+            .min(width as f32)
+            .ceil()
+            .max(0.0) as u32;
+        let bottom = (self.start[1] + self.size[1])
+            .min(height as f32)
+            .ceil()
+            .max(0.0) as u32;
+        if right <= left || bottom <= top {
+            return None;
+        }
+        return Some([left, top, right - left, bottom - top]);
+    }
+}
+
+// nested clips. every pushed clip is intersected with the current one.
+#[derive(Clone, Debug)]
+pub struct ClipStack {
+    stack: Vec<Clip>,
+}
+
+impl ClipStack {
+    pub fn new(base: Clip) -> Self {
+        return Self { stack: vec![base] };
+    }
+
+    pub fn push(&mut self, clip: Clip) {
+        let clip = self.get_current().intersect(&clip);
+        self.stack.push(clip);
+    }
+
+    // the base clip is never popped.
+    pub fn pop(&mut self) {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+        }
+    }
+
+    pub fn get_current(&self) -> Clip {
+        return *self.stack.last().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Clip, ClipStack};
+
+    #[test]
+    fn clip_stack() {
+        let mut stack = ClipStack::new(Clip::unbounded());
+        stack.push(Clip::new([10.0, 10.0], [100.0, 50.0]));
+        stack.push(Clip::rounded([50.0, 0.0], [100.0, 100.0], [4.0; 4]));
+        assert_eq!(
+            stack.get_current(),
+            Clip::rounded([50.0, 10.0], [60.0, 50.0], [4.0; 4])
+        );
+        assert_eq!(
+            stack.get_current().get_scissor(80, 80),
+            Some([50, 10, 30, 50])
+        );
+
+        stack.pop();
+        stack.pop();
+        stack.pop();
+        assert_eq!(stack.get_current(), Clip::unbounded());
+
+        stack.push(Clip::new([200.0, 0.0], [10.0, 10.0]));
+        assert_eq!(stack.get_current().get_scissor(80, 80), None);
+    }
+}
@@ -0,0 +1,127 @@
+use wgpu::{CompositeAlphaMode, PresentMode, SurfaceCapabilities, SurfaceConfiguration};
+
+// how a window surface is presented and paced.
+// present_mode and alpha_mode fall back to Fifo and Auto when the surface does not support them.
+// frame_rate: maximum frames per second, None draws as soon as something changed.
+// sample_count: msaa samples per pixel, falls back to 1 when the adapter does not support it.
+// triangles and meshes, svg icons included, have jagged edges without msaa.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RendererConfig {
+    pub present_mode: PresentMode,
+    pub alpha_mode: CompositeAlphaMode,
+    pub frame_rate: Option<f64>,
+    pub sample_count: u32,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        return Self {
+            present_mode: PresentMode::Fifo,
+            alpha_mode: CompositeAlphaMode::Auto,
+            frame_rate: Some(60.0),
+            sample_count: 1,
+        };
+    }
+}
+
+impl RendererConfig {
+    // Fifo waits for vsync, Mailbox replaces queued frames, Immediate tears.
+    // AutoVsync and AutoNoVsync pick the best supported mode themselves.
+    pub fn set_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        return self;
+    }
+
+    // PreMultiplied or PostMultiplied for transparent windows.
+    pub fn set_alpha_mode(mut self, alpha_mode: CompositeAlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        return self;
+    }
+
+    pub fn set_frame_rate(mut self, frame_rate: Option<f64>) -> Self {
+        self.frame_rate = frame_rate;
+        return self;
+    }
+
+    pub fn set_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        return self;
+    }
+
+    // the window has to be created transparent for these alpha modes.
+    pub fn is_transparent(&self) -> bool {
+        return matches!(
+            self.alpha_mode,
+            CompositeAlphaMode::PreMultiplied | CompositeAlphaMode::PostMultiplied
+        );
+    }
+
+    pub fn get_present_mode(&self, caps: &SurfaceCapabilities) -> PresentMode {
+        return match self.present_mode {
+            PresentMode::AutoVsync | PresentMode::AutoNoVsync => self.present_mode,
+            mode if caps.present_modes.contains(&mode) => mode,
+            _ => PresentMode::Fifo,
+        };
+    }
+
+    pub fn get_alpha_mode(&self, caps: &SurfaceCapabilities) -> CompositeAlphaMode {
+        if caps.alpha_modes.contains(&self.alpha_mode) {
+            return self.alpha_mode;
+        }
+        return CompositeAlphaMode::Auto;
+    }
+
+    // surface configuration with the fallbacks applied. the frame can be copied from and into
+    // when the surface allows it, which backdrop blur and partial redraw need.
+    pub fn get_surface_configuration(
+        &self,
+        caps: &SurfaceCapabilities,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> SurfaceConfiguration {
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (caps.usages & (wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST));
+        return SurfaceConfiguration {
+            usage,
+            format,
+            width,
+            height,
+            present_mode: self.get_present_mode(caps),
+            alpha_mode: self.get_alpha_mode(caps),
+            view_formats: vec![],
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wgpu::{CompositeAlphaMode, PresentMode, SurfaceCapabilities, TextureUsages};
+
+    use super::RendererConfig;
+
+    #[test]
+    fn config_fallback() {
+        let caps = SurfaceCapabilities {
+            formats: vec![wgpu::TextureFormat::Bgra8UnormSrgb],
+            present_modes: vec![PresentMode::Fifo, PresentMode::Immediate],
+            alpha_modes: vec![CompositeAlphaMode::Opaque],
+            usages: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        };
+
+        let config = RendererConfig::default().set_present_mode(PresentMode::Immediate);
+        assert_eq!(config.get_present_mode(&caps), PresentMode::Immediate);
+        let config = config
+            .set_present_mode(PresentMode::Mailbox)
+            .set_alpha_mode(CompositeAlphaMode::PreMultiplied);
+        assert!(config.is_transparent());
+
+        let surface = config.get_surface_configuration(&caps, caps.formats[0], 800, 600);
+        assert_eq!(surface.present_mode, PresentMode::Fifo);
+        assert_eq!(surface.alpha_mode, CompositeAlphaMode::Auto);
+        assert_eq!(
+            surface.usage,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::clip::Clip;
+
+// region of the target that changed since the last frame, in logical pixels.
+// damaged rects are merged into their bounding box, which is what gets redrawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Damage {
+    full: bool,
+    bounds: Option<Clip>,
+}
+
+impl Damage {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn add(&mut self, start: [f32; 2], size: [f32; 2]) {
+        if size[0] <= 0.0 || size[1] <= 0.0 {
+            return;
+        }
+        let rect = Clip::new(start, size);
+        self.bounds = match self.bounds {
+            Some(bounds) => {
+                let left = bounds.start[0].min(start[0]);
+                let top = bounds.start[1].min(start[1]);
+                let right = (bounds.start[0] + bounds.size[0]).max(start[0] + size[0]);
+                let bottom = (bounds.start[1] + bounds.size[1]).max(start[1] + size[1]);
+                Some(Clip::new([left, top], [right - left, bottom - top]))
+            }
+            None => Some(rect),
+        };
+    }
+
+    // the whole target is redrawn, e.g. after a resize.
+    pub fn add_all(&mut self) {
+        self.full = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.full = false;
+        self.bounds = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return !self.full && self.bounds.is_none();
+    }
+
+    pub fn is_full(&self) -> bool {
+        return self.full;
+    }
+
+    // bounding box of the damage inside a target of width and height, None when nothing changed.
+    pub fn get_bounds(&self, width: f32, height: f32) -> Option<Clip> {
+        let target = Clip::new([0.0, 0.0], [width, height]);
+        if self.full {
+            return Some(target);
+        }
+        return self
+            .bounds
+            .map(|bounds| bounds.intersect(&target))
+            .filter(|bounds| !bounds.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Damage;
+    use crate::clip::Clip;
+
+    #[test]
+    fn damage_bounds() {
+        let mut damage = Damage::new();
+        assert!(damage.is_empty());
+        assert_eq!(damage.get_bounds(100.0, 100.0), None);
+
+        damage.add([10.0, 10.0], [0.0, 20.0]);
+        assert!(damage.is_empty());
+        damage.add([10.0, 10.0], [20.0, 20.0]);
+        damage.add([50.0, 5.0], [80.0, 10.0]);
+        assert_eq!(
+            damage.get_bounds(100.0, 100.0),
+            Some(Clip::new([10.0, 5.0], [90.0, 25.0]))
+        );
+
+        damage.add_all();
+        assert!(damage.is_full());
+        assert_eq!(
+            damage.get_bounds(100.0, 100.0),
+            Some(Clip::new([0.0, 0.0], [100.0, 100.0]))
+        );
+
+        damage.clear();
+        damage.add([200.0, 0.0], [10.0, 10.0]);
+        assert!(!damage.is_empty());
+        assert_eq!(damage.get_bounds(100.0, 100.0), None);
+    }
+}
@@ -1,106 +1,367 @@
-use wgpu::{util::DeviceExt, Buffer, Device};
+use std::{ops::Range, sync::Arc};
 
-use crate::pipeline::image::ImageResource;
+use wgpu::{Device, Queue};
+
+use crate::{
+    buffer::{InstanceBuffer, PaintBuffer, StateBuffer},
+    clip::{Clip, ClipStack},
+    paint::Gradient,
+    pipeline::{
+        image::{
+            atlas::{AtlasImage, ImageAtlas},
+            ImageInstance, ImageResource,
+        },
+        line::{LineInstance, LineStyle},
+        mesh::{MeshInstance, MeshResource},
+        shadow::ShadowInstance,
+        text::{GlyphAtlas, Text},
+    },
+    state::DrawState,
+    transform::Transform,
+};
 
 use super::pipeline::{circle::CircleInstance, rect::RectInstance, triangle::TriangleInstance};
 
+// one draw call. consecutive pushes of the same primitive are merged into one command.
+#[derive(Clone, Debug)]
+pub enum DrawCommand {
+    Rects(Vec<RectInstance>),
+    Triangles(Vec<TriangleInstance>),
+    Circles(Vec<CircleInstance>),
+    Lines(Vec<LineInstance>),
+    Shadows(Vec<ShadowInstance>),
+    Image(ImageResource),
+    Mesh(MeshResource),
+    Texts(Vec<Text>),
+    PushClip(Clip),
+    PopClip,
+    PushTransform(Transform),
+    PopTransform,
+}
+
+// commands are painted in the order they were pushed. layers are painted by z index,
+// and layers with the same z index keep their submission order.
 #[derive(Debug)]
 pub struct Layer {
-    pub circles: Vec<CircleInstance>,
-    pub rects: Vec<RectInstance>,
-    pub triangles: Vec<TriangleInstance>,
-    pub images: Vec<ImageResource>,
+    pub z_index: i32,
+    pub clip: Option<Clip>,
+    pub bounds: Option<Clip>,
+    pub transform: Transform,
+    pub commands: Vec<DrawCommand>,
+    pub gradients: Vec<Gradient>,
+    pub backdrop_blur: Option<f32>,
 }
 
 impl Default for Layer {
     fn default() -> Self {
         Self {
-            circles: vec![],
-            rects: vec![],
-            triangles: vec![],
-            images: vec![],
+            z_index: 0,
+            clip: None,
+            bounds: None,
+            transform: Transform::identity(),
+            commands: vec![],
+            gradients: vec![],
+            backdrop_blur: None,
         }
     }
 }
 
 impl Layer {
-    pub fn get_all_buffers(&self, device: &Device) -> LayerBuffer {
-        return LayerBuffer {
-            rect_buffer: self.get_rect_buffer(device),
-            rect_count: self.rects.len(),
-            triangle_buffer: self.get_triangle_buffer(device),
-            triangle_count: self.triangles.len(),
-            circle_buffer: self.get_circle_buffer(device),
-            circle_count: self.circles.len(),
-            image_buffers: self.get_image_buffers(device),
+    pub fn set_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        return self;
+    }
+
+    // clip applied to the whole layer in screen pixels, below any clip pushed with push_clip.
+    pub fn set_clip(mut self, clip: Clip) -> Self {
+        self.clip = Some(clip);
+        return self;
+    }
+
+    // area in screen pixels the commands draw inside of, without clipping them. the renderer
+    // skips the layer when it does not reach the damaged region.
+    pub fn set_bounds(mut self, bounds: Clip) -> Self {
+        self.bounds = Some(bounds);
+        return self;
+    }
+
+    // the area the layer can paint in, None when it is unbounded.
+    pub fn get_bounds(&self) -> Option<Clip> {
+        return match (self.clip, self.bounds) {
+            (Some(clip), Some(bounds)) => Some(clip.intersect(&bounds)),
+            (clip, bounds) => clip.or(bounds),
         };
     }
 
-    pub fn push_circle(&mut self, instance: CircleInstance) {
-        self.circles.push(instance);
+    // transform applied to every command of the layer, below any pushed transform.
+    pub fn set_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        return self;
     }
 
-    pub fn get_circle_buffer(&self, device: &Device) -> wgpu::Buffer {
-        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("layer.circle.buffer"),
-            contents: bytemuck::cast_slice(&self.circles),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+    // blurs what is already drawn behind the layer before painting it, inside the layer clip.
+    // radius: standard deviation of the gaussian in logical pixels.
+    pub fn set_backdrop_blur(mut self, radius: f32) -> Self {
+        self.backdrop_blur = Some(radius);
+        return self;
     }
 
-    pub fn push_rect(&mut self, instance: RectInstance) {
-        self.rects.push(instance);
+    pub fn write_buffers(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        buffer: &mut LayerBuffer,
+        atlas: &mut GlyphAtlas,
+        states: &mut StateBuffer,
+        paints: &mut PaintBuffer,
+    ) {
+        let mut rects: Vec<RectInstance> = vec![];
+        let mut triangles: Vec<TriangleInstance> = vec![];
+        let mut circles: Vec<CircleInstance> = vec![];
+        let mut lines: Vec<LineInstance> = vec![];
+        let mut shadows: Vec<ShadowInstance> = vec![];
+        let mut images: Vec<ImageInstance> = vec![];
+        let mut meshes: Vec<MeshInstance> = vec![];
+        let mut glyphs = vec![];
+
+        let paint_offset = paints.push(&self.gradients);
+        let mut clips = ClipStack::new(self.clip.unwrap_or(Clip::unbounded()));
+        let mut transforms = vec![self.transform];
+        buffer.draws.clear();
+        for command in &self.commands {
+            let instances = match command {
+                DrawCommand::Rects(instances) => append(&mut rects, instances),
+                DrawCommand::Triangles(instances) => append(&mut triangles, instances),
+                DrawCommand::Circles(instances) => append(&mut circles, instances),
+                DrawCommand::Lines(instances) => append(&mut lines, instances),
+                DrawCommand::Shadows(instances) => append(&mut shadows, instances),
+                DrawCommand::Image(image) => append(&mut images, &image.instances),
+                DrawCommand::Mesh(mesh) if mesh.is_empty() => 0..0,
+                DrawCommand::Mesh(mesh) => append(&mut meshes, &mesh.instances),
+                DrawCommand::Texts(texts) => {
+                    let start = glyphs.len() as u32;
+                    texts
+                        .iter()
+                        .for_each(|text| glyphs.extend(atlas.get_glyph_instances(text)));
+                    start..glyphs.len() as u32
+                }
+                DrawCommand::PushClip(clip) => {
+                    clips.push(clip.transform(transforms.last().unwrap()));
+                    0..0
+                }
+                DrawCommand::PopClip => {
+                    clips.pop();
+                    0..0
+                }
+                DrawCommand::PushTransform(transform) => {
+                    transforms.push(transforms.last().unwrap().multiply(transform));
+                    0..0
+                }
+                DrawCommand::PopTransform => {
+                    if transforms.len() > 1 {
+                        transforms.pop();
+                    }
+                    0..0
+                }
+            };
+            let clip = clips.get_current();
+            let state = match instances.is_empty() {
+                true => 0,
+                false => states.push(DrawState::new(
+                    clip,
+                    *transforms.last().unwrap(),
+                    paint_offset,
+                )),
+            };
+            buffer.draws.push(LayerDraw {
+                instances,
+                state,
+                clip,
+            });
+        }
+
+        buffer.rect_buffer.write(device, queue, &rects);
+        buffer.triangle_buffer.write(device, queue, &triangles);
+        buffer.circle_buffer.write(device, queue, &circles);
+        buffer.line_buffer.write(device, queue, &lines);
+        buffer.shadow_buffer.write(device, queue, &shadows);
+        buffer.image_buffer.write(device, queue, &images);
+        buffer.mesh_buffer.write(device, queue, &meshes);
+        buffer.text_buffer.write(device, queue, &glyphs);
+    }
+
+    // returns the paint of the gradient, to be set on rect and circle instances of this layer.
+    pub fn push_gradient(&mut self, gradient: Gradient) -> u32 {
+        self.gradients.push(gradient);
+        return self.gradients.len() as u32;
+    }
+
+    pub fn push_circle(&mut self, instance: CircleInstance) {
+        match self.commands.last_mut() {
+            Some(DrawCommand::Circles(instances)) => instances.push(instance),
+            _ => self.commands.push(DrawCommand::Circles(vec![instance])),
+        }
     }
 
-    pub fn get_rect_buffer(&self, device: &Device) -> wgpu::Buffer {
-        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("layer.rect.buffer"),
-            contents: bytemuck::cast_slice(&self.rects),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+    pub fn push_rect(&mut self, instance: RectInstance) {
+        match self.commands.last_mut() {
+            Some(DrawCommand::Rects(instances)) => instances.push(instance),
+            _ => self.commands.push(DrawCommand::Rects(vec![instance])),
+        }
     }
 
     pub fn push_triangle(&mut self, instance: TriangleInstance) {
-        self.triangles.push(instance);
-    }
-
-    pub fn get_triangle_buffer(&self, device: &Device) -> wgpu::Buffer {
-        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("layer.triangle.buffer"),
-            contents: bytemuck::cast_slice(&self.triangles),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-    }
-
-    pub fn push_image(&mut self, instance: ImageResource) {
-        self.images.push(instance);
-    }
-
-    pub fn get_image_buffers(&self, device: &Device) -> Vec<(wgpu::BindGroup, wgpu::Buffer, u32)> {
-        return self
-            .images
-            .iter()
-            .map(|r| {
-                return (
-                    r.get_bind_group(device),
-                    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                        label: Some("layer.image.buffer"),
-                        contents: bytemuck::cast_slice(&r.instances),
-                        usage: wgpu::BufferUsages::VERTEX,
-                    }),
-                    r.instances.len() as u32,
-                );
-            })
-            .collect();
+        match self.commands.last_mut() {
+            Some(DrawCommand::Triangles(instances)) => instances.push(instance),
+            _ => self.commands.push(DrawCommand::Triangles(vec![instance])),
+        }
+    }
+
+    pub fn push_line(&mut self, start: [f32; 2], end: [f32; 2], style: &LineStyle) {
+        self.push_lines(vec![LineInstance::new(start, end, style)]);
     }
+
+    pub fn push_polyline(&mut self, points: &[[f32; 2]], style: &LineStyle) {
+        self.push_lines(LineInstance::polyline(points, style));
+    }
+
+    fn push_lines(&mut self, instances: Vec<LineInstance>) {
+        match self.commands.last_mut() {
+            Some(DrawCommand::Lines(lines)) => lines.extend(instances),
+            _ => self.commands.push(DrawCommand::Lines(instances)),
+        }
+    }
+
+    pub fn push_shadow(&mut self, instance: ShadowInstance) {
+        match self.commands.last_mut() {
+            Some(DrawCommand::Shadows(instances)) => instances.push(instance),
+            _ => self.commands.push(DrawCommand::Shadows(vec![instance])),
+        }
+    }
+
+    pub fn push_image(&mut self, image: ImageResource) {
+        match self.commands.last_mut() {
+            Some(DrawCommand::Image(last)) if Arc::ptr_eq(&last.texture, &image.texture) => {
+                last.instances.extend(image.instances)
+            }
+            _ => self.commands.push(DrawCommand::Image(image)),
+        }
+    }
+
+    // consecutive instances of the same mesh draw in a single call.
+    pub fn push_mesh(&mut self, mesh: MeshResource) {
+        match self.commands.last_mut() {
+            Some(DrawCommand::Mesh(last)) if Arc::ptr_eq(&last.buffers, &mesh.buffers) => {
+                last.instances.extend(mesh.instances)
+            }
+            _ => self.commands.push(DrawCommand::Mesh(mesh)),
+        }
+    }
+
+    // images from the same atlas page share one resource, so consecutive ones draw in a single call.
+    pub fn push_atlas_image(
+        &mut self,
+        atlas: &ImageAtlas,
+        image: &AtlasImage,
+        start: [f32; 2],
+        size: [f32; 2],
+    ) {
+        let mut resource = atlas.get_resource(image);
+        resource.push_instance(image.instance(start, size));
+        self.push_image(resource);
+    }
+
+    pub fn push_text(&mut self, text: Text) {
+        match self.commands.last_mut() {
+            Some(DrawCommand::Texts(texts)) => texts.push(text),
+            _ => self.commands.push(DrawCommand::Texts(vec![text])),
+        }
+    }
+
+    // commands pushed until the matching pop_clip are clipped to the intersection of all open clips.
+    // the clip is given in the space of the current transform.
+    pub fn push_clip(&mut self, clip: Clip) {
+        self.commands.push(DrawCommand::PushClip(clip));
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.commands.push(DrawCommand::PopClip);
+    }
+
+    // commands pushed until the matching pop_transform are transformed on the gpu.
+    // nested transforms compose, the innermost is applied first.
+    pub fn push_transform(&mut self, transform: Transform) {
+        self.commands.push(DrawCommand::PushTransform(transform));
+    }
+
+    pub fn pop_transform(&mut self) {
+        self.commands.push(DrawCommand::PopTransform);
+    }
+}
+
+fn append<T: Copy>(data: &mut Vec<T>, instances: &[T]) -> Range<u32> {
+    let start = data.len() as u32;
+    data.extend_from_slice(instances);
+    return start..data.len() as u32;
+}
+
+// instances, state offset and clip of one command.
+#[derive(Clone, Debug)]
+pub struct LayerDraw {
+    pub instances: Range<u32>,
+    pub state: u32,
+    pub clip: Clip,
 }
 
+// one instance buffer per primitive type. draws holds one entry per command.
+#[derive(Debug)]
 pub struct LayerBuffer {
-    pub rect_buffer: Buffer,
-    pub rect_count: usize,
-    pub triangle_buffer: Buffer,
-    pub triangle_count: usize,
-    pub circle_buffer: Buffer,
-    pub circle_count: usize,
-    pub image_buffers: Vec<(wgpu::BindGroup, wgpu::Buffer, u32)>,
+    pub rect_buffer: InstanceBuffer,
+    pub triangle_buffer: InstanceBuffer,
+    pub circle_buffer: InstanceBuffer,
+    pub line_buffer: InstanceBuffer,
+    pub shadow_buffer: InstanceBuffer,
+    pub image_buffer: InstanceBuffer,
+    pub mesh_buffer: InstanceBuffer,
+    pub text_buffer: InstanceBuffer,
+    pub draws: Vec<LayerDraw>,
+}
+
+impl LayerBuffer {
+    pub fn new(device: &Device) -> Self {
+        return Self {
+            rect_buffer: InstanceBuffer::new(device, "layer.rect.buffer"),
+            triangle_buffer: InstanceBuffer::new(device, "layer.triangle.buffer"),
+            circle_buffer: InstanceBuffer::new(device, "layer.circle.buffer"),
+            line_buffer: InstanceBuffer::new(device, "layer.line.buffer"),
+            shadow_buffer: InstanceBuffer::new(device, "layer.shadow.buffer"),
+            image_buffer: InstanceBuffer::new(device, "layer.image.buffer"),
+            mesh_buffer: InstanceBuffer::new(device, "layer.mesh.buffer"),
+            text_buffer: InstanceBuffer::new(device, "layer.text.buffer"),
+            draws: vec![],
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DrawCommand, Layer};
+    use crate::pipeline::{circle::CircleInstance, rect::RectInstance};
+
+    #[test]
+    fn layer_command_order() {
+        let rect = RectInstance::fill([0.0, 0.0], [10.0, 10.0], [0.0; 4], [1.0; 4]);
+        let circle = CircleInstance::fill([5.0, 5.0], 5.0, [1.0; 4]);
+
+        let mut layer = Layer::default();
+        layer.push_rect(rect);
+        layer.push_rect(rect);
+        layer.push_circle(circle);
+        layer.push_rect(rect);
+
+        assert_eq!(layer.commands.len(), 3);
+        assert!(matches!(&layer.commands[0], DrawCommand::Rects(rects) if rects.len() == 2));
+        assert!(matches!(&layer.commands[1], DrawCommand::Circles(_)));
+        assert!(matches!(&layer.commands[2], DrawCommand::Rects(rects) if rects.len() == 1));
+    }
 }
@@ -0,0 +1,108 @@
+use std::{mem, sync::Arc};
+
+use wgpu::{BindGroupLayout, BindGroupLayoutDescriptor, Device};
+
+use crate::{paint::GradientData, state::DrawState};
+
+// bind group layouts shared by all pipelines, created once per device.
+// the viewport group also holds the per draw state, bound with a dynamic offset,
+// and the gradient paints of the frame.
+#[derive(Clone, Debug)]
+pub struct BindGroupLayouts {
+    pub viewport: Arc<BindGroupLayout>,
+    pub texture: Arc<BindGroupLayout>,
+}
+
+impl BindGroupLayouts {
+    pub fn new(device: &Device) -> Self {
+        let viewport = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("viewport.layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(
+                            mem::size_of::<DrawState>() as wgpu::BufferAddress
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            mem::size_of::<GradientData>() as wgpu::BufferAddress
+                        ),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let texture = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("texture.layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        return Self {
+            viewport: Arc::new(viewport),
+            texture: Arc::new(texture),
+        };
+    }
+
+    pub fn create_texture_group(
+        &self,
+        device: &Device,
+        label: &str,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        return device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout: &self.texture,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+    }
+}
@@ -1,33 +1,732 @@
+pub mod buffer;
+pub mod clip;
+pub mod config;
+pub mod damage;
 pub mod layer;
+pub mod layouts;
+pub mod paint;
 pub mod pipeline;
+pub mod profiler;
+pub mod renderer;
+pub mod snapshot;
+pub mod state;
+pub mod target;
+pub mod transform;
 pub mod viewport;
 
 #[cfg(test)]
 mod tests {
-    use std::time::Instant;
+    use std::{sync::Arc, time::Duration};
 
     use env_logger;
+    use image::{DynamicImage, Rgba, RgbaImage};
     use wgpu::{InstanceDescriptor, InstanceFlags};
     use winit::error::EventLoopError;
     use winit::event_loop::EventLoopBuilder;
     use winit::{
         dpi::PhysicalSize,
         event::{Event, WindowEvent},
-        event_loop::ControlFlow,
         platform::wayland::EventLoopBuilderExtWayland,
         window::WindowBuilder,
     };
 
+    use crate::clip::Clip;
     use crate::layer::Layer;
-    use crate::pipeline::image::{ImageInstance, ImagePipeline, ImageResource};
-    use crate::{
-        pipeline::{
-            circle::{CircleInstance, CirclePipeline},
-            rect::{RectInstance, RectPipeline},
-            triangle::{TriangleInstance, TrianglePipeline},
-        },
-        viewport::Viewport,
+    use crate::paint::Gradient;
+    use crate::pipeline::image::ImageInstance;
+    use crate::pipeline::{
+        circle::CircleInstance,
+        line::{LineCap, LineJoin, LineStyle},
+        mesh::svg::SvgCache,
+        rect::RectInstance,
+        shadow::ShadowInstance,
+        text::{Font, Text},
+        triangle::TriangleInstance,
     };
+    use crate::profiler::{Phase, PipelineKind};
+    use crate::renderer::Renderer;
+    use crate::snapshot::Snapshot;
+    use crate::target::SurfaceTarget;
+    use crate::transform::Transform;
+
+    // headless renderer for the tests, which fail instead of passing without a graphics adapter.
+    pub(crate) fn headless_renderer() -> Renderer {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        return match rt.block_on(Renderer::headless()) {
+            Ok(renderer) => renderer,
+            Err(error) => panic!("{}, the test needs a graphics adapter", error),
+        };
+    }
+
+    #[test]
+    fn test_headless() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 48);
+
+        let mut layer = Layer::default();
+        layer.push_rect(RectInstance::fill(
+            [8.0, 8.0],
+            [32.0, 24.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 1.0],
+        ));
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.dimensions(), (64, 48));
+        assert_eq!(image.get_pixel(20, 20).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(60, 40).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_paint_order() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 32);
+
+        let mut popup = Layer::default().set_z_index(1);
+        popup.push_rect(RectInstance::fill(
+            [40.0, 0.0],
+            [24.0, 32.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 1.0],
+        ));
+
+        let mut layer = Layer::default();
+        layer.push_circle(CircleInstance::fill(
+            [16.0, 16.0],
+            16.0,
+            [1.0, 0.0, 0.0, 1.0],
+        ));
+        layer.push_rect(RectInstance::fill(
+            [8.0, 8.0],
+            [48.0, 16.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 1.0],
+        ));
+        layer.push_circle(CircleInstance::fill(
+            [16.0, 16.0],
+            4.0,
+            [1.0, 0.0, 0.0, 1.0],
+        ));
+        renderer.render(&target, &[popup, layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(16, 4).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(10, 16).0, [0, 255, 0, 255]);
+        assert_eq!(image.get_pixel(16, 16).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(48, 16).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 32);
+        let background = RectInstance::fill(
+            [0.0, 0.0],
+            [64.0, 32.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 1.0],
+        );
+
+        let mut layer = Layer::default().set_clip(Clip::new([0.0, 0.0], [60.0, 32.0]));
+        layer.push_clip(Clip::new([8.0, 8.0], [24.0, 16.0]));
+        layer.push_rect(background);
+        layer.pop_clip();
+        layer.push_clip(Clip::rounded([36.0, 0.0], [28.0, 32.0], [12.0; 4]));
+        layer.push_circle(CircleInstance::fill(
+            [48.0, 16.0],
+            20.0,
+            [0.0, 1.0, 0.0, 1.0],
+        ));
+        layer.pop_clip();
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(31, 23).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(7, 16).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(16, 24).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(32, 16).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(48, 16).0, [0, 255, 0, 255]);
+        assert_eq!(image.get_pixel(59, 16).0, [0, 255, 0, 255]);
+        assert_eq!(image.get_pixel(60, 16).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(36, 0).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 32);
+        let square = RectInstance::fill(
+            [0.0, 0.0],
+            [8.0, 8.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0],
+        );
+
+        let mut layer = Layer::default().set_transform(Transform::translate(4.0, 4.0));
+        layer.push_transform(Transform::scale(2.0, 2.0));
+        layer.push_rect(square);
+        layer.push_clip(Clip::new([8.0, 0.0], [4.0, 8.0]));
+        layer.push_rect(RectInstance::fill(
+            [8.0, 0.0],
+            [8.0, 8.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 1.0],
+        ));
+        layer.pop_clip();
+        layer.pop_transform();
+        layer.push_transform(Transform::rotate(std::f32::consts::FRAC_PI_4).around(44.0, 12.0));
+        layer.push_rect(RectInstance::fill(
+            [36.0, 4.0],
+            [16.0, 16.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [1.0, 1.0, 1.0, 1.0],
+        ));
+        layer.pop_transform();
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(4, 4).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(19, 19).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(20, 20).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(3, 3).0, [0, 0, 0, 255]);
+        // the clip is scaled with the content : only the left half of the red square is drawn.
+        assert_eq!(image.get_pixel(20, 4).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(27, 19).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(28, 10).0, [0, 0, 0, 255]);
+        // the rotated square is a diamond around (48, 16), its corners are empty.
+        assert!(image.get_pixel(48, 16).0[0] > 245);
+        assert_eq!(image.get_pixel(48, 6).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(41, 9).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 48);
+
+        let mut layer = Layer::default();
+        layer.push_line(
+            [4.0, 4.0],
+            [60.0, 4.0],
+            &LineStyle::new(4.0, [1.0, 1.0, 1.0, 1.0]),
+        );
+        layer.push_line(
+            [4.0, 16.0],
+            [60.0, 16.0],
+            &LineStyle::new(4.0, [1.0, 0.0, 0.0, 1.0]).set_dash(8.0, 8.0, 0.0),
+        );
+        layer.push_polyline(
+            &[[8.0, 40.0], [24.0, 28.0], [40.0, 40.0]],
+            &LineStyle::new(4.0, [0.0, 1.0, 0.0, 1.0])
+                .set_cap(LineCap::Round)
+                .set_join(LineJoin::Round),
+        );
+        layer.push_polyline(
+            &[[44.0, 30.0], [52.0, 30.0], [52.0, 44.0]],
+            &LineStyle::new(4.0, [0.0, 0.0, 1.0, 0.5]).set_join(LineJoin::Round),
+        );
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(30, 3).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(30, 8).0, [0, 0, 0, 255]);
+        // butt caps end exactly at the end points.
+        assert_eq!(image.get_pixel(2, 3).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(8, 16).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(16, 16).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(24, 16).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(24, 28).0, [0, 255, 0, 255]);
+        assert_eq!(image.get_pixel(24, 36).0, [0, 0, 0, 255]);
+        // round caps extend past the end points.
+        assert_eq!(image.get_pixel(7, 40).0, [0, 255, 0, 255]);
+        // a translucent joint is blended once.
+        assert_eq!(image.get_pixel(52, 30), image.get_pixel(48, 30));
+        assert_eq!(image.get_pixel(52, 30), image.get_pixel(52, 38));
+    }
+
+    #[test]
+    fn test_scale_factor() {
+        let mut renderer = headless_renderer();
+        renderer.set_scale_factor(2.0);
+        let target = renderer.create_offscreen_target(64, 48);
+
+        let mut layer = Layer::default();
+        layer.push_clip(Clip::new([0.0, 0.0], [16.0, 24.0]));
+        layer.push_rect(RectInstance::fill(
+            [4.0, 4.0],
+            [20.0, 8.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 1.0],
+        ));
+        layer.pop_clip();
+
+        // images are drawn with the variant rasterized for the scale factor.
+        let red = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+        let green = RgbaImage::from_pixel(8, 8, Rgba([0, 255, 0, 255]));
+        let icon = renderer.create_scaled_image(vec![
+            (2.0, DynamicImage::ImageRgba8(green)),
+            (1.0, DynamicImage::ImageRgba8(red)),
+        ]);
+        assert_eq!(icon.get_resource(1.0).unwrap().texture.texture.width(), 4);
+        assert_eq!(icon.get_resource(3.0).unwrap().texture.texture.width(), 8);
+        let mut image = icon.get_resource(renderer.get_scale_factor()).unwrap();
+        image.push_instance(ImageInstance::new([24.0, 4.0], [4.0, 4.0]));
+        layer.push_image(image);
+        renderer.render(&target, &[layer]).unwrap();
+
+        // logical coordinates are doubled on the physical target.
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(8, 8).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(7, 7).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(31, 23).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(20, 24).0, [0, 0, 0, 255]);
+        // the clip is scaled as well.
+        assert_eq!(image.get_pixel(32, 10).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(52, 12).0, [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_mesh() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 32);
+
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="8">
+            <path d="M 0 0 L 8 0 L 0 8 Z" fill="#ffffff"/>
+        </svg>"##;
+        let mut cache = SvgCache::new();
+        let mut icon = cache
+            .load(renderer.get_device(), "corner", svg.as_bytes())
+            .unwrap();
+        assert!(Arc::ptr_eq(
+            &icon.buffers,
+            &cache.get("corner").unwrap().buffers
+        ));
+
+        // the same mesh drawn at its native size and scaled up, tinted by the instance color.
+        icon.push_instance(icon.instance([0.0, 0.0], [8.0, 8.0], [1.0, 0.0, 0.0, 1.0]));
+        icon.push_instance(icon.instance([16.0, 0.0], [32.0, 32.0], [0.0, 0.0, 1.0, 1.0]));
+        let mut layer = Layer::default();
+        layer.push_mesh(icon);
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(1, 1).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(6, 6).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(18, 2).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(20, 24).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(40, 24).0, [0, 0, 0, 255]);
+
+        // the diagonal is only smoothed with msaa.
+        let diagonal = |image: &image::RgbaImage| {
+            return (16..48)
+                .filter(|x| {
+                    let blue = image.get_pixel(*x, 47 - *x).0[2];
+                    return blue > 10 && blue < 245;
+                })
+                .count();
+        };
+        assert_eq!(diagonal(&image), 0);
+        let mut icon = cache.get("corner").unwrap();
+        icon.push_instance(icon.instance([16.0, 0.0], [32.0, 32.0], [0.0, 0.0, 1.0, 1.0]));
+        let mut layer = Layer::default();
+        layer.push_mesh(icon);
+        renderer.set_sample_count(4);
+        let target = renderer.create_offscreen_target(64, 32);
+        renderer.render(&target, &[layer]).unwrap();
+        assert!(diagonal(&renderer.read_image(&target)) > 16);
+    }
+
+    #[test]
+    fn test_gradient() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 32);
+
+        let mut layer = Layer::default();
+        let horizontal = layer.push_gradient(
+            Gradient::linear(0.0)
+                .add_stop(0.0, [1.0, 0.0, 0.0, 1.0])
+                .add_stop(0.5, [0.0, 1.0, 0.0, 1.0])
+                .add_stop(1.0, [0.0, 0.0, 1.0, 1.0]),
+        );
+        let radial = layer.push_gradient(
+            Gradient::radial([0.5, 0.5], 1.0)
+                .add_stop(0.0, [1.0, 1.0, 1.0, 1.0])
+                .add_stop(1.0, [1.0, 1.0, 1.0, 0.0]),
+        );
+        layer.push_rect(
+            RectInstance::fill(
+                [0.0, 0.0],
+                [32.0, 16.0],
+                [0.0, 0.0, 0.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            )
+            .set_fill_paint(horizontal)
+            .set_line_paint(horizontal),
+        );
+        layer.push_rect(
+            RectInstance::outline([0.0, 16.0], [32.0, 16.0], 2, [0.0; 4], [0.0; 4])
+                .set_line_paint(horizontal),
+        );
+        layer
+            .push_circle(CircleInstance::fill([48.0, 16.0], 16.0, [0.0; 4]).set_fill_paint(radial));
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert!(image.get_pixel(0, 8).0[0] > 245 && image.get_pixel(0, 8).0[2] == 0);
+        assert!(image.get_pixel(31, 8).0[2] > 245 && image.get_pixel(31, 8).0[0] == 0);
+        assert!(image.get_pixel(16, 8).0[1] > 250);
+        // the stroke uses the gradient, the inside of the outline stays empty.
+        assert!(image.get_pixel(0, 24).0[0] > 250);
+        assert_eq!(image.get_pixel(16, 24).0, [0, 0, 0, 255]);
+        assert!(image.get_pixel(48, 16).0[0] > 245);
+        assert!(image.get_pixel(48, 2).0[0] < 160);
+    }
+
+    #[test]
+    fn test_shadow() {
+        let mut renderer = headless_renderer();
+        renderer.set_clear_color(wgpu::Color::WHITE);
+        let target = renderer.create_offscreen_target(64, 64);
+
+        let mut layer = Layer::default();
+        layer.push_shadow(
+            ShadowInstance::new([16.0, 16.0], [24.0, 24.0], [4.0; 4], [0.0, 0.0, 0.0, 1.0])
+                .set_offset(4.0, 4.0)
+                .set_blur(8.0),
+        );
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        // dark in the middle of the offset box, fading towards the outside.
+        assert!(image.get_pixel(32, 32).0[0] < 10);
+        let edge = image.get_pixel(44, 32).0[0];
+        assert!(edge > 60 && edge < 200);
+        assert!(image.get_pixel(58, 32).0[0] > 245);
+        // the offset moves the shadow away from the top left.
+        assert!(image.get_pixel(14, 32).0[0] > image.get_pixel(46, 32).0[0]);
+
+        // a spread keeps the corners of a square box sharp and grows rounded ones.
+        let mut layer = Layer::default();
+        layer.push_shadow(
+            ShadowInstance::new([16.0, 16.0], [16.0, 16.0], [0.0; 4], [0.0, 0.0, 0.0, 1.0])
+                .set_spread(4.0),
+        );
+        layer.push_shadow(
+            ShadowInstance::new([44.0, 16.0], [8.0, 8.0], [4.0; 4], [0.0, 0.0, 0.0, 1.0])
+                .set_spread(4.0),
+        );
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(12, 12).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(35, 35).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(40, 12).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(48, 20).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_backdrop_blur() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 64);
+
+        // white right half behind a blurred layer covering the top half.
+        let mut background = Layer::default();
+        background.push_rect(RectInstance::fill(
+            [32.0, 0.0],
+            [32.0, 64.0],
+            [0.0; 4],
+            [1.0, 1.0, 1.0, 1.0],
+        ));
+        let popup = Layer::default()
+            .set_z_index(1)
+            .set_clip(Clip::new([0.0, 0.0], [64.0, 32.0]))
+            .set_backdrop_blur(4.0);
+        renderer.render(&target, &[popup, background]).unwrap();
+
+        let image = renderer.read_image(&target);
+        let blurred = image.get_pixel(31, 16).0[0];
+        assert!(blurred > 40 && blurred < 200);
+        assert!(image.get_pixel(4, 16).0[0] < 5);
+        assert!(image.get_pixel(60, 16).0[0] > 250);
+        // outside of the clip the edge stays sharp.
+        assert_eq!(image.get_pixel(31, 48).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(32, 48).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_anti_aliasing() {
+        let mut renderer = headless_renderer();
+
+        let mut layer = Layer::default();
+        layer.push_triangle(TriangleInstance::fill(
+            [0.0, 0.0],
+            [0.0, 32.0],
+            [64.0, 32.0],
+            [1.0, 1.0, 1.0, 1.0],
+        ));
+        let partial = |image: &image::RgbaImage| {
+            return image
+                .pixels()
+                .filter(|pixel| pixel.0[0] > 10 && pixel.0[0] < 245)
+                .count();
+        };
+
+        let target = renderer.create_offscreen_target(64, 32);
+        renderer
+            .render(&target, std::slice::from_ref(&layer))
+            .unwrap();
+        let aliased = partial(&renderer.read_image(&target));
+
+        // the triangle edge is only smoothed by multisampling.
+        renderer.set_sample_count(4);
+        let target = renderer.create_offscreen_target(64, 32);
+        renderer
+            .render(&target, std::slice::from_ref(&layer))
+            .unwrap();
+        let image = renderer.read_image(&target);
+        assert!(partial(&image) > aliased + 16);
+        assert_eq!(image.get_pixel(2, 30).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(60, 2).0, [0, 0, 0, 255]);
+
+        // a wider sdf edge softens the border of a rect.
+        let mut layer = Layer::default();
+        layer.push_rect(RectInstance::new(
+            [8.0, 8.0],
+            [48.0, 16.0],
+            0,
+            [0.0; 4],
+            [1.0, 1.0, 1.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ));
+        renderer
+            .render(&target, std::slice::from_ref(&layer))
+            .unwrap();
+        let sharp = renderer.read_image(&target).get_pixel(9, 16).0[0];
+        renderer.set_aa_width(4.0);
+        renderer
+            .render(&target, std::slice::from_ref(&layer))
+            .unwrap();
+        let soft = renderer.read_image(&target).get_pixel(9, 16).0[0];
+        assert!(sharp > 245 && soft < sharp);
+
+        // counts the adapter does not support fall back to no multisampling.
+        renderer.set_sample_count(3);
+        assert_eq!(renderer.get_sample_count(), 1);
+    }
+
+    #[test]
+    fn test_partial_redraw() {
+        let mut renderer = headless_renderer();
+        renderer.set_partial_redraw(true);
+        let target = renderer.create_offscreen_target(64, 32);
+        let fill = |color: [f32; 4]| {
+            let mut layer = Layer::default();
+            layer.push_rect(RectInstance::fill(
+                [0.0, 0.0],
+                [64.0, 32.0],
+                [0.0; 4],
+                color,
+            ));
+            return layer;
+        };
+
+        // the first frame is fully drawn.
+        renderer
+            .render(&target, &[fill([1.0, 0.0, 0.0, 1.0])])
+            .unwrap();
+        assert!(renderer.get_damage().is_empty());
+        assert_eq!(
+            renderer.read_image(&target).get_pixel(40, 20).0,
+            [255, 0, 0, 255]
+        );
+
+        // only the damaged rect takes the new content, the rest of the last frame is kept.
+        renderer.add_damage([8.0, 8.0], [16.0, 8.0]);
+        renderer
+            .render(&target, &[fill([0.0, 0.0, 1.0, 1.0])])
+            .unwrap();
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(8, 8).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(23, 15).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(24, 15).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(40, 20).0, [255, 0, 0, 255]);
+
+        // damage clears the region, layers clipped outside of it are skipped.
+        let clipped = fill([0.0, 1.0, 0.0, 1.0]).set_clip(Clip::new([40.0, 0.0], [24.0, 32.0]));
+        renderer.add_damage([0.0, 0.0], [8.0, 8.0]);
+        renderer.render(&target, &[clipped]).unwrap();
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(2, 2).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(40, 20).0, [255, 0, 0, 255]);
+
+        // and so are layers whose bounds are outside of it.
+        let bounded = fill([0.0, 1.0, 0.0, 1.0]).set_bounds(Clip::new([40.0, 0.0], [24.0, 32.0]));
+        renderer.add_damage([8.0, 0.0], [8.0, 8.0]);
+        renderer.render(&target, &[bounded]).unwrap();
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(10, 2).0, [0, 0, 0, 255]);
+
+        // without damage nothing is drawn.
+        renderer
+            .render(&target, &[fill([1.0, 1.0, 1.0, 1.0])])
+            .unwrap();
+        assert_eq!(renderer.read_image(&target), image);
+    }
+
+    #[test]
+    fn test_render_to_texture() {
+        let mut renderer = headless_renderer();
+        renderer.set_clear_color(wgpu::Color::WHITE);
+        let texture = renderer.create_texture_target(32, 32);
+        let target = renderer.create_offscreen_target(64, 32);
+
+        // the left half of the texture is red, the rest stays transparent.
+        let mut content = Layer::default();
+        content.push_rect(RectInstance::fill(
+            [0.0, 0.0],
+            [16.0, 32.0],
+            [0.0; 4],
+            [1.0, 0.0, 0.0, 1.0],
+        ));
+        renderer.render_to_texture(&texture, &[content]).unwrap();
+
+        let mut layer = Layer::default();
+        layer.push_transform(Transform::translate(16.0, 0.0));
+        let mut image = texture.get_image();
+        image.push_instance(ImageInstance::new([0.0, 0.0], [32.0, 32.0]).set_opacity(0.5));
+        layer.push_image(image);
+        layer.pop_transform();
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        let close = |pixel: [u8; 4], expected: [u8; 4]| {
+            return pixel
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (*a as i32 - b as i32).abs() <= 2);
+        };
+        assert_eq!(image.get_pixel(8, 16).0, [255, 255, 255, 255]);
+        assert!(close(image.get_pixel(24, 16).0, [255, 188, 188, 255]));
+        assert_eq!(image.get_pixel(40, 16).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_profiler() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 32);
+        let mut layer = Layer::default();
+        layer.push_rect(RectInstance::fill(
+            [0.0, 0.0],
+            [8.0, 8.0],
+            [0.0; 4],
+            [1.0; 4],
+        ));
+        layer.push_rect(RectInstance::fill(
+            [8.0, 8.0],
+            [8.0, 8.0],
+            [0.0; 4],
+            [1.0; 4],
+        ));
+        let blurred = Layer::default().set_backdrop_blur(4.0);
+
+        renderer.render(&target, &[]).unwrap();
+        assert!(renderer.get_profiler().get_last().is_none());
+
+        renderer.get_profiler_mut().set_enabled(true);
+        renderer.render(&target, &[layer, blurred]).unwrap();
+        let stats = renderer.get_profiler().get_last().unwrap();
+        assert_eq!(stats.get_draws(PipelineKind::Rect).draw_calls, 1);
+        assert_eq!(stats.get_draws(PipelineKind::Rect).instances, 2);
+        assert_eq!(stats.get_draws(PipelineKind::Blur).draw_calls, 2);
+        assert_eq!(stats.get_draws(PipelineKind::Text).draw_calls, 0);
+        assert!(stats.get_cpu(Phase::Encode) > Duration::ZERO);
+        assert_eq!(stats.get_cpu(Phase::Layout), Duration::ZERO);
+        if !renderer.get_profiler().has_gpu_timing() {
+            assert_eq!(stats.gpu, None);
+        }
+    }
+
+    fn snapshot() -> Snapshot {
+        return Snapshot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src/test/snapshots"));
+    }
+
+    #[test]
+    fn test_snapshot_primitives() {
+        let mut renderer = headless_renderer();
+
+        let mut rect_layer = Layer::default();
+        rect_layer.push_rect(RectInstance::new(
+            [10.0, 10.0],
+            [80.0, 50.0],
+            3,
+            [4.0, 12.0, 20.0, 30.0],
+            [0.2, 0.4, 0.8, 1.0],
+            [1.0, 1.0, 1.0, 1.0],
+        ));
+        rect_layer.push_rect(RectInstance::outline(
+            [20.5, 70.5],
+            [60.0, 20.0],
+            1,
+            [10.0, 10.0, 10.0, 10.0],
+            [0.0, 1.0, 0.0, 1.0],
+        ));
+
+        let mut circle_layer = Layer::default();
+        circle_layer.push_circle(CircleInstance::new(
+            [30.0, 30.0],
+            20.0,
+            2,
+            [1.0, 0.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0, 1.0],
+        ));
+        circle_layer.push_circle(CircleInstance::new(
+            [70.5, 70.5],
+            15.3,
+            4,
+            [0.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 1.0, 0.5],
+        ));
+
+        let mut triangle_layer = Layer::default();
+        triangle_layer.push_triangle(TriangleInstance::fill(
+            [50.0, 10.0],
+            [10.0, 90.0],
+            [90.0, 90.0],
+            [0.0, 1.0, 0.0, 1.0],
+        ));
+
+        let mut text_layer = Layer::default();
+        let font = Font::from_bytes(include_bytes!("test/assets/DejaVuSansMono.ttf")).unwrap();
+        text_layer.push_text(Text::new(
+            font,
+            "atoz\nfn()",
+            [8.0, 8.0],
+            24.0,
+            [1.0, 1.0, 1.0, 1.0],
+        ));
+
+        let mut image_layer = Layer::default();
+        let mut image = renderer
+            .create_image(image::load_from_memory(include_bytes!("test/assets/rust.png")).unwrap());
+        image.push_instance(ImageInstance::new([10.0, 10.0], [40.0, 40.0]));
+        image.push_instance(ImageInstance::new([50.0, 50.0], [40.0, 40.0]));
+        image_layer.push_image(image);
+
+        let snapshot = snapshot();
+        for (name, layer) in [
+            ("rect", rect_layer),
+            ("circle", circle_layer),
+            ("triangle", triangle_layer),
+            ("text", text_layer),
+            ("image", image_layer),
+        ] {
+            snapshot
+                .check_layers(name, &mut renderer, 100, 100, &[layer])
+                .unwrap();
+        }
+    }
 
     #[test]
     fn test_pipeline() -> Result<(), EventLoopError> {
@@ -41,7 +740,6 @@ mod tests {
         let _ = window.request_inner_size(PhysicalSize::new(800, 600));
 
         let size = window.inner_size();
-        let mut viewport = Viewport::new(size.width as f32, size.height as f32);
 
         let instance = wgpu::Instance::new(InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -79,7 +777,7 @@ mod tests {
             .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
-        let mut config = wgpu::SurfaceConfiguration {
+        let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width,
@@ -88,18 +786,7 @@ mod tests {
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
             view_formats: vec![],
         };
-        surface.configure(&device, &config);
-
-        let resize = |device: &wgpu::Device,
-                      surface: &wgpu::Surface,
-                      config: &mut wgpu::SurfaceConfiguration,
-                      new_size: winit::dpi::PhysicalSize<u32>| {
-            if new_size.width > 0 && new_size.height > 0 {
-                config.width = new_size.width;
-                config.height = new_size.height;
-                surface.configure(&device, &config);
-            }
-        };
+        let mut target = SurfaceTarget::new(surface, config, &device, 1);
 
         let mut draw_layer = Layer::default();
         draw_layer.push_circle(CircleInstance::new(
@@ -122,100 +809,24 @@ mod tests {
             [0.0, 1.0, 0.0, 1.0],
         ));
 
+        let mut renderer = Renderer::new(&adapter, device, queue, surface_format);
+        renderer.set_clear_color(wgpu::Color::TRANSPARENT);
+
         let image_bytes = include_bytes!("test/assets/rust.png");
-        let mut image_res = ImageResource::new(
-            image::load_from_memory(image_bytes).unwrap(),
-            &device,
-            &queue,
-        );
+        let mut image_res = renderer.create_image(image::load_from_memory(image_bytes).unwrap());
         image_res.push_instance(ImageInstance::new([100.0, 100.0], [200.0, 200.0]));
         draw_layer.push_image(image_res);
 
-        let mut circle_pipeline = CirclePipeline::new(&device, surface_format);
-        let mut rect_pipeline = RectPipeline::new(&device, surface_format);
-        let mut triangle_pipeline = TrianglePipeline::new(&device, surface_format);
-        let mut image_pipeline = ImagePipeline::new(&device, surface_format);
-
-        let render = |device: &wgpu::Device,
-                      queue: &wgpu::Queue,
-                      surface: &wgpu::Surface,
-                      circle_pipeline: &mut CirclePipeline,
-                      rect_pipeline: &mut RectPipeline,
-                      triangle_pipeline: &mut TrianglePipeline,
-                      image_pipeline: &mut ImagePipeline,
-                      layer: &Layer,
-                      viewport_bind_group: &wgpu::BindGroup| {
-            let output = surface.get_current_texture().unwrap();
-            let view = output
-                .texture
-                .create_view(&wgpu::TextureViewDescriptor::default());
-            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
-
-            let rect_instance_buffer = layer.get_rect_buffer(device);
-            let circle_instance_buffer = layer.get_circle_buffer(device);
-            let triangle_instance_buffer = layer.get_triangle_buffer(device);
-            let image_instance_buffers = layer.get_image_buffers(device);
-
-            {
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("Render Pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.0,
-                                g: 0.0,
-                                b: 0.0,
-                                a: 0.0,
-                            }),
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
-                });
-
-                rect_pipeline.render(
-                    &mut render_pass,
-                    &rect_instance_buffer,
-                    layer.rects.len() as _,
-                    viewport_bind_group,
-                );
-
-                circle_pipeline.render(
-                    &mut render_pass,
-                    &circle_instance_buffer,
-                    layer.circles.len() as _,
-                    viewport_bind_group,
-                );
-
-                triangle_pipeline.render(
-                    &mut render_pass,
-                    &triangle_instance_buffer,
-                    layer.triangles.len() as _,
-                    viewport_bind_group,
-                );
-
-                image_instance_buffers.iter().for_each(
-                    |(texture, instance_buffer, instance_count)| {
-                        image_pipeline.render(
-                            &mut render_pass,
-                            instance_buffer,
-                            instance_count.clone(),
-                            texture,
-                            viewport_bind_group,
-                        )
-                    },
-                );
-            }
+        let font = Font::from_bytes(include_bytes!("test/assets/DejaVuSansMono.ttf")).unwrap();
+        draw_layer.push_text(Text::new(
+            font,
+            "fn main() {\n    println!(\"atoz\");\n}",
+            [450.0, 260.0],
+            18.0,
+            [1.0, 1.0, 1.0, 1.0],
+        ));
 
-            queue.submit(std::iter::once(encoder.finish()));
-            output.present();
-        };
+        let layers = vec![draw_layer];
 
         return event_loop.run(move |event, elwt| match event {
             Event::WindowEvent {
@@ -226,24 +837,14 @@ mod tests {
                     elwt.exit();
                 }
                 WindowEvent::Resized(physical_size) => {
-                    resize(&device, &surface, &mut config, *physical_size);
-                    viewport =
-                        Viewport::new(physical_size.width as f32, physical_size.height as f32);
+                    target.resize(
+                        renderer.get_device(),
+                        physical_size.width,
+                        physical_size.height,
+                    );
                 }
                 WindowEvent::RedrawRequested => {
-                    let i = Instant::now();
-                    render(
-                        &device,
-                        &queue,
-                        &surface,
-                        &mut circle_pipeline,
-                        &mut rect_pipeline,
-                        &mut triangle_pipeline,
-                        &mut image_pipeline,
-                        &draw_layer,
-                        &mut viewport.get_bind_group(&device),
-                    );
-                    println!("{}", i.elapsed().as_millis());
+                    renderer.render(&target, &layers).unwrap();
                 }
                 _ => {}
             },
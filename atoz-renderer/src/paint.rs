@@ -0,0 +1,111 @@
+use bytemuck::{Pod, Zeroable};
+
+// must match GRADIENT_* in common.wgsl.
+const GRADIENT_LINEAR: u32 = 1;
+const GRADIENT_RADIAL: u32 = 2;
+
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+// gradients are laid out over the box of the instance they paint.
+// angle: radians, 0 runs from left to right and positive angles turn clockwise.
+// center: relative to the box, [0.5, 0.5] is its middle. radius: relative to half the smaller side.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientKind {
+    Linear { angle: f32 },
+    Radial { center: [f32; 2], radius: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientStop {
+    pub offset: f32,
+    pub color: [f32; 4],
+}
+
+// only the first MAX_GRADIENT_STOPS stops are drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    pub fn linear(angle: f32) -> Self {
+        return Self {
+            kind: GradientKind::Linear { angle },
+            stops: vec![],
+        };
+    }
+
+    pub fn radial(center: [f32; 2], radius: f32) -> Self {
+        return Self {
+            kind: GradientKind::Radial { center, radius },
+            stops: vec![],
+        };
+    }
+
+    pub fn add_stop(mut self, offset: f32, color: [f32; 4]) -> Self {
+        self.stops.push(GradientStop { offset, color });
+        return self;
+    }
+
+    pub fn to_data(&self) -> GradientData {
+        let mut stops = self.stops.clone();
+        stops.truncate(MAX_GRADIENT_STOPS);
+        stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+        let mut data = GradientData::zeroed();
+        (data.kind, data.geometry) = match self.kind {
+            GradientKind::Linear { angle } => {
+                (GRADIENT_LINEAR, [angle.cos(), angle.sin(), 0.0, 0.0])
+            }
+            GradientKind::Radial { center, radius } => {
+                (GRADIENT_RADIAL, [center[0], center[1], radius, 0.0])
+            }
+        };
+        data.count = stops.len() as u32;
+        stops.iter().enumerate().for_each(|(index, stop)| {
+            data.offsets[index / 4][index % 4] = stop.offset;
+            data.colors[index] = stop.color;
+        });
+        return data;
+    }
+}
+
+// one gradient in the paint storage buffer. must match Gradient in common.wgsl.
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct GradientData {
+    pub kind: u32,
+    pub count: u32,
+    pub _padding: [u32; 2],
+    pub geometry: [f32; 4],
+    pub offsets: [[f32; 4]; 2],
+    pub colors: [[f32; 4]; MAX_GRADIENT_STOPS],
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Gradient, GRADIENT_LINEAR};
+
+    #[test]
+    fn gradient_data() {
+        let data = Gradient::linear(0.0)
+            .add_stop(1.0, [0.0, 0.0, 1.0, 1.0])
+            .add_stop(0.0, [1.0, 0.0, 0.0, 1.0])
+            .add_stop(0.5, [0.0, 1.0, 0.0, 1.0])
+            .to_data();
+        assert_eq!(std::mem::size_of_val(&data), 192);
+        assert_eq!(data.kind, GRADIENT_LINEAR);
+        assert_eq!(data.count, 3);
+        assert_eq!(data.geometry, [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(data.offsets[0], [0.0, 0.5, 1.0, 0.0]);
+        assert_eq!(data.colors[0], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(data.colors[2], [0.0, 0.0, 1.0, 1.0]);
+
+        let mut gradient = Gradient::radial([0.5, 0.5], 1.0);
+        for index in 0..10 {
+            gradient = gradient.add_stop(index as f32 / 10.0, [1.0; 4]);
+        }
+        assert_eq!(gradient.to_data().count, 8);
+    }
+}
@@ -1,4 +1,9 @@
+pub mod blur;
 pub mod circle;
 pub mod image;
+pub mod line;
+pub mod mesh;
 pub mod rect;
+pub mod shadow;
+pub mod text;
 pub mod triangle;
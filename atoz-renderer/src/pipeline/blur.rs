@@ -0,0 +1,329 @@
+use bytemuck::{Pod, Zeroable};
+use std::mem;
+use wgpu::{util::DeviceExt, BindGroup, Buffer, CommandEncoder, Device, RenderPipeline, Texture};
+
+use crate::{buffer::InstanceBuffer, layouts::BindGroupLayouts};
+
+// a sigma above this would need more taps than blur.wgsl takes.
+pub const MAX_BLUR_SIGMA: f32 = 21.0;
+
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct BlurVertex {
+    pub position: [f32; 2],
+}
+
+impl BlurVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+}
+
+// one direction of a separable blur over a region, in physical pixels.
+// the vertical pass masks the result with the rounded region, radius < 0 disables the mask.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct BlurInstance {
+    pub start: [f32; 2],
+    pub size: [f32; 2],
+    pub target_size: [f32; 2],
+    pub direction: [f32; 2],
+    pub sigma: f32,
+    pub radius: [f32; 4],
+}
+
+impl BlurInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x2,
+        5 => Float32,
+        6 => Float32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+
+    // the horizontal and the vertical pass of a blur.
+    pub fn passes(
+        start: [f32; 2],
+        size: [f32; 2],
+        radius: [f32; 4],
+        target_size: [f32; 2],
+        sigma: f32,
+    ) -> [Self; 2] {
+        let pass = Self {
+            start,
+            size,
+            target_size,
+            direction: [1.0, 0.0],
+            sigma: sigma.min(MAX_BLUR_SIGMA),
+            radius: [-1.0; 4],
+        };
+        return [
+            pass,
+            Self {
+                direction: [0.0, 1.0],
+                radius,
+                ..pass
+            },
+        ];
+    }
+}
+
+const BLUR_VERTICES: [BlurVertex; 4] = [
+    BlurVertex {
+        position: [0.0, 0.0],
+    },
+    BlurVertex {
+        position: [0.0, 1.0],
+    },
+    BlurVertex {
+        position: [1.0, 1.0],
+    },
+    BlurVertex {
+        position: [1.0, 0.0],
+    },
+];
+
+const BLUR_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+// copy of the frame and the horizontally blurred frame, sized like the target.
+#[derive(Debug)]
+struct BlurTextures {
+    size: (u32, u32),
+    format: wgpu::TextureFormat,
+    source: Texture,
+    source_group: BindGroup,
+    temp_view: wgpu::TextureView,
+    temp_group: BindGroup,
+}
+
+#[derive(Debug)]
+pub struct BlurPipeline {
+    pipeline: RenderPipeline,
+    frame_pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    instances: InstanceBuffer,
+    sampler: wgpu::Sampler,
+    layouts: BindGroupLayouts,
+    textures: Option<BlurTextures>,
+}
+
+impl BlurPipeline {
+    pub fn new(
+        device: &Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
+        let blur_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("blur.shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shader/blur.wgsl").into()),
+        });
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("blur.vertex"),
+            contents: bytemuck::cast_slice(&BLUR_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("blur.index"),
+            contents: bytemuck::cast_slice(&BLUR_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blur.pipeline.layout"),
+            bind_group_layouts: &[&layouts.texture],
+            push_constant_ranges: &[],
+        });
+
+        // the first pass draws into a texture of its own, the second one into the frame.
+        let pipeline = create_pipeline(device, format, &pipeline_layout, &blur_shader, 1);
+        let frame_pipeline =
+            create_pipeline(device, format, &pipeline_layout, &blur_shader, sample_count);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        return Self {
+            pipeline,
+            frame_pipeline,
+            vertex_buffer,
+            index_buffer,
+            instances: InstanceBuffer::new(device, "blur.instance.buffer"),
+            sampler,
+            layouts: layouts.clone(),
+            textures: None,
+        };
+    }
+
+    // writes the passes of the frame and makes sure the textures match the frame.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &wgpu::Queue,
+        frame: &Texture,
+        instances: &[BlurInstance],
+    ) {
+        self.instances.write(device, queue, instances);
+
+        let size = (frame.width(), frame.height());
+        let format = frame.format();
+        if let Some(textures) = &self.textures {
+            if textures.size == size && textures.format == format {
+                return;
+            }
+        }
+
+        let create_texture = |label, usage| {
+            return device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: frame.size(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | usage,
+                view_formats: &[],
+            });
+        };
+        let source = create_texture("blur.source", wgpu::TextureUsages::COPY_DST);
+        let temp = create_texture("blur.temp", wgpu::TextureUsages::RENDER_ATTACHMENT);
+        let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+        let temp_view = temp.create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.textures = Some(BlurTextures {
+            size,
+            format,
+            source_group: self.layouts.create_texture_group(
+                device,
+                "blur.source.group",
+                &source_view,
+                &self.sampler,
+            ),
+            temp_group: self.layouts.create_texture_group(
+                device,
+                "blur.temp.group",
+                &temp_view,
+                &self.sampler,
+            ),
+            source,
+            temp_view,
+        });
+    }
+
+    // blurs the region of the frame described by the instances of pass 2 * index and 2 * index + 1.
+    // frame is the resolved texture, frame_view the attachment the layers are drawn into.
+    pub fn render(
+        &self,
+        encoder: &mut CommandEncoder,
+        frame: &Texture,
+        frame_view: &wgpu::TextureView,
+        index: u32,
+    ) {
+        let textures = self
+            .textures
+            .as_ref()
+            .expect("BlurPipeline : prepare must be called before render.");
+
+        encoder.copy_texture_to_texture(
+            frame.as_image_copy(),
+            textures.source.as_image_copy(),
+            frame.size(),
+        );
+
+        let passes = [
+            (
+                &self.pipeline,
+                &textures.temp_view,
+                &textures.source_group,
+                index * 2,
+            ),
+            (
+                &self.frame_pipeline,
+                frame_view,
+                &textures.temp_group,
+                index * 2 + 1,
+            ),
+        ];
+        for (pipeline, view, group, instance) in passes {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("blur.render_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instances.get_buffer().slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..BLUR_INDICES.len() as u32, 0, instance..instance + 1);
+        }
+    }
+}
+
+fn create_pipeline(
+    device: &Device,
+    format: wgpu::TextureFormat,
+    pipeline_layout: &wgpu::PipelineLayout,
+    blur_shader: &wgpu::ShaderModule,
+    sample_count: u32,
+) -> RenderPipeline {
+    return device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("blur.pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: blur_shader,
+            entry_point: "vs_main",
+            buffers: &[BlurVertex::desc(), BlurInstance::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: blur_shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    });
+}
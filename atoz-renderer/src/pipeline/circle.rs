@@ -1,8 +1,8 @@
 use bytemuck::{Pod, Zeroable};
-use std::mem;
+use std::{mem, ops::Range};
 use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
 
-use crate::viewport::Viewport;
+use crate::layouts::BindGroupLayouts;
 
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 #[repr(C)]
@@ -30,15 +30,17 @@ pub struct CircleInstance {
     pub thickness: f32,
     pub fill_color: [f32; 4],
     pub line_color: [f32; 4],
+    pub paint: [u32; 2],
 }
 
 impl CircleInstance {
-    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
         1 => Float32x2,
         2 => Float32,
         3 => Float32,
         4 => Float32x4,
         5 => Float32x4,
+        6 => Uint32x2,
     ];
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
@@ -78,6 +80,7 @@ impl CircleInstance {
             thickness: thickness as f32,
             fill_color,
             line_color,
+            paint: [0, 0],
         };
     }
 
@@ -88,6 +91,7 @@ impl CircleInstance {
             thickness: 1.0,
             fill_color: color,
             line_color: color,
+            paint: [0, 0],
         };
     }
 
@@ -102,8 +106,20 @@ impl CircleInstance {
             thickness: thickness as f32,
             fill_color: [0.0, 0.0, 0.0, 0.0],
             line_color: color,
+            paint: [0, 0],
         };
     }
+
+    // paints are returned by Layer::push_gradient and replace the flat color. 0 is the flat color.
+    pub fn set_fill_paint(mut self, paint: u32) -> Self {
+        self.paint[0] = paint;
+        return self;
+    }
+
+    pub fn set_line_paint(mut self, paint: u32) -> Self {
+        self.paint[1] = paint;
+        return self;
+    }
 }
 
 pub const CIRCLE_VERTICES: [CircleVertex; 4] = [
@@ -131,10 +147,21 @@ pub struct CirclePipeline {
 }
 
 impl CirclePipeline {
-    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
         let circle_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("circle.shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shader/circle.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shader/common.wgsl"),
+                    include_str!("../shader/circle.wgsl")
+                )
+                .into(),
+            ),
         });
 
         let vertex_buffer = CircleInstance::get_vertex_buffer(device);
@@ -142,7 +169,7 @@ impl CirclePipeline {
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("circle.pipeline.layout"),
-            bind_group_layouts: &[&Viewport::layout(device)],
+            bind_group_layouts: &[&layouts.viewport],
             push_constant_ranges: &[],
         });
 
@@ -167,14 +194,14 @@ impl CirclePipeline {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
             },
             depth_stencil: None,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -192,16 +219,17 @@ impl CirclePipeline {
         &'a self,
         render_pass: &mut RenderPass<'a>,
         instance_buffer: &'a Buffer,
-        instance_count: u32,
+        instances: Range<u32>,
         viewport: &'a BindGroup,
+        state_offset: u32,
     ) {
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, viewport, &[]);
+        render_pass.set_bind_group(0, viewport, &[state_offset]);
 
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-        render_pass.draw_indexed(0..CIRCLE_INDICES.len() as u32, 0, 0..instance_count);
+        render_pass.draw_indexed(0..CIRCLE_INDICES.len() as u32, 0, instances);
     }
 }
@@ -1,13 +1,15 @@
-use std::mem;
+use std::{mem, ops::Range, sync::Arc};
 
 use bytemuck::{Pod, Zeroable};
 use image::{DynamicImage, GenericImageView};
 use wgpu::{
-    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline,
-    Sampler, Texture, TextureView,
+    util::DeviceExt, BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, Sampler,
+    Texture, TextureView,
 };
 
-use crate::viewport::Viewport;
+use crate::layouts::BindGroupLayouts;
+
+pub mod atlas;
 
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 #[repr(C)]
@@ -32,11 +34,19 @@ impl ImageVertex {
 pub struct ImageInstance {
     pub start: [f32; 2],
     pub size: [f32; 2],
+    pub uv_start: [f32; 2],
+    pub uv_size: [f32; 2],
+    pub opacity: f32,
 }
 
 impl ImageInstance {
-    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![1 => Float32x2, 2 => Float32x2];
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x2,
+        5 => Float32,
+    ];
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         return wgpu::VertexBufferLayout {
@@ -63,7 +73,29 @@ impl ImageInstance {
     }
 
     pub fn new(start: [f32; 2], size: [f32; 2]) -> Self {
-        return Self { start, size };
+        return Self {
+            start,
+            size,
+            uv_start: [0.0, 0.0],
+            uv_size: [1.0, 1.0],
+            opacity: 1.0,
+        };
+    }
+
+    // draws only the uv rectangle of the texture, in normalized texture coordinates.
+    pub fn with_uv(start: [f32; 2], size: [f32; 2], uv_start: [f32; 2], uv_size: [f32; 2]) -> Self {
+        return Self {
+            start,
+            size,
+            uv_start,
+            uv_size,
+            opacity: 1.0,
+        };
+    }
+
+    pub fn set_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        return self;
     }
 }
 
@@ -85,15 +117,29 @@ const IMAGE_VERTICES: [ImageVertex; 4] = [
 const IMAGE_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
 
 #[derive(Debug)]
-pub struct ImageResource {
+pub struct ImageTexture {
     pub texture: Texture,
     pub view: TextureView,
     pub sampler: Sampler,
+    pub bind_group: BindGroup,
+    // the texture was rendered into, so its colors are multiplied by their alpha.
+    pub premultiplied: bool,
+}
+
+// the texture and its bind group are shared, so cloning a resource into a new layer is cheap.
+#[derive(Clone, Debug)]
+pub struct ImageResource {
+    pub texture: Arc<ImageTexture>,
     pub instances: Vec<ImageInstance>,
 }
 
 impl ImageResource {
-    pub fn new(image: DynamicImage, device: &Device, queue: &Queue) -> Self {
+    pub fn new(
+        image: DynamicImage,
+        device: &Device,
+        queue: &Queue,
+        layouts: &BindGroupLayouts,
+    ) -> Self {
         let data = image.to_rgba8();
         let dimension = image.dimensions();
 
@@ -131,6 +177,16 @@ impl ImageResource {
             size,
         );
 
+        return Self::from_texture(texture, false, device, layouts);
+    }
+
+    // image sampling an existing texture, which needs the TEXTURE_BINDING usage.
+    pub fn from_texture(
+        texture: Texture,
+        premultiplied: bool,
+        device: &Device,
+        layouts: &BindGroupLayouts,
+    ) -> Self {
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -141,73 +197,89 @@ impl ImageResource {
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
         });
+        let bind_group = layouts.create_texture_group(device, "image.group", &view, &sampler);
 
-        Self {
-            texture,
-            view,
-            sampler,
+        return Self {
+            texture: Arc::new(ImageTexture {
+                texture,
+                view,
+                sampler,
+                bind_group,
+                premultiplied,
+            }),
             instances: vec![],
-        }
-    }
-
-    fn layout(device: &Device) -> BindGroupLayout {
-        return device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("image.layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-        });
+        };
     }
 
-    pub fn get_bind_group(&self, device: &Device) -> BindGroup {
-        return device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("image.group"),
-            layout: &Self::layout(device),
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&self.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&self.sampler),
-                },
-            ],
-        });
+    pub fn get_bind_group(&self) -> &BindGroup {
+        return &self.texture.bind_group;
     }
 
     pub fn push_instance(&mut self, instance: ImageInstance) {
         self.instances.push(instance);
     }
+
+    pub fn clear_instances(&mut self) {
+        self.instances.clear();
+    }
+}
+
+// one image rasterized at several scale factors, such as icon.png and icon@2x.png.
+#[derive(Clone, Debug, Default)]
+pub struct ScaledImage {
+    variants: Vec<(f32, ImageResource)>,
+}
+
+impl ScaledImage {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    // the resource holds the image rasterized at scale_factor physical pixels per logical pixel.
+    pub fn add(&mut self, scale_factor: f32, resource: ImageResource) {
+        self.variants.push((scale_factor, resource));
+        self.variants.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    // the smallest variant at least as sharp as scale_factor, or the sharpest one.
+    // the returned resource has no instances.
+    pub fn get_resource(&self, scale_factor: f32) -> Option<ImageResource> {
+        let (_, resource) = self
+            .variants
+            .iter()
+            .find(|(scale, _)| *scale >= scale_factor)
+            .or(self.variants.last())?;
+        return Some(ImageResource {
+            texture: resource.texture.clone(),
+            instances: vec![],
+        });
+    }
 }
 
 #[derive(Debug)]
 pub struct ImagePipeline {
     pipeline: RenderPipeline,
+    premultiplied_pipeline: RenderPipeline,
     vertex_buffer: Buffer,
     index_buffer: Buffer,
 }
 
 impl ImagePipeline {
-    pub fn new(device: &Device, format: wgpu::TextureFormat) -> Self {
+    pub fn new(
+        device: &Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
         let image_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("image.shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shader/image.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shader/common.wgsl"),
+                    include_str!("../shader/image.wgsl")
+                )
+                .into(),
+            ),
         });
 
         let vertex_buffer = ImageInstance::get_vertex_buffer(device);
@@ -215,13 +287,13 @@ impl ImagePipeline {
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("image.pipeline.layout"),
-            bind_group_layouts: &[&Viewport::layout(device), &ImageResource::layout(device)],
+            bind_group_layouts: &[&layouts.viewport, &layouts.texture],
             push_constant_ranges: &[],
         });
 
-        let pipeline: RenderPipeline =
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("image.pipeline"),
+        let create_pipeline = |label, entry_point, blend| {
+            return device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
                 layout: Some(&pipeline_layout),
                 vertex: wgpu::VertexState {
                     module: &image_shader,
@@ -230,10 +302,10 @@ impl ImagePipeline {
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &image_shader,
-                    entry_point: "fs_main",
+                    entry_point,
                     targets: &[Some(wgpu::ColorTargetState {
                         format,
-                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        blend: Some(blend),
                         write_mask: wgpu::ColorWrites::ALL,
                     })],
                 }),
@@ -241,22 +313,32 @@ impl ImagePipeline {
                     topology: wgpu::PrimitiveTopology::TriangleList,
                     strip_index_format: None,
                     front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
+                    cull_mode: None,
                     polygon_mode: wgpu::PolygonMode::Fill,
                     unclipped_depth: false,
                     conservative: false,
                 },
                 depth_stencil: None,
                 multisample: wgpu::MultisampleState {
-                    count: 1,
+                    count: sample_count,
                     mask: !0,
                     alpha_to_coverage_enabled: false,
                 },
                 multiview: None,
             });
+        };
 
         return Self {
-            pipeline,
+            pipeline: create_pipeline(
+                "image.pipeline",
+                "fs_main",
+                wgpu::BlendState::ALPHA_BLENDING,
+            ),
+            premultiplied_pipeline: create_pipeline(
+                "image.premultiplied_pipeline",
+                "fs_premultiplied",
+                wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            ),
             vertex_buffer,
             index_buffer,
         };
@@ -266,18 +348,22 @@ impl ImagePipeline {
         &'a self,
         render_pass: &mut RenderPass<'a>,
         instance_buffer: &'a Buffer,
-        instance_count: u32,
-        texture: &'a BindGroup,
+        instances: Range<u32>,
+        texture: &'a ImageTexture,
         viewport: &'a BindGroup,
+        state_offset: u32,
     ) {
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, viewport, &[]);
-        render_pass.set_bind_group(1, texture, &[]);
+        render_pass.set_pipeline(match texture.premultiplied {
+            true => &self.premultiplied_pipeline,
+            false => &self.pipeline,
+        });
+        render_pass.set_bind_group(0, viewport, &[state_offset]);
+        render_pass.set_bind_group(1, &texture.bind_group, &[]);
 
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-        render_pass.draw_indexed(0..IMAGE_INDICES.len() as u32, 0, 0..instance_count);
+        render_pass.draw_indexed(0..IMAGE_INDICES.len() as u32, 0, instances);
     }
 }
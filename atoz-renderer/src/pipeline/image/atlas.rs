@@ -0,0 +1,255 @@
+use std::{fmt, sync::Arc};
+
+use etagere::{size2, AllocId, AtlasAllocator};
+use image::{DynamicImage, RgbaImage};
+use wgpu::{Device, Queue};
+
+use super::{ImageInstance, ImageResource, ImageTexture};
+use crate::layouts::BindGroupLayouts;
+
+const IMAGE_PADDING: i32 = 1;
+
+// not Clone, so that an image cannot be removed from its atlas twice.
+#[derive(Debug, PartialEq)]
+pub struct AtlasImage {
+    page: usize,
+    alloc: AllocId,
+    pub uv_start: [f32; 2],
+    pub uv_size: [f32; 2],
+    pub size: [u32; 2],
+}
+
+impl AtlasImage {
+    pub fn get_page(&self) -> usize {
+        return self.page;
+    }
+
+    pub fn instance(&self, start: [f32; 2], size: [f32; 2]) -> ImageInstance {
+        return ImageInstance::with_uv(start, size, self.uv_start, self.uv_size);
+    }
+}
+
+struct AtlasPage {
+    allocator: AtlasAllocator,
+    texture: Arc<ImageTexture>,
+}
+
+impl fmt::Debug for AtlasPage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f
+            .debug_struct("AtlasPage")
+            .field("allocated", &self.allocator.allocated_space())
+            .finish();
+    }
+}
+
+// packs small images such as icons into shared textures, so that they draw in one call per page.
+#[derive(Debug)]
+pub struct ImageAtlas {
+    pages: Vec<AtlasPage>,
+    page_size: u32,
+    layouts: BindGroupLayouts,
+}
+
+impl ImageAtlas {
+    pub fn new(layouts: &BindGroupLayouts, page_size: u32) -> Self {
+        return Self {
+            pages: vec![],
+            page_size,
+            layouts: layouts.clone(),
+        };
+    }
+
+    pub fn get_page_count(&self) -> usize {
+        return self.pages.len();
+    }
+
+    // returns None when the image is empty or does not fit in a single page.
+    pub fn add(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        image: &DynamicImage,
+    ) -> Option<AtlasImage> {
+        let data = image.to_rgba8();
+        let (width, height) = data.dimensions();
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let alloc_size = size2(
+            width as i32 + IMAGE_PADDING * 2,
+            height as i32 + IMAGE_PADDING * 2,
+        );
+        if alloc_size.width > self.page_size as i32 || alloc_size.height > self.page_size as i32 {
+            return None;
+        }
+
+        let mut found = None;
+        for (index, page) in self.pages.iter_mut().enumerate() {
+            if let Some(allocation) = page.allocator.allocate(alloc_size) {
+                found = Some((index, allocation));
+                break;
+            }
+        }
+        let (page, allocation) = match found {
+            Some(found) => found,
+            None => {
+                let mut page = self.create_page(device);
+                let allocation = page.allocator.allocate(alloc_size)?;
+                self.pages.push(page);
+                (self.pages.len() - 1, allocation)
+            }
+        };
+
+        // the edge pixels are repeated into the padding, so that filtering at the edges of the
+        // image does not blend in the texels around it.
+        let padding = IMAGE_PADDING as u32;
+        let padded = RgbaImage::from_fn(width + padding * 2, height + padding * 2, |x, y| {
+            let x = x.saturating_sub(padding).min(width - 1);
+            let y = y.saturating_sub(padding).min(height - 1);
+            return *data.get_pixel(x, y);
+        });
+        let x = allocation.rectangle.min.x as u32;
+        let y = allocation.rectangle.min.y as u32;
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.pages[page].texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &padded,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * padded.width()),
+                rows_per_image: Some(padded.height()),
+            },
+            wgpu::Extent3d {
+                width: padded.width(),
+                height: padded.height(),
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let (x, y) = (x + padding, y + padding);
+
+        let page_size = self.page_size as f32;
+        return Some(AtlasImage {
+            page,
+            alloc: allocation.id,
+            uv_start: [x as f32 / page_size, y as f32 / page_size],
+            uv_size: [width as f32 / page_size, height as f32 / page_size],
+            size: [width, height],
+        });
+    }
+
+    pub fn remove(&mut self, image: AtlasImage) {
+        if let Some(page) = self.pages.get_mut(image.page) {
+            page.allocator.deallocate(image.alloc);
+        }
+    }
+
+    // resource for the page holding the image, ready to receive instances.
+    pub fn get_resource(&self, image: &AtlasImage) -> ImageResource {
+        return ImageResource {
+            texture: self.pages[image.page].texture.clone(),
+            instances: vec![],
+        };
+    }
+
+    fn create_page(&self, device: &Device) -> AtlasPage {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("image_atlas.texture"),
+            size: wgpu::Extent3d {
+                width: self.page_size,
+                height: self.page_size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let bind_group =
+            self.layouts
+                .create_texture_group(device, "image_atlas.group", &view, &sampler);
+
+        return AtlasPage {
+            allocator: AtlasAllocator::new(size2(self.page_size as i32, self.page_size as i32)),
+            texture: Arc::new(ImageTexture {
+                texture,
+                view,
+                sampler,
+                bind_group,
+                premultiplied: false,
+            }),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    use super::ImageAtlas;
+    use crate::{
+        layer::{DrawCommand, Layer},
+        tests::headless_renderer,
+    };
+
+    #[test]
+    fn atlas_pages() {
+        let mut renderer = headless_renderer();
+        let (device, queue) = (renderer.get_device(), renderer.get_queue());
+        let icon = DynamicImage::ImageRgba8(RgbaImage::from_pixel(30, 30, Rgba([255, 0, 0, 255])));
+
+        let mut atlas = ImageAtlas::new(renderer.get_layouts(), 64);
+        let first = atlas.add(device, queue, &icon).unwrap();
+        let second = atlas.add(device, queue, &icon).unwrap();
+        assert_eq!(atlas.get_page_count(), 1);
+        assert_eq!(first.uv_start, [1.0 / 64.0, 1.0 / 64.0]);
+        assert_ne!(first.uv_start, second.uv_start);
+
+        let mut layer = Layer::default();
+        layer.push_atlas_image(&atlas, &first, [0.0, 0.0], [16.0, 16.0]);
+        layer.push_atlas_image(&atlas, &second, [16.0, 0.0], [16.0, 16.0]);
+        assert_eq!(layer.commands.len(), 1);
+        assert!(
+            matches!(&layer.commands[0], DrawCommand::Image(image) if image.instances.len() == 2)
+        );
+
+        atlas.add(device, queue, &icon).unwrap();
+        atlas.add(device, queue, &icon).unwrap();
+        atlas.add(device, queue, &icon).unwrap();
+        assert_eq!(atlas.get_page_count(), 2);
+
+        // scaled up, the edge of the image does not blend with the padding around it.
+        let mut layer = Layer::default();
+        layer.push_atlas_image(&atlas, &first, [0.0, 0.0], [60.0, 60.0]);
+        let target = renderer.create_offscreen_target(60, 60);
+        renderer.render(&target, &[layer]).unwrap();
+        assert_eq!(
+            renderer.read_image(&target).get_pixel(0, 0).0,
+            [255, 0, 0, 255]
+        );
+        let (device, queue) = (renderer.get_device(), renderer.get_queue());
+
+        atlas.remove(first);
+        assert_eq!(atlas.add(device, queue, &icon).unwrap().get_page(), 0);
+
+        let large = DynamicImage::ImageRgba8(RgbaImage::new(100, 10));
+        assert!(atlas.add(device, queue, &large).is_none());
+    }
+}
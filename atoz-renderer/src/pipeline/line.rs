@@ -0,0 +1,375 @@
+use bytemuck::{Pod, Zeroable};
+use std::{mem, ops::Range};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
+
+use crate::layouts::BindGroupLayouts;
+
+// end kinds of a segment, must match line.wgsl.
+const END_BUTT: u32 = 0;
+const END_ROUND: u32 = 1;
+const END_SQUARE: u32 = 2;
+const END_JOIN: u32 = 3;
+const END_ROUND_JOIN: u32 = 4;
+
+// a miter longer than MITER_LIMIT * thickness / 2 falls back to a round join.
+const MITER_LIMIT: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+}
+
+// dash: [dash length, gap length]. a dash length of 0 draws a solid line.
+#[derive(Clone, Copy, Debug)]
+pub struct LineStyle {
+    pub thickness: f32,
+    pub color: [f32; 4],
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub dash: [f32; 2],
+    pub dash_offset: f32,
+}
+
+impl LineStyle {
+    pub fn new(thickness: f32, color: [f32; 4]) -> Self {
+        return Self {
+            thickness,
+            color,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            dash: [0.0, 0.0],
+            dash_offset: 0.0,
+        };
+    }
+
+    pub fn set_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        return self;
+    }
+
+    pub fn set_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        return self;
+    }
+
+    pub fn set_dash(mut self, dash: f32, gap: f32, offset: f32) -> Self {
+        self.dash = [dash, gap];
+        self.dash_offset = offset;
+        return self;
+    }
+
+    fn cap_kind(&self) -> u32 {
+        return match self.cap {
+            LineCap::Butt => END_BUTT,
+            LineCap::Round => END_ROUND,
+            LineCap::Square => END_SQUARE,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct LineVertex {
+    pub position: [f32; 2],
+}
+
+impl LineVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+}
+
+// one segment. at a join the segment is cut by the plane through the joint point
+// with the join normal, so that neighbouring segments meet without overlapping.
+// a round join is cut the same way, each segment drawing the half of the disc on its side.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct LineInstance {
+    pub start: [f32; 2],
+    pub end: [f32; 2],
+    pub start_normal: [f32; 2],
+    pub end_normal: [f32; 2],
+    pub thickness: f32,
+    pub kind: [u32; 2],
+    pub dash: [f32; 2],
+    pub dash_offset: f32,
+    pub color: [f32; 4],
+}
+
+impl LineInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 9] = wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x2,
+        5 => Float32,
+        6 => Uint32x2,
+        7 => Float32x2,
+        8 => Float32,
+        9 => Float32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+
+    pub fn get_vertex_buffer(device: &Device) -> wgpu::Buffer {
+        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("line_instance.vertex"),
+            contents: bytemuck::cast_slice(&LINE_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+    }
+
+    pub fn get_index_buffer(device: &Device) -> wgpu::Buffer {
+        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("line_instance.index"),
+            contents: bytemuck::cast_slice(&LINE_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+    }
+
+    pub fn new(start: [f32; 2], end: [f32; 2], style: &LineStyle) -> Self {
+        let cap = style.cap_kind();
+        return Self {
+            start,
+            end,
+            start_normal: [0.0, 0.0],
+            end_normal: [0.0, 0.0],
+            thickness: style.thickness,
+            kind: [cap, cap],
+            dash: style.dash,
+            dash_offset: style.dash_offset,
+            color: style.color,
+        };
+    }
+
+    // segments of a polyline. the dash pattern continues across joints.
+    pub fn polyline(points: &[[f32; 2]], style: &LineStyle) -> Vec<Self> {
+        let points = points
+            .iter()
+            .enumerate()
+            .filter(|(index, point)| *index == 0 || distance(points[index - 1], **point) > 0.0)
+            .map(|(_, point)| *point)
+            .collect::<Vec<_>>();
+        if points.len() < 2 {
+            return vec![];
+        }
+
+        let mut instances = vec![];
+        let mut offset = style.dash_offset;
+        for index in 0..points.len() - 1 {
+            let mut instance = Self::new(points[index], points[index + 1], style);
+            instance.dash_offset = offset;
+            offset += distance(points[index], points[index + 1]);
+            instances.push(instance);
+        }
+
+        for index in 1..points.len() - 1 {
+            let before = direction(points[index - 1], points[index]);
+            let after = direction(points[index], points[index + 1]);
+            let bisector = normalize([before[0] + after[0], before[1] + after[1]]);
+            let cos = bisector[0] * before[0] + bisector[1] * before[1];
+            // segments turning back on each other have no plane between them.
+            let (kind, normal) = match style.join {
+                _ if bisector == [0.0, 0.0] => (END_ROUND, bisector),
+                LineJoin::Miter if cos > 1.0 / MITER_LIMIT => (END_JOIN, bisector),
+                _ => (END_ROUND_JOIN, bisector),
+            };
+            instances[index - 1].kind[1] = kind;
+            instances[index - 1].end_normal = normal;
+            instances[index].kind[0] = kind;
+            instances[index].start_normal = normal;
+        }
+        return instances;
+    }
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    return ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt();
+}
+
+fn direction(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    return normalize([b[0] - a[0], b[1] - a[1]]);
+}
+
+fn normalize(v: [f32; 2]) -> [f32; 2] {
+    let length = (v[0] * v[0] + v[1] * v[1]).sqrt();
+    if length == 0.0 {
+        return [0.0, 0.0];
+    }
+    return [v[0] / length, v[1] / length];
+}
+
+// x : along the segment, y : across the segment. expanded in the vertex shader.
+pub const LINE_VERTICES: [LineVertex; 4] = [
+    LineVertex {
+        position: [0.0, 0.0],
+    },
+    LineVertex {
+        position: [0.0, 1.0],
+    },
+    LineVertex {
+        position: [1.0, 1.0],
+    },
+    LineVertex {
+        position: [1.0, 0.0],
+    },
+];
+
+pub const LINE_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+#[derive(Debug)]
+pub struct LinePipeline {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+}
+
+impl LinePipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
+        let line_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("line.shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shader/common.wgsl"),
+                    include_str!("../shader/line.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let vertex_buffer = LineInstance::get_vertex_buffer(device);
+        let index_buffer = LineInstance::get_index_buffer(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("line.pipeline.layout"),
+            bind_group_layouts: &[&layouts.viewport],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("line.pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &line_shader,
+                entry_point: "vs_main",
+                buffers: &[LineVertex::desc(), LineInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &line_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            // the quad winding depends on the segment direction, so nothing is culled.
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        return Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+        };
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        instance_buffer: &'a Buffer,
+        instances: Range<u32>,
+        viewport: &'a BindGroup,
+        state_offset: u32,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, viewport, &[state_offset]);
+
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        render_pass.draw_indexed(0..LINE_INDICES.len() as u32, 0, instances);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LineInstance, LineJoin, LineStyle, END_BUTT, END_JOIN, END_ROUND, END_ROUND_JOIN};
+
+    #[test]
+    fn polyline_joins() {
+        let style = LineStyle::new(2.0, [1.0; 4]).set_dash(4.0, 2.0, 1.0);
+        let points = [
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 10.0],
+            [0.0, 10.5],
+        ];
+        let instances = LineInstance::polyline(&points, &style);
+
+        assert_eq!(instances.len(), 3);
+        assert_eq!(instances[0].kind, [END_BUTT, END_JOIN]);
+        assert_eq!(instances[1].kind, [END_JOIN, END_JOIN]);
+        assert_eq!(instances[2].kind, [END_JOIN, END_BUTT]);
+        assert_eq!(instances[1].dash_offset, 11.0);
+
+        // a sharp turn exceeds the miter limit.
+        let points = [[0.0, 0.0], [10.0, 0.0], [0.0, 0.5]];
+        let instances = LineInstance::polyline(&points, &style);
+        assert_eq!(instances[0].kind[1], END_ROUND_JOIN);
+
+        // a round join is cut between the segments like a miter.
+        let style = style.set_join(LineJoin::Round);
+        let instances = LineInstance::polyline(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]], &style);
+        assert_eq!(instances[0].kind[1], END_ROUND_JOIN);
+        assert_eq!(instances[1].kind[0], END_ROUND_JOIN);
+        assert_eq!(instances[0].end_normal, instances[1].start_normal);
+        assert_ne!(instances[0].end_normal, [0.0, 0.0]);
+
+        let instances = LineInstance::polyline(&[[0.0, 0.0], [1.0, 0.0], [0.0, 0.0]], &style);
+        assert_eq!(instances[0].kind[1], END_ROUND);
+        assert!(LineInstance::polyline(&[[1.0, 1.0]], &style).is_empty());
+    }
+}
@@ -0,0 +1,410 @@
+use std::{fmt, mem, ops::Range, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+    StrokeVertex, TessellationError, VertexBuffers,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
+
+use super::line::{LineCap, LineJoin, LineStyle};
+use crate::{layouts::BindGroupLayouts, transform::Transform};
+
+pub use lyon::{math::point, path::Path};
+
+pub mod svg;
+
+// maximum distance in pixels between a curve and its tessellation.
+const TOLERANCE: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+    Tessellation(TessellationError),
+    Svg(usvg::Error),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            MeshError::Tessellation(err) => write!(f, "mesh tessellation error : {:?}", err),
+            MeshError::Svg(err) => write!(f, "mesh svg error : {}", err),
+        };
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl From<TessellationError> for MeshError {
+    fn from(err: TessellationError) -> Self {
+        return MeshError::Tessellation(err);
+    }
+}
+
+impl From<usvg::Error> for MeshError {
+    fn from(err: usvg::Error) -> Self {
+        return MeshError::Svg(err);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct MeshVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl MeshVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+}
+
+// places a mesh : its vertices are scaled, then moved to start. the color multiplies the vertex colors.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct MeshInstance {
+    pub start: [f32; 2],
+    pub scale: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl MeshInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+
+    pub fn new(start: [f32; 2], scale: [f32; 2], color: [f32; 4]) -> Self {
+        return Self {
+            start,
+            scale,
+            color,
+        };
+    }
+}
+
+// triangles tessellated on the cpu, in pixels. size is the box the mesh was drawn for.
+// the edges are not anti-aliased in the shader, smooth meshes need a sample_count above 1.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+    pub size: [f32; 2],
+}
+
+impl Mesh {
+    pub fn new(size: [f32; 2]) -> Self {
+        return Self {
+            vertices: vec![],
+            indices: vec![],
+            size,
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.indices.is_empty();
+    }
+
+    pub fn fill_path(
+        &mut self,
+        path: &Path,
+        rule: FillRule,
+        color: [f32; 4],
+    ) -> Result<(), MeshError> {
+        return self.fill_transformed(path, rule, color, &Transform::identity());
+    }
+
+    // dash patterns are only supported by the line pipeline, they are ignored here.
+    pub fn stroke_path(&mut self, path: &Path, style: &LineStyle) -> Result<(), MeshError> {
+        return self.stroke_transformed(path, style, &Transform::identity());
+    }
+
+    // tessellates in the space of the path and transforms the vertices afterwards,
+    // so that strokes are scaled with the path.
+    fn fill_transformed(
+        &mut self,
+        path: &Path,
+        rule: FillRule,
+        color: [f32; 4],
+        transform: &Transform,
+    ) -> Result<(), MeshError> {
+        let options = FillOptions::tolerance(get_tolerance(transform)).with_fill_rule(match rule {
+            FillRule::NonZero => lyon::tessellation::FillRule::NonZero,
+            FillRule::EvenOdd => lyon::tessellation::FillRule::EvenOdd,
+        });
+        let mut buffers: VertexBuffers<MeshVertex, u32> = VertexBuffers::new();
+        FillTessellator::new().tessellate_path(
+            path,
+            &options,
+            &mut BuffersBuilder::new(&mut buffers, |vertex: FillVertex| MeshVertex {
+                position: vertex.position().to_array(),
+                color,
+            }),
+        )?;
+        self.append(buffers, transform);
+        return Ok(());
+    }
+
+    fn stroke_transformed(
+        &mut self,
+        path: &Path,
+        style: &LineStyle,
+        transform: &Transform,
+    ) -> Result<(), MeshError> {
+        let cap = match style.cap {
+            LineCap::Butt => lyon::tessellation::LineCap::Butt,
+            LineCap::Round => lyon::tessellation::LineCap::Round,
+            LineCap::Square => lyon::tessellation::LineCap::Square,
+        };
+        let join = match style.join {
+            LineJoin::Miter => lyon::tessellation::LineJoin::Miter,
+            LineJoin::Round => lyon::tessellation::LineJoin::Round,
+        };
+        let options = StrokeOptions::tolerance(get_tolerance(transform))
+            .with_line_width(style.thickness)
+            .with_line_cap(cap)
+            .with_line_join(join);
+        let color = style.color;
+        let mut buffers: VertexBuffers<MeshVertex, u32> = VertexBuffers::new();
+        StrokeTessellator::new().tessellate_path(
+            path,
+            &options,
+            &mut BuffersBuilder::new(&mut buffers, |vertex: StrokeVertex| MeshVertex {
+                position: vertex.position().to_array(),
+                color,
+            }),
+        )?;
+        self.append(buffers, transform);
+        return Ok(());
+    }
+
+    fn append(&mut self, buffers: VertexBuffers<MeshVertex, u32>, transform: &Transform) {
+        let offset = self.vertices.len() as u32;
+        self.vertices
+            .extend(buffers.vertices.into_iter().map(|vertex| MeshVertex {
+                position: transform.apply(vertex.position),
+                color: vertex.color,
+            }));
+        self.indices
+            .extend(buffers.indices.into_iter().map(|index| index + offset));
+    }
+}
+
+// a path drawn scaled up needs a finer tessellation.
+fn get_tolerance(transform: &Transform) -> f32 {
+    let scale = transform.get_determinant().abs().sqrt();
+    if scale < f32::EPSILON {
+        return TOLERANCE;
+    }
+    return TOLERANCE / scale;
+}
+
+#[derive(Debug)]
+pub struct MeshBuffers {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    pub index_count: u32,
+    pub size: [f32; 2],
+}
+
+// a mesh uploaded once and drawn any number of times, like an ImageResource.
+#[derive(Clone, Debug)]
+pub struct MeshResource {
+    pub buffers: Arc<MeshBuffers>,
+    pub instances: Vec<MeshInstance>,
+}
+
+impl MeshResource {
+    pub fn new(mesh: &Mesh, device: &Device) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh.vertex"),
+            contents: bytemuck::cast_slice(&mesh.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh.index"),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        return Self {
+            buffers: Arc::new(MeshBuffers {
+                vertex_buffer,
+                index_buffer,
+                index_count: mesh.indices.len() as u32,
+                size: mesh.size,
+            }),
+            instances: vec![],
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.buffers.index_count == 0;
+    }
+
+    pub fn get_size(&self) -> [f32; 2] {
+        return self.buffers.size;
+    }
+
+    // instance stretching the mesh over the given rect.
+    pub fn instance(&self, start: [f32; 2], size: [f32; 2], color: [f32; 4]) -> MeshInstance {
+        let [width, height] = self.buffers.size;
+        let scale = [
+            if width > 0.0 { size[0] / width } else { 1.0 },
+            if height > 0.0 { size[1] / height } else { 1.0 },
+        ];
+        return MeshInstance::new(start, scale, color);
+    }
+
+    pub fn push_instance(&mut self, instance: MeshInstance) {
+        self.instances.push(instance);
+    }
+
+    pub fn clear_instances(&mut self) {
+        self.instances.clear();
+    }
+}
+
+#[derive(Debug)]
+pub struct MeshPipeline {
+    pipeline: RenderPipeline,
+}
+
+impl MeshPipeline {
+    pub fn new(
+        device: &Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
+        let mesh_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("mesh.shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shader/common.wgsl"),
+                    include_str!("../shader/mesh.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("mesh.pipeline.layout"),
+            bind_group_layouts: &[&layouts.viewport],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("mesh.pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &mesh_shader,
+                entry_point: "vs_main",
+                buffers: &[MeshVertex::desc(), MeshInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &mesh_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            // tessellated and mirrored meshes have both windings.
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        return Self { pipeline };
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        instance_buffer: &'a Buffer,
+        instances: Range<u32>,
+        mesh: &'a MeshBuffers,
+        viewport: &'a BindGroup,
+        state_offset: u32,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, viewport, &[state_offset]);
+
+        render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        render_pass.draw_indexed(0..mesh.index_count, 0, instances);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{point, FillRule, Mesh, Path};
+    use crate::pipeline::line::LineStyle;
+
+    #[test]
+    fn mesh_tessellation() {
+        let mut builder = Path::builder();
+        builder.begin(point(0.0, 0.0));
+        builder.line_to(point(10.0, 0.0));
+        builder.line_to(point(10.0, 10.0));
+        builder.line_to(point(0.0, 10.0));
+        builder.end(true);
+        let path = builder.build();
+
+        let mut mesh = Mesh::new([10.0, 10.0]);
+        assert!(mesh.is_empty());
+        mesh.fill_path(&path, FillRule::NonZero, [1.0; 4]).unwrap();
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 6);
+
+        // appended geometry keeps its own vertices.
+        mesh.stroke_path(&path, &LineStyle::new(2.0, [1.0; 4]))
+            .unwrap();
+        assert!(mesh.indices[6..].iter().all(|index| *index >= 4));
+        assert!(mesh
+            .vertices
+            .iter()
+            .all(|vertex| vertex.position[0] >= -1.0 && vertex.position[0] <= 11.0));
+    }
+}
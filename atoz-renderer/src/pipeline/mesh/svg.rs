@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use lyon::path::Path;
+use usvg::{NodeExt, NodeKind, TreeParsing};
+use wgpu::Device;
+
+use super::{point, FillRule, Mesh, MeshError, MeshResource};
+use crate::{
+    pipeline::line::{LineCap, LineJoin, LineStyle},
+    transform::Transform,
+};
+
+impl Mesh {
+    // tessellates the filled and stroked paths of an svg, in the pixels of its width and height.
+    // gradients and patterns are drawn with their first color, text and images are skipped.
+    pub fn from_svg(data: &[u8]) -> Result<Mesh, MeshError> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default())?;
+        let mut mesh = Mesh::new([tree.size.width(), tree.size.height()]);
+        let view_box =
+            usvg::utils::view_box_to_transform(tree.view_box.rect, tree.view_box.aspect, tree.size);
+
+        for node in tree.root.descendants() {
+            let path = match &*node.borrow() {
+                NodeKind::Path(path) if path.visibility == usvg::Visibility::Visible => {
+                    path.clone()
+                }
+                _ => continue,
+            };
+            let transform = convert_transform(view_box.pre_concat(node.abs_transform()));
+            let opacity = node
+                .ancestors()
+                .map(|ancestor| match &*ancestor.borrow() {
+                    NodeKind::Group(group) => group.opacity.get(),
+                    _ => 1.0,
+                })
+                .product::<f32>();
+            let data = convert_path(&path.data);
+
+            let fill = path.fill.as_ref().and_then(|fill| {
+                let color = get_paint_color(&fill.paint, fill.opacity.get() * opacity)?;
+                let rule = match fill.rule {
+                    usvg::FillRule::NonZero => FillRule::NonZero,
+                    usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+                };
+                return Some((rule, color));
+            });
+            let stroke = path.stroke.as_ref().and_then(|stroke| {
+                let color = get_paint_color(&stroke.paint, stroke.opacity.get() * opacity)?;
+                let cap = match stroke.linecap {
+                    usvg::LineCap::Butt => LineCap::Butt,
+                    usvg::LineCap::Round => LineCap::Round,
+                    usvg::LineCap::Square => LineCap::Square,
+                };
+                let join = match stroke.linejoin {
+                    usvg::LineJoin::Round => LineJoin::Round,
+                    _ => LineJoin::Miter,
+                };
+                return Some(
+                    LineStyle::new(stroke.width.get(), color)
+                        .set_cap(cap)
+                        .set_join(join),
+                );
+            });
+
+            let stroke_first = path.paint_order == usvg::PaintOrder::StrokeAndFill;
+            if stroke_first {
+                if let Some(style) = &stroke {
+                    mesh.stroke_transformed(&data, style, &transform)?;
+                }
+            }
+            if let Some((rule, color)) = fill {
+                mesh.fill_transformed(&data, rule, color, &transform)?;
+            }
+            if !stroke_first {
+                if let Some(style) = &stroke {
+                    mesh.stroke_transformed(&data, style, &transform)?;
+                }
+            }
+        }
+        return Ok(mesh);
+    }
+}
+
+fn convert_transform(transform: usvg::Transform) -> Transform {
+    return Transform {
+        matrix: [
+            [transform.sx, transform.kx, transform.tx],
+            [transform.ky, transform.sy, transform.ty],
+        ],
+    };
+}
+
+fn convert_path(data: &usvg::tiny_skia_path::Path) -> Path {
+    let mut builder = Path::builder();
+    let mut open = false;
+    for segment in data.segments() {
+        match segment {
+            usvg::tiny_skia_path::PathSegment::MoveTo(p) => {
+                if open {
+                    builder.end(false);
+                }
+                builder.begin(point(p.x, p.y));
+                open = true;
+            }
+            usvg::tiny_skia_path::PathSegment::LineTo(p) => {
+                builder.line_to(point(p.x, p.y));
+            }
+            usvg::tiny_skia_path::PathSegment::QuadTo(p1, p) => {
+                builder.quadratic_bezier_to(point(p1.x, p1.y), point(p.x, p.y));
+            }
+            usvg::tiny_skia_path::PathSegment::CubicTo(p1, p2, p) => {
+                builder.cubic_bezier_to(point(p1.x, p1.y), point(p2.x, p2.y), point(p.x, p.y));
+            }
+            usvg::tiny_skia_path::PathSegment::Close => {
+                if open {
+                    builder.end(true);
+                }
+                open = false;
+            }
+        }
+    }
+    if open {
+        builder.end(false);
+    }
+    return builder.build();
+}
+
+fn get_paint_color(paint: &usvg::Paint, opacity: f32) -> Option<[f32; 4]> {
+    let (color, alpha) = match paint {
+        usvg::Paint::Color(color) => (*color, 1.0),
+        usvg::Paint::LinearGradient(gradient) => {
+            let stop = gradient.stops.first()?;
+            (stop.color, stop.opacity.get())
+        }
+        usvg::Paint::RadialGradient(gradient) => {
+            let stop = gradient.stops.first()?;
+            (stop.color, stop.opacity.get())
+        }
+        usvg::Paint::Pattern(_) => return None,
+    };
+    return Some([
+        color.red as f32 / 255.0,
+        color.green as f32 / 255.0,
+        color.blue as f32 / 255.0,
+        alpha * opacity,
+    ]);
+}
+
+// svg icons tessellated once and kept on the gpu, by name. like every mesh they are only
+// anti-aliased by msaa, see RendererConfig::sample_count.
+#[derive(Debug, Default)]
+pub struct SvgCache {
+    meshes: HashMap<String, MeshResource>,
+}
+
+impl SvgCache {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    // the returned resource has no instances.
+    pub fn load(
+        &mut self,
+        device: &Device,
+        name: &str,
+        data: &[u8],
+    ) -> Result<MeshResource, MeshError> {
+        if let Some(mesh) = self.meshes.get(name) {
+            return Ok(mesh.clone());
+        }
+        let mesh = MeshResource::new(&Mesh::from_svg(data)?, device);
+        self.meshes.insert(name.to_string(), mesh.clone());
+        return Ok(mesh);
+    }
+
+    pub fn get(&self, name: &str) -> Option<MeshResource> {
+        return self.meshes.get(name).cloned();
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.meshes.remove(name);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.meshes.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.meshes.is_empty();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pipeline::mesh::Mesh;
+
+    #[test]
+    fn svg_mesh() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 12 12">
+            <rect x="1" y="1" width="10" height="10" fill="#ff0000"/>
+            <path d="M 2 6 L 10 6" stroke="#00ff00" stroke-width="1" fill="none"/>
+            <circle cx="6" cy="6" r="2" fill="none"/>
+        </svg>"##;
+        let mesh = Mesh::from_svg(svg.as_bytes()).unwrap();
+        assert_eq!(mesh.size, [24.0, 24.0]);
+        assert!(!mesh.is_empty());
+        // the view box is scaled to the svg size.
+        let min_x = mesh
+            .vertices
+            .iter()
+            .map(|vertex| vertex.position[0])
+            .fold(f32::MAX, f32::min);
+        let max_x = mesh
+            .vertices
+            .iter()
+            .map(|vertex| vertex.position[0])
+            .fold(f32::MIN, f32::max);
+        assert_eq!((min_x, max_x), (2.0, 22.0));
+        assert!(mesh
+            .vertices
+            .iter()
+            .any(|vertex| vertex.color == [0.0, 1.0, 0.0, 1.0]));
+
+        assert!(Mesh::from_svg(b"<svg").is_err());
+    }
+}
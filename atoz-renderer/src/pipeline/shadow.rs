@@ -0,0 +1,214 @@
+use bytemuck::{Pod, Zeroable};
+use std::{mem, ops::Range};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
+
+use crate::layouts::BindGroupLayouts;
+
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct ShadowVertex {
+    pub position: [f32; 2],
+}
+
+impl ShadowVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+}
+
+// box shadow of a rect with the same start, size and radius as a RectInstance.
+// blur is the css blur radius, the shadow fades over twice its standard deviation.
+// the shadow is also drawn below the box, so it is meant to be covered by an opaque rect.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct ShadowInstance {
+    pub start: [f32; 2],
+    pub size: [f32; 2],
+    pub radius: [f32; 4],
+    pub offset: [f32; 2],
+    pub blur: f32,
+    pub spread: f32,
+    pub color: [f32; 4],
+}
+
+impl ShadowInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x4,
+        4 => Float32x2,
+        5 => Float32,
+        6 => Float32,
+        7 => Float32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+
+    pub fn get_vertex_buffer(device: &Device) -> wgpu::Buffer {
+        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shadow_instance.vertex"),
+            contents: bytemuck::cast_slice(&SHADOW_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+    }
+
+    pub fn get_index_buffer(device: &Device) -> wgpu::Buffer {
+        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shadow_instance.index"),
+            contents: bytemuck::cast_slice(&SHADOW_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+    }
+
+    pub fn new(start: [f32; 2], size: [f32; 2], radius: [f32; 4], color: [f32; 4]) -> Self {
+        return Self {
+            start,
+            size,
+            radius,
+            offset: [0.0, 0.0],
+            blur: 0.0,
+            spread: 0.0,
+            color,
+        };
+    }
+
+    pub fn set_offset(mut self, x: f32, y: f32) -> Self {
+        self.offset = [x, y];
+        return self;
+    }
+
+    pub fn set_blur(mut self, blur: f32) -> Self {
+        self.blur = blur.max(0.0);
+        return self;
+    }
+
+    // grows the shadow box on every side, negative values shrink it. the radii grow like in css.
+    pub fn set_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        return self;
+    }
+}
+
+pub const SHADOW_VERTICES: [ShadowVertex; 4] = [
+    ShadowVertex {
+        position: [0.0, 0.0],
+    },
+    ShadowVertex {
+        position: [0.0, 1.0],
+    },
+    ShadowVertex {
+        position: [1.0, 1.0],
+    },
+    ShadowVertex {
+        position: [1.0, 0.0],
+    },
+];
+
+pub const SHADOW_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+#[derive(Debug)]
+pub struct ShadowPipeline {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+}
+
+impl ShadowPipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
+        let shadow_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shadow.shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shader/common.wgsl"),
+                    include_str!("../shader/shadow.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let vertex_buffer = ShadowInstance::get_vertex_buffer(device);
+        let index_buffer = ShadowInstance::get_index_buffer(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("shadow.pipeline.layout"),
+            bind_group_layouts: &[&layouts.viewport],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("shadow.pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shadow_shader,
+                entry_point: "vs_main",
+                buffers: &[ShadowVertex::desc(), ShadowInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shadow_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        return Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+        };
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        instance_buffer: &'a Buffer,
+        instances: Range<u32>,
+        viewport: &'a BindGroup,
+        state_offset: u32,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, viewport, &[state_offset]);
+
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        render_pass.draw_indexed(0..SHADOW_INDICES.len() as u32, 0, instances);
+    }
+}
@@ -0,0 +1,734 @@
+use std::{
+    collections::HashMap,
+    fmt, mem,
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bytemuck::{Pod, Zeroable};
+use etagere::{size2, AllocId, BucketedAtlasAllocator};
+use wgpu::{
+    util::DeviceExt, BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, Sampler,
+    Texture, TextureView,
+};
+
+use crate::layouts::BindGroupLayouts;
+
+static FONT_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone)]
+pub struct Font {
+    id: usize,
+    inner: Arc<fontdue::Font>,
+}
+
+impl fmt::Debug for Font {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_struct("Font").field("id", &self.id).finish();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionedGlyph {
+    pub index: u16,
+    pub x: f32,
+    pub baseline: f32,
+}
+
+impl Font {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        let inner = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())?;
+        return Ok(Self {
+            id: FONT_ID.fetch_add(1, Ordering::Relaxed),
+            inner: Arc::new(inner),
+        });
+    }
+
+    pub fn id(&self) -> usize {
+        return self.id;
+    }
+
+    pub fn ascent(&self, size: f32) -> f32 {
+        return match self.inner.horizontal_line_metrics(size) {
+            Some(metrics) => metrics.ascent,
+            None => size,
+        };
+    }
+
+    pub fn line_height(&self, size: f32) -> f32 {
+        return match self.inner.horizontal_line_metrics(size) {
+            Some(metrics) => metrics.new_line_size,
+            None => size,
+        };
+    }
+
+    pub fn advance(&self, character: char, size: f32) -> f32 {
+        return self.inner.metrics(character, size).advance_width;
+    }
+
+    // glyph positions relative to the top-left corner of the first line.
+    pub fn layout(&self, text: &str, size: f32) -> Vec<PositionedGlyph> {
+        let ascent = self.ascent(size);
+        let line_height = self.line_height(size);
+
+        let mut glyphs = vec![];
+        let mut x = 0.0;
+        let mut baseline = ascent;
+        let mut prev: Option<u16> = None;
+
+        for character in text.chars() {
+            if character == '\n' {
+                x = 0.0;
+                baseline += line_height;
+                prev = None;
+                continue;
+            }
+            let index = self.inner.lookup_glyph_index(character);
+            if let Some(prev) = prev {
+                x += self
+                    .inner
+                    .horizontal_kern_indexed(prev, index, size)
+                    .unwrap_or(0.0);
+            }
+            glyphs.push(PositionedGlyph { index, x, baseline });
+            x += self.inner.metrics_indexed(index, size).advance_width;
+            prev = Some(index);
+        }
+        return glyphs;
+    }
+
+    pub fn measure(&self, text: &str, size: f32) -> [f32; 2] {
+        let line_height = self.line_height(size);
+        let mut width: f32 = 0.0;
+        let mut lines = 0;
+
+        for line in text.split('\n') {
+            let mut x = 0.0;
+            let mut prev: Option<u16> = None;
+            for character in line.chars() {
+                let index = self.inner.lookup_glyph_index(character);
+                if let Some(prev) = prev {
+                    x += self
+                        .inner
+                        .horizontal_kern_indexed(prev, index, size)
+                        .unwrap_or(0.0);
+                }
+                x += self.inner.metrics_indexed(index, size).advance_width;
+                prev = Some(index);
+            }
+            width = width.max(x);
+            lines += 1;
+        }
+        return [width, line_height * lines as f32];
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Text {
+    pub font: Font,
+    pub content: String,
+    pub position: [f32; 2],
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl Text {
+    pub fn new(font: Font, content: &str, position: [f32; 2], size: f32, color: [f32; 4]) -> Self {
+        return Self {
+            font,
+            content: content.to_string(),
+            position,
+            size,
+            color,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct GlyphVertex {
+    pub position: [f32; 2],
+}
+
+impl GlyphVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+}
+
+// uv_start and uv_size are in atlas pixels, so they stay valid when the atlas grows.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct GlyphInstance {
+    pub start: [f32; 2],
+    pub size: [f32; 2],
+    pub uv_start: [f32; 2],
+    pub uv_size: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl GlyphInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x2,
+        5 => Float32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        return wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        };
+    }
+
+    pub fn get_vertex_buffer(device: &Device) -> wgpu::Buffer {
+        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("glyph_instance.vertex"),
+            contents: bytemuck::cast_slice(&GLYPH_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+    }
+
+    pub fn get_index_buffer(device: &Device) -> wgpu::Buffer {
+        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("glyph_instance.index"),
+            contents: bytemuck::cast_slice(&GLYPH_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+    }
+}
+
+const GLYPH_VERTICES: [GlyphVertex; 4] = [
+    GlyphVertex {
+        position: [0.0, 0.0],
+    },
+    GlyphVertex {
+        position: [0.0, 1.0],
+    },
+    GlyphVertex {
+        position: [1.0, 1.0],
+    },
+    GlyphVertex {
+        position: [1.0, 0.0],
+    },
+];
+
+const GLYPH_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+const GLYPH_PADDING: i32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: usize,
+    index: u16,
+    size: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GlyphEntry {
+    alloc: Option<AllocId>,
+    uv: [f32; 4],
+    offset: [f32; 2],
+    last_used: u64,
+}
+
+// cpu side of the glyph atlas : packing, eviction and a mirror of the texture pixels.
+pub struct GlyphCache {
+    allocator: BucketedAtlasAllocator,
+    glyphs: HashMap<GlyphKey, GlyphEntry>,
+    pixels: Vec<u8>,
+    size: u32,
+    max_size: u32,
+    frame: u64,
+    dirty: Option<[u32; 4]>,
+    scale_factor: f32,
+    // whether a glyph was dropped in this frame because the atlas is full.
+    full: bool,
+}
+
+impl GlyphCache {
+    pub fn new(size: u32, max_size: u32) -> Self {
+        return Self {
+            allocator: BucketedAtlasAllocator::new(size2(size as i32, size as i32)),
+            glyphs: HashMap::new(),
+            pixels: vec![0; (size * size) as usize],
+            size,
+            max_size: max_size.max(size),
+            frame: 0,
+            dirty: None,
+            scale_factor: 1.0,
+            full: false,
+        };
+    }
+
+    pub fn size(&self) -> u32 {
+        return self.size;
+    }
+
+    pub fn glyph_count(&self) -> usize {
+        return self.glyphs.len();
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+        self.full = false;
+    }
+
+    // glyphs are rasterized at the physical size, so text stays sharp on hidpi displays.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn get_glyph_instances(&mut self, text: &Text) -> Vec<GlyphInstance> {
+        let scale = self.scale_factor;
+        let size = text.size * scale;
+        let mut instances = vec![];
+        for glyph in text.font.layout(&text.content, size) {
+            let entry = match self.get_glyph(&text.font, glyph.index, size) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if entry.alloc.is_none() {
+                continue;
+            }
+            // snapped to physical pixels, then converted back to logical pixels.
+            instances.push(GlyphInstance {
+                start: [
+                    ((text.position[0] * scale + glyph.x).round() + entry.offset[0]) / scale,
+                    ((text.position[1] * scale + glyph.baseline).round() + entry.offset[1]) / scale,
+                ],
+                size: [entry.uv[2] / scale, entry.uv[3] / scale],
+                uv_start: [entry.uv[0], entry.uv[1]],
+                uv_size: [entry.uv[2], entry.uv[3]],
+                color: text.color,
+            });
+        }
+        return instances;
+    }
+
+    fn get_glyph(&mut self, font: &Font, index: u16, size: f32) -> Option<GlyphEntry> {
+        let key = GlyphKey {
+            font: font.id,
+            index,
+            size: size.to_bits(),
+        };
+        if let Some(entry) = self.glyphs.get_mut(&key) {
+            entry.last_used = self.frame;
+            return Some(*entry);
+        }
+
+        let (metrics, bitmap) = font.inner.rasterize_indexed(index, size);
+        let offset = [
+            metrics.xmin as f32,
+            -(metrics.ymin as f32 + metrics.height as f32),
+        ];
+        if metrics.width == 0 || metrics.height == 0 {
+            let entry = GlyphEntry {
+                alloc: None,
+                uv: [0.0; 4],
+                offset,
+                last_used: self.frame,
+            };
+            self.glyphs.insert(key, entry);
+            return Some(entry);
+        }
+
+        let width = metrics.width as i32 + GLYPH_PADDING * 2;
+        let height = metrics.height as i32 + GLYPH_PADDING * 2;
+        let Some(allocation) = self.allocate(width, height) else {
+            if !self.full {
+                log::warn!(
+                    "glyph atlas : {}x{} is full, glyphs are not drawn",
+                    self.max_size,
+                    self.max_size
+                );
+                self.full = true;
+            }
+            return None;
+        };
+        let (left, top) = (
+            allocation.rectangle.min.x as u32,
+            allocation.rectangle.min.y as u32,
+        );
+        let x = left + GLYPH_PADDING as u32;
+        let y = top + GLYPH_PADDING as u32;
+
+        // the padding of a reused slot still holds the evicted glyph, which would bleed in.
+        for row in top..top + height as u32 {
+            let start = (row * self.size + left) as usize;
+            self.pixels[start..start + width as usize].fill(0);
+        }
+        for row in 0..metrics.height {
+            let src = row * metrics.width;
+            let dst = (y as usize + row) * self.size as usize + x as usize;
+            self.pixels[dst..dst + metrics.width]
+                .copy_from_slice(&bitmap[src..src + metrics.width]);
+        }
+        self.mark_dirty([left, top, width as u32, height as u32]);
+
+        let entry = GlyphEntry {
+            alloc: Some(allocation.id),
+            uv: [
+                x as f32,
+                y as f32,
+                metrics.width as f32,
+                metrics.height as f32,
+            ],
+            offset,
+            last_used: self.frame,
+        };
+        self.glyphs.insert(key, entry);
+        return Some(entry);
+    }
+
+    // grows the atlas until max_size, then evicts glyphs that were not used in this frame.
+    fn allocate(&mut self, width: i32, height: i32) -> Option<etagere::Allocation> {
+        loop {
+            if let Some(allocation) = self.allocator.allocate(size2(width, height)) {
+                return Some(allocation);
+            }
+            if self.size < self.max_size {
+                self.grow((self.size * 2).min(self.max_size));
+            } else if !self.evict() {
+                return None;
+            }
+        }
+    }
+
+    fn grow(&mut self, size: u32) {
+        let mut pixels = vec![0; (size * size) as usize];
+        for row in 0..self.size as usize {
+            let src = row * self.size as usize;
+            let dst = row * size as usize;
+            pixels[dst..dst + self.size as usize]
+                .copy_from_slice(&self.pixels[src..src + self.size as usize]);
+        }
+        self.allocator.grow(size2(size as i32, size as i32));
+        self.pixels = pixels;
+        self.size = size;
+        self.dirty = Some([0, 0, size, size]);
+    }
+
+    fn evict(&mut self) -> bool {
+        let frame = self.frame;
+        let stale = self
+            .glyphs
+            .iter()
+            .filter(|(_, entry)| entry.last_used < frame && entry.alloc.is_some())
+            .map(|(key, _)| *key)
+            .collect::<Vec<GlyphKey>>();
+        if stale.is_empty() {
+            return false;
+        }
+        for key in stale {
+            if let Some(GlyphEntry {
+                alloc: Some(alloc), ..
+            }) = self.glyphs.remove(&key)
+            {
+                self.allocator.deallocate(alloc);
+            }
+        }
+        return true;
+    }
+
+    fn mark_dirty(&mut self, rect: [u32; 4]) {
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => {
+                let left = dirty[0].min(rect[0]);
+                let top = dirty[1].min(rect[1]);
+                let right = (dirty[0] + dirty[2]).max(rect[0] + rect[2]);
+                let bottom = (dirty[1] + dirty[3]).max(rect[1] + rect[3]);
+                [left, top, right - left, bottom - top]
+            }
+            None => rect,
+        });
+    }
+}
+
+pub struct GlyphAtlas {
+    cache: GlyphCache,
+    layouts: BindGroupLayouts,
+    texture: Texture,
+    view: TextureView,
+    sampler: Sampler,
+    bind_group: BindGroup,
+}
+
+impl fmt::Debug for GlyphAtlas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f
+            .debug_struct("GlyphAtlas")
+            .field("size", &self.cache.size)
+            .field("glyphs", &self.cache.glyphs.len())
+            .finish();
+    }
+}
+
+impl GlyphAtlas {
+    const INITIAL_SIZE: u32 = 512;
+    const MAX_SIZE: u32 = 4096;
+
+    pub fn new(device: &Device, layouts: &BindGroupLayouts) -> Self {
+        let max_size = device.limits().max_texture_dimension_2d.min(Self::MAX_SIZE);
+        let cache = GlyphCache::new(Self::INITIAL_SIZE.min(max_size), max_size);
+        let texture = Self::create_texture(device, cache.size);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let bind_group = layouts.create_texture_group(device, "glyph_atlas.group", &view, &sampler);
+
+        return Self {
+            cache,
+            layouts: layouts.clone(),
+            texture,
+            view,
+            sampler,
+            bind_group,
+        };
+    }
+
+    fn create_texture(device: &Device, size: u32) -> Texture {
+        return device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("glyph_atlas.texture"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.cache.begin_frame();
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.cache.set_scale_factor(scale_factor);
+    }
+
+    pub fn get_glyph_instances(&mut self, text: &Text) -> Vec<GlyphInstance> {
+        return self.cache.get_glyph_instances(text);
+    }
+
+    pub fn get_bind_group(&self) -> &BindGroup {
+        return &self.bind_group;
+    }
+
+    // writes newly rasterized glyphs to the texture, recreating it when the atlas has grown.
+    pub fn upload(&mut self, device: &Device, queue: &Queue) {
+        if self.texture.width() != self.cache.size {
+            self.texture = Self::create_texture(device, self.cache.size);
+            self.view = self
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            self.bind_group = self.layouts.create_texture_group(
+                device,
+                "glyph_atlas.group",
+                &self.view,
+                &self.sampler,
+            );
+            self.cache.dirty = Some([0, 0, self.cache.size, self.cache.size]);
+        }
+
+        let [x, y, width, height] = match self.cache.dirty.take() {
+            Some(dirty) => dirty,
+            None => return,
+        };
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.cache.pixels[(y * self.cache.size + x) as usize..],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(self.cache.size),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct TextPipeline {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+}
+
+impl TextPipeline {
+    pub fn new(
+        device: &Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
+        let text_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("text.shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shader/common.wgsl"),
+                    include_str!("../shader/text.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let vertex_buffer = GlyphInstance::get_vertex_buffer(device);
+        let index_buffer = GlyphInstance::get_index_buffer(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("text.pipeline.layout"),
+            bind_group_layouts: &[&layouts.viewport, &layouts.texture],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("text.pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &text_shader,
+                entry_point: "vs_main",
+                buffers: &[GlyphVertex::desc(), GlyphInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &text_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        return Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+        };
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        instance_buffer: &'a Buffer,
+        instances: Range<u32>,
+        atlas: &'a BindGroup,
+        viewport: &'a BindGroup,
+        state_offset: u32,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, viewport, &[state_offset]);
+        render_pass.set_bind_group(1, atlas, &[]);
+
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        render_pass.draw_indexed(0..GLYPH_INDICES.len() as u32, 0, instances);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Font, GlyphCache, Text};
+
+    fn font() -> Font {
+        return Font::from_bytes(include_bytes!("../test/assets/DejaVuSansMono.ttf")).unwrap();
+    }
+
+    #[test]
+    fn font_measure() {
+        let font = font();
+        let [width, height] = font.measure("abc\nde", 16.0);
+        assert_eq!(width, font.advance('a', 16.0) * 3.0);
+        assert_eq!(height, font.line_height(16.0) * 2.0);
+        assert_eq!(font.layout("a b", 16.0).len(), 3);
+    }
+
+    #[test]
+    fn glyph_cache_grow_and_evict() {
+        let font = font();
+        let mut cache = GlyphCache::new(64, 128);
+
+        cache.begin_frame();
+        let text = Text::new(font.clone(), "0123456789", [0.0, 0.0], 40.0, [1.0; 4]);
+        assert_eq!(cache.get_glyph_instances(&text).len(), 10);
+        assert_eq!(cache.size(), 128);
+
+        cache.begin_frame();
+        let text = Text::new(font.clone(), "ABCDEFGHIJ", [0.0, 0.0], 40.0, [1.0; 4]);
+        assert_eq!(cache.get_glyph_instances(&text).len(), 10);
+        assert_eq!(cache.size(), 128);
+        assert!(cache.glyph_count() < 20);
+
+        // glyphs in reused slots have a clean padding around them.
+        for entry in cache.glyphs.values().filter(|entry| entry.alloc.is_some()) {
+            let [x, y, width, height] = entry.uv.map(|value| value as usize);
+            let size = cache.size() as usize;
+            for column in x - 1..=x + width {
+                assert_eq!(cache.pixels[(y - 1) * size + column], 0);
+                assert_eq!(cache.pixels[(y + height) * size + column], 0);
+            }
+            for row in y - 1..=y + height {
+                assert_eq!(cache.pixels[row * size + x - 1], 0);
+                assert_eq!(cache.pixels[row * size + x + width], 0);
+            }
+        }
+
+        // glyphs that do not fit in a full atlas are dropped.
+        assert!(!cache.full);
+        let text = Text::new(font, "KLMNOPQRSTUVWXYZ", [0.0, 0.0], 40.0, [1.0; 4]);
+        assert!(cache.get_glyph_instances(&text).len() < 16);
+        assert!(cache.full);
+    }
+}
@@ -1,8 +1,8 @@
 use bytemuck::{Pod, Zeroable};
-use std::mem;
+use std::{mem, ops::Range};
 use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
 
-use crate::viewport::Viewport;
+use crate::layouts::BindGroupLayouts;
 
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 #[repr(C)]
@@ -134,10 +134,21 @@ pub struct TrianglePipeline {
 }
 
 impl TrianglePipeline {
-    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layouts: &BindGroupLayouts,
+        sample_count: u32,
+    ) -> Self {
         let triangle_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("triangle.shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shader/triangle.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shader/common.wgsl"),
+                    include_str!("../shader/triangle.wgsl")
+                )
+                .into(),
+            ),
         });
 
         let vertex_buffer = TriangleInstance::get_vertex_buffer(device);
@@ -145,7 +156,7 @@ impl TrianglePipeline {
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("triangle.pipeline.layout"),
-            bind_group_layouts: &[&Viewport::layout(device)],
+            bind_group_layouts: &[&layouts.viewport],
             push_constant_ranges: &[],
         });
 
@@ -170,14 +181,14 @@ impl TrianglePipeline {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
             },
             depth_stencil: None,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -195,16 +206,17 @@ impl TrianglePipeline {
         &'a self,
         render_pass: &mut RenderPass<'a>,
         instance_buffer: &'a Buffer,
-        instance_count: u32,
+        instances: Range<u32>,
         viewport: &'a BindGroup,
+        state_offset: u32,
     ) {
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, viewport, &[]);
+        render_pass.set_bind_group(0, viewport, &[state_offset]);
 
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-        render_pass.draw_indexed(0..TRIANGLE_INDICES.len() as u32, 0, 0..instance_count);
+        render_pass.draw_indexed(0..TRIANGLE_INDICES.len() as u32, 0, instances);
     }
 }
@@ -0,0 +1,387 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use wgpu::{Buffer, CommandEncoder, Device, QuerySet, Queue};
+
+pub const PHASE_COUNT: usize = 5;
+pub const PIPELINE_COUNT: usize = 9;
+pub const HISTORY_SIZE: usize = 120;
+
+// cpu phases of a frame. layout and layer build are recorded by the gui.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Layout,
+    LayerBuild,
+    Upload,
+    Encode,
+    Submit,
+}
+
+impl Phase {
+    pub const ALL: [Phase; PHASE_COUNT] = [
+        Phase::Layout,
+        Phase::LayerBuild,
+        Phase::Upload,
+        Phase::Encode,
+        Phase::Submit,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineKind {
+    Rect,
+    Triangle,
+    Circle,
+    Line,
+    Shadow,
+    Blur,
+    Image,
+    Mesh,
+    Text,
+}
+
+impl PipelineKind {
+    pub const ALL: [PipelineKind; PIPELINE_COUNT] = [
+        PipelineKind::Rect,
+        PipelineKind::Triangle,
+        PipelineKind::Circle,
+        PipelineKind::Line,
+        PipelineKind::Shadow,
+        PipelineKind::Blur,
+        PipelineKind::Image,
+        PipelineKind::Mesh,
+        PipelineKind::Text,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    pub draw_calls: u32,
+    pub instances: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub frame: u64,
+    pub cpu: [Duration; PHASE_COUNT],
+    // None until the timestamps were read back, or when the adapter has no timestamp queries.
+    pub gpu: Option<Duration>,
+    pub draws: [DrawStats; PIPELINE_COUNT],
+}
+
+impl FrameStats {
+    pub fn get_cpu(&self, phase: Phase) -> Duration {
+        return self.cpu[phase as usize];
+    }
+
+    pub fn get_cpu_total(&self) -> Duration {
+        return self.cpu.iter().sum();
+    }
+
+    pub fn get_draws(&self, kind: PipelineKind) -> DrawStats {
+        return self.draws[kind as usize];
+    }
+
+    pub fn get_draw_calls(&self) -> u32 {
+        return self.draws.iter().map(|draws| draws.draw_calls).sum();
+    }
+
+    pub fn get_instances(&self) -> u32 {
+        return self.draws.iter().map(|draws| draws.instances).sum();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MapResult {
+    Pending,
+    Mapped,
+    Failed,
+}
+
+// result of a map_async, set by its callback and taken by the reader.
+#[derive(Debug, Clone, Default)]
+struct MapState {
+    result: Arc<AtomicU8>,
+}
+
+impl MapState {
+    fn set(&self, result: MapResult) {
+        self.result.store(result as u8, Ordering::Release);
+    }
+
+    // the result is taken once, then the state is pending again.
+    fn take(&self) -> MapResult {
+        return match self
+            .result
+            .swap(MapResult::Pending as u8, Ordering::Acquire)
+        {
+            value if value == MapResult::Mapped as u8 => MapResult::Mapped,
+            value if value == MapResult::Failed as u8 => MapResult::Failed,
+            _ => MapResult::Pending,
+        };
+    }
+}
+
+// timestamps written at the start and the end of a frame, read back without blocking.
+// a new frame is only timed once the last one was read, or failed to map.
+#[derive(Debug)]
+struct GpuTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    read_buffer: Buffer,
+    period: f32,
+    mapped: MapState,
+    pending: Option<u64>,
+    timing: Option<u64>,
+}
+
+impl GpuTimer {
+    const SIZE: wgpu::BufferAddress = 2 * std::mem::size_of::<u64>() as wgpu::BufferAddress;
+
+    fn new(device: &Device, queue: &Queue) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("profiler.query_set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let create_buffer = |label, usage| {
+            return device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: Self::SIZE,
+                usage,
+                mapped_at_creation: false,
+            });
+        };
+        return Self {
+            query_set,
+            resolve_buffer: create_buffer(
+                "profiler.resolve",
+                wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            ),
+            read_buffer: create_buffer(
+                "profiler.read",
+                wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            ),
+            period: queue.get_timestamp_period(),
+            mapped: MapState::default(),
+            pending: None,
+            timing: None,
+        };
+    }
+
+    fn begin(&mut self, encoder: &mut CommandEncoder, frame: u64) {
+        if self.pending.is_some() {
+            return;
+        }
+        encoder.write_timestamp(&self.query_set, 0);
+        self.timing = Some(frame);
+    }
+
+    fn end(&mut self, encoder: &mut CommandEncoder) {
+        if self.timing.is_none() {
+            return;
+        }
+        encoder.write_timestamp(&self.query_set, 1);
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.read_buffer, 0, Self::SIZE);
+    }
+
+    // after the frame was submitted.
+    fn map(&mut self) {
+        let Some(frame) = self.timing.take() else {
+            return;
+        };
+        let mapped = self.mapped.clone();
+        self.read_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                mapped.set(match result {
+                    Ok(()) => MapResult::Mapped,
+                    Err(_) => MapResult::Failed,
+                });
+            });
+        self.pending = Some(frame);
+    }
+
+    // gpu time of a finished frame, and the frame it belongs to.
+    fn read(&mut self, device: &Device) -> Option<(u64, Duration)> {
+        let frame = self.pending?;
+        device.poll(wgpu::Maintain::Poll);
+        match self.mapped.take() {
+            MapResult::Pending => return None,
+            // the frame is dropped, so that the next one is timed.
+            MapResult::Failed => {
+                self.pending = None;
+                return None;
+            }
+            MapResult::Mapped => {}
+        }
+        let ticks = {
+            let data = self.read_buffer.slice(..).get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&data);
+            timestamps[1].saturating_sub(timestamps[0])
+        };
+        self.read_buffer.unmap();
+        self.pending = None;
+        return Some((
+            frame,
+            Duration::from_nanos((ticks as f64 * self.period as f64) as u64),
+        ));
+    }
+}
+
+// frame statistics of the renderer, collected while enabled.
+#[derive(Debug)]
+pub struct Profiler {
+    enabled: bool,
+    current: FrameStats,
+    history: VecDeque<FrameStats>,
+    timer: Option<GpuTimer>,
+}
+
+impl Profiler {
+    // gpu timing needs the TIMESTAMP_QUERY feature on the device, it is skipped without it.
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let timer = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| GpuTimer::new(device, queue));
+        return Self {
+            enabled: false,
+            current: FrameStats::default(),
+            history: VecDeque::with_capacity(HISTORY_SIZE),
+            timer,
+        };
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    pub fn has_gpu_timing(&self) -> bool {
+        return self.timer.is_some();
+    }
+
+    // adds the time since start to a phase of the current frame.
+    pub fn record(&mut self, phase: Phase, start: Instant) {
+        if self.enabled {
+            self.current.cpu[phase as usize] += start.elapsed();
+        }
+    }
+
+    pub fn count_draw(&mut self, kind: PipelineKind, instances: u32) {
+        if self.enabled {
+            let draws = &mut self.current.draws[kind as usize];
+            draws.draw_calls += 1;
+            draws.instances += instances;
+        }
+    }
+
+    pub fn begin_gpu(&mut self, encoder: &mut CommandEncoder) {
+        if let (true, Some(timer)) = (self.enabled, &mut self.timer) {
+            timer.begin(encoder, self.current.frame);
+        }
+    }
+
+    pub fn end_gpu(&mut self, encoder: &mut CommandEncoder) {
+        if let Some(timer) = &mut self.timer {
+            timer.end(encoder);
+        }
+    }
+
+    // closes the current frame after it was submitted.
+    pub fn end_frame(&mut self, device: &Device) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(timer) = &mut self.timer {
+            timer.map();
+            if let Some((frame, duration)) = timer.read(device) {
+                let stats = self.history.iter_mut().find(|stats| stats.frame == frame);
+                if let Some(stats) = stats {
+                    stats.gpu = Some(duration);
+                } else if frame == self.current.frame {
+                    self.current.gpu = Some(duration);
+                }
+            }
+        }
+
+        if self.history.len() == HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        let frame = self.current.frame + 1;
+        self.history.push_back(std::mem::take(&mut self.current));
+        self.current.frame = frame;
+    }
+
+    pub fn get_last(&self) -> Option<&FrameStats> {
+        return self.history.back();
+    }
+
+    // oldest frame first.
+    pub fn get_history(&self) -> &VecDeque<FrameStats> {
+        return &self.history;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{MapResult, MapState, Phase, PipelineKind, Profiler, HISTORY_SIZE};
+    use crate::tests::headless_renderer;
+
+    #[test]
+    fn profiler_history() {
+        let renderer = headless_renderer();
+        let (device, queue) = (renderer.get_device(), renderer.get_queue());
+
+        let mut profiler = Profiler::new(device, queue);
+        profiler.count_draw(PipelineKind::Rect, 4);
+        profiler.end_frame(device);
+        assert!(profiler.get_last().is_none());
+
+        profiler.set_enabled(true);
+        profiler.record(Phase::Upload, Instant::now() - Duration::from_millis(2));
+        profiler.count_draw(PipelineKind::Rect, 4);
+        profiler.count_draw(PipelineKind::Rect, 2);
+        profiler.count_draw(PipelineKind::Text, 10);
+        profiler.end_frame(device);
+
+        let stats = profiler.get_last().unwrap();
+        assert!(stats.get_cpu(Phase::Upload) >= Duration::from_millis(2));
+        assert_eq!(stats.get_cpu(Phase::Layout), Duration::ZERO);
+        assert_eq!(stats.get_draws(PipelineKind::Rect).draw_calls, 2);
+        assert_eq!(stats.get_draws(PipelineKind::Rect).instances, 6);
+        assert_eq!((stats.get_draw_calls(), stats.get_instances()), (3, 16));
+
+        for _ in 0..HISTORY_SIZE + 10 {
+            profiler.end_frame(device);
+        }
+        assert_eq!(profiler.get_history().len(), HISTORY_SIZE);
+        assert_eq!(profiler.get_last().unwrap().frame, HISTORY_SIZE as u64 + 10);
+    }
+
+    #[test]
+    fn map_state() {
+        let state = MapState::default();
+        assert_eq!(state.take(), MapResult::Pending);
+        // a failed mapping is reported once, not mistaken for a mapping still on its way.
+        let callback = state.clone();
+        callback.set(MapResult::Failed);
+        assert_eq!(state.take(), MapResult::Failed);
+        assert_eq!(state.take(), MapResult::Pending);
+        callback.set(MapResult::Mapped);
+        assert_eq!(state.take(), MapResult::Mapped);
+    }
+}
@@ -1,3 +1,708 @@
-pub mod layer;
-pub mod pipeline;
-pub mod viewport;
+use std::{fmt, time::Instant};
+
+use image::{DynamicImage, RgbaImage};
+use wgpu::{Device, InstanceDescriptor, InstanceFlags, Queue};
+
+use crate::{
+    buffer::BufferManager,
+    clip::Clip,
+    damage::Damage,
+    layer::{DrawCommand, Layer},
+    layouts::BindGroupLayouts,
+    pipeline::{
+        blur::{BlurInstance, BlurPipeline},
+        circle::CirclePipeline,
+        image::{atlas::ImageAtlas, ImagePipeline, ImageResource, ScaledImage},
+        line::LinePipeline,
+        mesh::{Mesh, MeshPipeline, MeshResource},
+        rect::{RectInstance, RectPipeline},
+        shadow::ShadowPipeline,
+        text::{GlyphAtlas, TextPipeline},
+        triangle::TrianglePipeline,
+    },
+    profiler::{Phase, PipelineKind, Profiler},
+    target::{Frame, OffscreenTarget, RenderTarget, TextureTarget},
+    viewport::{Viewport, DEFAULT_AA_WIDTH},
+};
+
+#[derive(Debug)]
+pub enum RenderError {
+    AdapterNotFound,
+    Window(winit::error::OsError),
+    CreateSurface(wgpu::CreateSurfaceError),
+    // the adapter cannot present to the surface in any format.
+    UnsupportedSurface,
+    RequestDevice(wgpu::RequestDeviceError),
+    // the surface could not be acquired, even after reconfiguring it.
+    Surface(wgpu::SurfaceError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            RenderError::AdapterNotFound => {
+                write!(f, "render error : no compatible graphics adapter found")
+            }
+            RenderError::Window(err) => {
+                write!(f, "render error : window creation failed : {}", err)
+            }
+            RenderError::CreateSurface(err) => {
+                write!(f, "render error : surface creation failed : {}", err)
+            }
+            RenderError::UnsupportedSurface => {
+                write!(f, "render error : the surface supports no texture format")
+            }
+            RenderError::RequestDevice(err) => {
+                write!(f, "render error : device request failed : {}", err)
+            }
+            RenderError::Surface(err) => write!(f, "render error : surface error : {}", err),
+        };
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<winit::error::OsError> for RenderError {
+    fn from(err: winit::error::OsError) -> Self {
+        return RenderError::Window(err);
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for RenderError {
+    fn from(err: wgpu::CreateSurfaceError) -> Self {
+        return RenderError::CreateSurface(err);
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for RenderError {
+    fn from(err: wgpu::RequestDeviceError) -> Self {
+        return RenderError::RequestDevice(err);
+    }
+}
+
+impl From<wgpu::SurfaceError> for RenderError {
+    fn from(err: wgpu::SurfaceError) -> Self {
+        return RenderError::Surface(err);
+    }
+}
+
+#[derive(Debug)]
+pub struct Renderer {
+    device: Device,
+    queue: Queue,
+    format: wgpu::TextureFormat,
+    layouts: BindGroupLayouts,
+    clear_color: wgpu::Color,
+    scale_factor: f32,
+    aa_width: f32,
+    sample_count: u32,
+    // the msaa sample counts the adapter supports for the format.
+    format_flags: wgpu::TextureFormatFeatureFlags,
+    partial_redraw: bool,
+    damage: Damage,
+    canvas: Option<OffscreenTarget>,
+    rect_pipeline: RectPipeline,
+    triangle_pipeline: TrianglePipeline,
+    circle_pipeline: CirclePipeline,
+    line_pipeline: LinePipeline,
+    shadow_pipeline: ShadowPipeline,
+    blur_pipeline: BlurPipeline,
+    image_pipeline: ImagePipeline,
+    mesh_pipeline: MeshPipeline,
+    text_pipeline: TextPipeline,
+    glyph_atlas: GlyphAtlas,
+    buffers: BufferManager,
+    profiler: Profiler,
+}
+
+impl Renderer {
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        device: Device,
+        queue: Queue,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let format_flags = adapter.get_texture_format_features(format).flags;
+        let layouts = BindGroupLayouts::new(&device);
+        let rect_pipeline = RectPipeline::new(&device, format, &layouts, 1);
+        let triangle_pipeline = TrianglePipeline::new(&device, format, &layouts, 1);
+        let circle_pipeline = CirclePipeline::new(&device, format, &layouts, 1);
+        let line_pipeline = LinePipeline::new(&device, format, &layouts, 1);
+        let shadow_pipeline = ShadowPipeline::new(&device, format, &layouts, 1);
+        let blur_pipeline = BlurPipeline::new(&device, format, &layouts, 1);
+        let image_pipeline = ImagePipeline::new(&device, format, &layouts, 1);
+        let mesh_pipeline = MeshPipeline::new(&device, format, &layouts, 1);
+        let text_pipeline = TextPipeline::new(&device, format, &layouts, 1);
+        let glyph_atlas = GlyphAtlas::new(&device, &layouts);
+        let buffers = BufferManager::new(&device, &layouts);
+        let profiler = Profiler::new(&device, &queue);
+
+        return Self {
+            device,
+            queue,
+            format,
+            layouts,
+            clear_color: wgpu::Color::BLACK,
+            scale_factor: 1.0,
+            aa_width: DEFAULT_AA_WIDTH,
+            sample_count: 1,
+            format_flags,
+            partial_redraw: false,
+            damage: Damage::new(),
+            canvas: None,
+            rect_pipeline,
+            triangle_pipeline,
+            circle_pipeline,
+            line_pipeline,
+            shadow_pipeline,
+            blur_pipeline,
+            image_pipeline,
+            mesh_pipeline,
+            text_pipeline,
+            glyph_atlas,
+            buffers,
+            profiler,
+        };
+    }
+
+    // renderer without a window. prefers a software adapter so that it also runs on CI machines.
+    pub async fn headless() -> Result<Self, RenderError> {
+        let instance = wgpu::Instance::new(InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+            flags: InstanceFlags::default(),
+            gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
+        });
+
+        let mut adapter = None;
+        for force_fallback_adapter in [true, false] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+
+        // timestamp queries are requested when available, for the gpu time of the profiler.
+        let adapter = adapter.ok_or(RenderError::AdapterNotFound)?;
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                    limits: wgpu::Limits::downlevel_defaults(),
+                    label: None,
+                },
+                None,
+            )
+            .await?;
+
+        return Ok(Self::new(
+            &adapter,
+            device,
+            queue,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        ));
+    }
+
+    pub fn get_device(&self) -> &Device {
+        return &self.device;
+    }
+
+    pub fn get_queue(&self) -> &Queue {
+        return &self.queue;
+    }
+
+    pub fn get_profiler(&self) -> &Profiler {
+        return &self.profiler;
+    }
+
+    pub fn get_profiler_mut(&mut self) -> &mut Profiler {
+        return &mut self.profiler;
+    }
+
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        return self.format;
+    }
+
+    pub fn get_layouts(&self) -> &BindGroupLayouts {
+        return &self.layouts;
+    }
+
+    pub fn create_image(&self, image: DynamicImage) -> ImageResource {
+        return ImageResource::new(image, &self.device, &self.queue, &self.layouts);
+    }
+
+    // images given with the scale factor they were rasterized at, see ScaledImage::get_resource.
+    pub fn create_scaled_image(&self, images: Vec<(f32, DynamicImage)>) -> ScaledImage {
+        let mut scaled = ScaledImage::new();
+        for (scale_factor, image) in images {
+            scaled.add(scale_factor, self.create_image(image));
+        }
+        return scaled;
+    }
+
+    pub fn create_mesh(&self, mesh: &Mesh) -> MeshResource {
+        return MeshResource::new(mesh, &self.device);
+    }
+
+    pub fn create_image_atlas(&self, page_size: u32) -> ImageAtlas {
+        return ImageAtlas::new(&self.layouts, page_size);
+    }
+
+    pub fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.clear_color = color;
+        self.damage.add_all();
+    }
+
+    // keeps the last frame in a texture of its own and only redraws the damaged region over it.
+    // targets need the COPY_DST usage, others are still fully redrawn. the clear color should be opaque.
+    pub fn set_partial_redraw(&mut self, partial_redraw: bool) {
+        self.partial_redraw = partial_redraw;
+        self.canvas = None;
+    }
+
+    pub fn get_partial_redraw(&self) -> bool {
+        return self.partial_redraw;
+    }
+
+    // marks a rect in logical pixels to be redrawn by the next partial redraw.
+    pub fn add_damage(&mut self, start: [f32; 2], size: [f32; 2]) {
+        self.damage.add(start, size);
+    }
+
+    pub fn damage_all(&mut self) {
+        self.damage.add_all();
+    }
+
+    pub fn get_damage(&self) -> &Damage {
+        return &self.damage;
+    }
+
+    // layers are given in logical pixels and rendered at scale_factor physical pixels per logical pixel.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+        self.glyph_atlas.set_scale_factor(scale_factor);
+        self.damage.add_all();
+    }
+
+    pub fn get_scale_factor(&self) -> f32 {
+        return self.scale_factor;
+    }
+
+    // width of the anti-aliased edges of the sdf primitives, in physical pixels.
+    pub fn set_aa_width(&mut self, aa_width: f32) {
+        self.aa_width = aa_width;
+        self.damage.add_all();
+    }
+
+    pub fn get_aa_width(&self) -> f32 {
+        return self.aa_width;
+    }
+
+    // msaa samples per pixel, 1 disables it. a count the adapter does not support for the format
+    // falls back to 1. targets must be created with the same count, see create_offscreen_target.
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        let sample_count = match self.format_flags.sample_count_supported(sample_count) {
+            true => sample_count,
+            false => 1,
+        };
+        if sample_count == self.sample_count {
+            return;
+        }
+        let (device, format, layouts) = (&self.device, self.format, &self.layouts);
+        self.sample_count = sample_count;
+        self.canvas = None;
+        self.rect_pipeline = RectPipeline::new(device, format, layouts, sample_count);
+        self.triangle_pipeline = TrianglePipeline::new(device, format, layouts, sample_count);
+        self.circle_pipeline = CirclePipeline::new(device, format, layouts, sample_count);
+        self.line_pipeline = LinePipeline::new(device, format, layouts, sample_count);
+        self.shadow_pipeline = ShadowPipeline::new(device, format, layouts, sample_count);
+        self.blur_pipeline = BlurPipeline::new(device, format, layouts, sample_count);
+        self.image_pipeline = ImagePipeline::new(device, format, layouts, sample_count);
+        self.mesh_pipeline = MeshPipeline::new(device, format, layouts, sample_count);
+        self.text_pipeline = TextPipeline::new(device, format, layouts, sample_count);
+    }
+
+    pub fn get_sample_count(&self) -> u32 {
+        return self.sample_count;
+    }
+
+    // width and height in physical pixels, like the other targets.
+    pub fn create_texture_target(&self, width: u32, height: u32) -> TextureTarget {
+        return TextureTarget::new(
+            &self.device,
+            &self.layouts,
+            width,
+            height,
+            self.format,
+            self.sample_count,
+        );
+    }
+
+    pub fn create_offscreen_target(&self, width: u32, height: u32) -> OffscreenTarget {
+        return OffscreenTarget::new(&self.device, width, height, self.format, self.sample_count);
+    }
+
+    pub fn read_image(&self, target: &OffscreenTarget) -> RgbaImage {
+        return target.read_image(&self.device, &self.queue);
+    }
+
+    // a lost or outdated surface is reconfigured by the target. on error nothing is drawn
+    // and the damage is kept for the next frame.
+    pub fn render(
+        &mut self,
+        target: &dyn RenderTarget,
+        layers: &[Layer],
+    ) -> Result<(), RenderError> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("renderer.render.encoder"),
+            });
+        self.profiler.begin_gpu(&mut encoder);
+
+        let frame = target.get_frame(&self.device)?;
+        let (target_width, target_height) = target.size();
+        assert_eq!(
+            frame.sample_count, self.sample_count,
+            "Renderer : the target was created with another sample count."
+        );
+        let bounds = Clip::new([0.0, 0.0], [target_width as f32, target_height as f32]);
+        let partial = self.partial_redraw
+            && frame
+                .get_texture()
+                .usage()
+                .contains(wgpu::TextureUsages::COPY_DST);
+
+        if !partial {
+            let layers = layers.iter().collect::<Vec<_>>();
+            self.draw(
+                &mut encoder,
+                &frame,
+                &layers,
+                bounds,
+                Some(self.clear_color),
+            );
+        } else {
+            let canvas = match self.canvas.take() {
+                Some(canvas) if canvas.size() == (target_width, target_height) => canvas,
+                _ => {
+                    self.damage.add_all();
+                    self.create_offscreen_target(target_width, target_height)
+                }
+            };
+
+            // only the layers that can reach the damaged region are drawn, over the last frame.
+            let region = self.damage.get_bounds(
+                target_width as f32 / self.scale_factor,
+                target_height as f32 / self.scale_factor,
+            );
+            if let Some(region) = region {
+                let clear_color = [
+                    self.clear_color.r as f32,
+                    self.clear_color.g as f32,
+                    self.clear_color.b as f32,
+                    self.clear_color.a as f32,
+                ];
+                let mut clear = Layer::default().set_z_index(i32::MIN);
+                clear.push_rect(RectInstance::fill(
+                    [region.start[0] - 1.0, region.start[1] - 1.0],
+                    [region.size[0] + 2.0, region.size[1] + 2.0],
+                    [0.0; 4],
+                    clear_color,
+                ));
+                let damaged = std::iter::once(&clear)
+                    .chain(layers.iter().filter(|layer| match layer.get_bounds() {
+                        Some(bounds) => !bounds.intersect(&region).is_empty(),
+                        None => true,
+                    }))
+                    .collect::<Vec<_>>();
+                let canvas_frame = canvas.get_frame(&self.device)?;
+                self.draw(
+                    &mut encoder,
+                    &canvas_frame,
+                    &damaged,
+                    region.scale(self.scale_factor),
+                    None,
+                );
+            }
+
+            encoder.copy_texture_to_texture(
+                canvas.get_texture().as_image_copy(),
+                frame.get_texture().as_image_copy(),
+                canvas.get_texture().size(),
+            );
+            self.canvas = Some(canvas);
+        }
+        self.damage.clear();
+        self.profiler.end_gpu(&mut encoder);
+
+        let start = Instant::now();
+        self.queue.submit(std::iter::once(encoder.finish()));
+        frame.present();
+        self.profiler.record(Phase::Submit, start);
+        self.profiler.end_frame(&self.device);
+        return Ok(());
+    }
+
+    // renders layers into a texture target, which is cleared to transparent first.
+    // it ignores the damage and does not present, the target is drawn with its image afterwards.
+    pub fn render_to_texture(
+        &mut self,
+        target: &TextureTarget,
+        layers: &[Layer],
+    ) -> Result<(), RenderError> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("renderer.render_to_texture.encoder"),
+            });
+        let frame = target.get_frame(&self.device)?;
+        let (target_width, target_height) = target.size();
+        assert_eq!(
+            frame.sample_count, self.sample_count,
+            "Renderer : the target was created with another sample count."
+        );
+        let layers = layers.iter().collect::<Vec<_>>();
+        self.draw(
+            &mut encoder,
+            &frame,
+            &layers,
+            Clip::new([0.0, 0.0], [target_width as f32, target_height as f32]),
+            Some(wgpu::Color::TRANSPARENT),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+        return Ok(());
+    }
+
+    // draws layers into a frame, only inside of region given in physical pixels.
+    // clear: the color the frame is cleared to first, None draws the layers over its content.
+    fn draw(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: &Frame,
+        layers: &[&Layer],
+        region: Clip,
+        clear: Option<wgpu::Color>,
+    ) {
+        let texture = frame.get_texture();
+        let (target_width, target_height) = (texture.width(), texture.height());
+
+        let start = Instant::now();
+        self.glyph_atlas.begin_frame();
+        self.buffers.update(
+            &self.device,
+            &self.queue,
+            Viewport::new(
+                target_width as f32 / self.scale_factor,
+                target_height as f32 / self.scale_factor,
+                self.scale_factor,
+            )
+            .set_aa_width(self.aa_width),
+            layers,
+            &mut self.glyph_atlas,
+        );
+        self.glyph_atlas.upload(&self.device, &self.queue);
+
+        let mut order = (0..layers.len()).collect::<Vec<_>>();
+        order.sort_by_key(|index| layers[*index].z_index);
+
+        // backdrop blurs need a copy of the frame, so they split the frame in several render passes.
+        let can_blur = texture.usage().contains(wgpu::TextureUsages::COPY_SRC);
+        let mut blurs = vec![None; layers.len()];
+        let mut blur_instances: Vec<BlurInstance> = vec![];
+        if can_blur {
+            for index in &order {
+                let layer = layers[*index];
+                let Some(radius) = layer.backdrop_blur else {
+                    continue;
+                };
+                let blur_region = match &layer.clip {
+                    Some(clip) => clip.scale(self.scale_factor).intersect(&region),
+                    None => region,
+                };
+                if blur_region.is_empty() || radius <= 0.0 {
+                    continue;
+                }
+                blurs[*index] = Some(blur_instances.len() as u32 / 2);
+                blur_instances.extend(BlurInstance::passes(
+                    blur_region.start,
+                    blur_region.size,
+                    blur_region.radius,
+                    [target_width as f32, target_height as f32],
+                    radius * self.scale_factor,
+                ));
+            }
+            if !blur_instances.is_empty() {
+                self.blur_pipeline
+                    .prepare(&self.device, &self.queue, texture, &blur_instances);
+            }
+        }
+        self.profiler.record(Phase::Upload, start);
+
+        let start = Instant::now();
+
+        let mut load = match clear {
+            Some(color) => wgpu::LoadOp::Clear(color),
+            None => wgpu::LoadOp::Load,
+        };
+        let mut pending = vec![];
+        let mut draws = vec![];
+        for index in order {
+            if let Some(blur) = blurs[index] {
+                draws.extend(self.render_layers(encoder, frame, load, layers, &pending, region));
+                self.blur_pipeline
+                    .render(encoder, texture, &frame.view, blur);
+                // horizontal and vertical pass.
+                draws.extend([(PipelineKind::Blur, 1); 2]);
+                load = wgpu::LoadOp::Load;
+                pending.clear();
+            }
+            pending.push(index);
+        }
+        draws.extend(self.render_layers(encoder, frame, load, layers, &pending, region));
+        for (kind, instances) in draws {
+            self.profiler.count_draw(kind, instances);
+        }
+        self.profiler.record(Phase::Encode, start);
+    }
+
+    // draws the given layers in one render pass, returns the pipeline and instance count of each draw.
+    fn render_layers(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: &Frame,
+        load: wgpu::LoadOp<wgpu::Color>,
+        layers: &[&Layer],
+        indices: &[usize],
+        region: Clip,
+    ) -> Vec<(PipelineKind, u32)> {
+        let texture = frame.get_texture();
+        let (target_width, target_height) = (texture.width(), texture.height());
+        let viewport_group = self.buffers.get_viewport_group();
+        let layer_buffers = self.buffers.get_layer_buffers();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("renderer.render.render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &frame.view,
+                resolve_target: frame.resolve_target.as_ref(),
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let mut draws = vec![];
+        for index in indices.iter().copied() {
+            let buffer = &layer_buffers[index];
+            for (command, draw) in layers[index].commands.iter().zip(&buffer.draws) {
+                if draw.instances.is_empty() {
+                    continue;
+                }
+                let [x, y, width, height] = match draw
+                    .clip
+                    .scale(self.scale_factor)
+                    .intersect(&region)
+                    .get_scissor(target_width, target_height)
+                {
+                    Some(scissor) => scissor,
+                    None => continue,
+                };
+                render_pass.set_scissor_rect(x, y, width, height);
+
+                let instances = draw.instances.end - draw.instances.start;
+                let kind = match command {
+                    DrawCommand::Rects(_) => PipelineKind::Rect,
+                    DrawCommand::Triangles(_) => PipelineKind::Triangle,
+                    DrawCommand::Circles(_) => PipelineKind::Circle,
+                    DrawCommand::Lines(_) => PipelineKind::Line,
+                    DrawCommand::Shadows(_) => PipelineKind::Shadow,
+                    DrawCommand::Image(_) => PipelineKind::Image,
+                    DrawCommand::Mesh(_) => PipelineKind::Mesh,
+                    DrawCommand::Texts(_) => PipelineKind::Text,
+                    DrawCommand::PushClip(_)
+                    | DrawCommand::PopClip
+                    | DrawCommand::PushTransform(_)
+                    | DrawCommand::PopTransform => continue,
+                };
+                draws.push((kind, instances));
+
+                match command {
+                    DrawCommand::Rects(_) => self.rect_pipeline.render(
+                        &mut render_pass,
+                        buffer.rect_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::Triangles(_) => self.triangle_pipeline.render(
+                        &mut render_pass,
+                        buffer.triangle_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::Circles(_) => self.circle_pipeline.render(
+                        &mut render_pass,
+                        buffer.circle_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::Lines(_) => self.line_pipeline.render(
+                        &mut render_pass,
+                        buffer.line_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::Shadows(_) => self.shadow_pipeline.render(
+                        &mut render_pass,
+                        buffer.shadow_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::Image(image) => self.image_pipeline.render(
+                        &mut render_pass,
+                        buffer.image_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        &image.texture,
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::Mesh(mesh) => self.mesh_pipeline.render(
+                        &mut render_pass,
+                        buffer.mesh_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        &mesh.buffers,
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::Texts(_) => self.text_pipeline.render(
+                        &mut render_pass,
+                        buffer.text_buffer.get_buffer(),
+                        draw.instances.clone(),
+                        self.glyph_atlas.get_bind_group(),
+                        viewport_group,
+                        draw.state,
+                    ),
+                    DrawCommand::PushClip(_)
+                    | DrawCommand::PopClip
+                    | DrawCommand::PushTransform(_)
+                    | DrawCommand::PopTransform => {}
+                }
+            }
+        }
+        return draws;
+    }
+}
@@ -0,0 +1,81 @@
+// must match MAX_BLUR_SIGMA in blur.rs : 3 sigma on each side.
+const MAX_TAPS = 64;
+
+// radius: [top-left, bottom-left, bottom-right, top-right]
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) start: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) target_size: vec2<f32>,
+    @location(4) direction: vec2<f32>,
+    @location(5) sigma: f32,
+    @location(6) radius: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) start: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) direction: vec2<f32>,
+    @location(3) sigma: f32,
+    @location(4) radius: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    var point = input.start + input.position * input.size;
+    output.position = vec4<f32>(
+        point.x / input.target_size.x * 2.0 - 1.0,
+        1.0 - point.y / input.target_size.y * 2.0,
+        0.0,
+        1.0,
+    );
+    output.start = input.start;
+    output.size = input.size;
+    output.direction = input.direction;
+    output.sigma = input.sigma;
+    output.radius = input.radius;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var taps = min(i32(ceil(input.sigma * 3.0)), MAX_TAPS);
+    var last = vec2<i32>(textureDimensions(source)) - 1;
+    var center = vec2<i32>(input.position.xy);
+    var direction = vec2<i32>(input.direction);
+
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var i = -taps; i <= taps; i++) {
+        var weight = exp(-f32(i * i) / max(2.0 * input.sigma * input.sigma, 0.0001));
+        var coords = clamp(center + direction * i, vec2<i32>(0), last);
+        sum += textureLoad(source, coords, 0) * weight;
+        total += weight;
+    }
+    var color = sum / total;
+
+    // rounded mask of the region, only used by the last pass.
+    color.a = 1.0;
+    if input.radius.x >= 0.0 {
+        var half_size = input.size / 2.0;
+        var p = input.position.xy - input.start - half_size;
+        var radius = select(
+            select(input.radius.x, input.radius.y, p.y > 0.0),
+            select(input.radius.w, input.radius.z, p.y > 0.0),
+            p.x > 0.0,
+        );
+        radius = min(radius, min(half_size.x, half_size.y));
+        var q = abs(p) - half_size + radius;
+        var distance = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+        color.a = clamp(0.5 - distance, 0.0, 1.0);
+    }
+    return color;
+}
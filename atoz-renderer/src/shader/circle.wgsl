@@ -1,17 +1,3 @@
-struct Viewport {
-    width: f32,
-    height: f32,
-}
-
-@group(0) @binding(0) var<uniform> viewport : Viewport;
-
-fn convert_pxl_dcm(x: f32, y: f32) -> vec2<f32> {
-    var pos: vec2<f32>;
-    pos.x = (x - viewport.width / 2.0) / (viewport.width / 2.0);
-    pos.y = - (y - viewport.height / 2.0) / (viewport.height / 2.0);
-    return pos;
-}
-
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) center: vec2<f32>,
@@ -19,6 +5,7 @@ struct VertexInput {
     @location(3) thickness: f32,
     @location(4) fill_color: vec4<f32>,
     @location(5) line_color: vec4<f32>,
+    @location(6) paint: vec2<u32>,
 }
 
 struct VertexOutput {
@@ -28,6 +15,7 @@ struct VertexOutput {
     @location(2) thickness: f32,
     @location(3) fill_color: vec4<f32>,
     @location(4) line_color: vec4<f32>,
+    @location(5) @interpolate(flat) paint: vec2<u32>,
 }
 
 @vertex
@@ -44,29 +32,35 @@ fn vs_main(input: VertexInput) -> VertexOutput {
     output.thickness = input.thickness;
     output.fill_color = input.fill_color;
     output.line_color = input.line_color;
+    output.paint = input.paint;
 
     return output;
 }
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var position = local_position(input.position.xy);
     var color: vec4<f32>;
+    var start = input.center - input.radius;
+    var size = vec2<f32>(input.radius * 2.0);
+    var fill_color = paint_color(input.paint.x, input.fill_color, position, start, size);
+    var line_color = paint_color(input.paint.y, input.line_color, position, start, size);
 
     var diff_1 = vec2<f32>(
-        input.center.x - (input.position.x + 0.25),
-        input.center.y - (input.position.y + 0.25),
+        input.center.x - (position.x + 0.25),
+        input.center.y - (position.y + 0.25),
     );
     var diff_2 = vec2<f32>(
-        input.center.x - (input.position.x + 0.25),
-        input.center.y - (input.position.y + 0.75),
+        input.center.x - (position.x + 0.25),
+        input.center.y - (position.y + 0.75),
     );
     var diff_3 = vec2<f32>(
-        input.center.x - (input.position.x + 0.75),
-        input.center.y - (input.position.y + 0.25),
+        input.center.x - (position.x + 0.75),
+        input.center.y - (position.y + 0.25),
     );
     var diff_4 = vec2<f32>(
-        input.center.x - (input.position.x + 0.75),
-        input.center.y - (input.position.y + 0.75),
+        input.center.x - (position.x + 0.75),
+        input.center.y - (position.y + 0.75),
     );
     var radiuses = vec4<f32>(
         sqrt(
@@ -96,7 +90,8 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
         step(radiuses[3], input.radius)
     ) / 4.0 - fill_alpha;
 
-    color = line_alpha * input.line_color + fill_alpha * input.fill_color;
+    color = line_alpha * line_color + fill_alpha * fill_color;
 
+    color.a *= clip_alpha(input.position.xy);
     return color;
 }
\ No newline at end of file
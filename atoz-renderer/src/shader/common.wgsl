@@ -0,0 +1,95 @@
+// width, height and aa_width in logical pixels.
+struct Viewport {
+    width: f32,
+    height: f32,
+    scale_factor: f32,
+    aa_width: f32,
+}
+
+// radius: [top-left, bottom-left, bottom-right, top-right]
+struct DrawState {
+    clip_start: vec2<f32>,
+    clip_size: vec2<f32>,
+    clip_radius: vec4<f32>,
+    transform: mat2x4<f32>,
+    inverse: mat2x4<f32>,
+    paint_offset: u32,
+}
+
+const GRADIENT_LINEAR = 1u;
+const GRADIENT_RADIAL = 2u;
+
+// linear geometry: direction. radial geometry: center relative to the box, radius.
+struct Gradient {
+    kind: u32,
+    count: u32,
+    geometry: vec4<f32>,
+    offsets: array<vec4<f32>, 2>,
+    colors: array<vec4<f32>, 8>,
+}
+
+@group(0) @binding(0) var<uniform> viewport : Viewport;
+@group(0) @binding(1) var<uniform> state : DrawState;
+@group(0) @binding(2) var<storage, read> gradients : array<Gradient>;
+
+// from the local space of the instances to screen pixels.
+fn transform_point(point: vec2<f32>) -> vec2<f32> {
+    var p = vec4<f32>(point, 1.0, 0.0);
+    return vec2<f32>(dot(state.transform[0], p), dot(state.transform[1], p));
+}
+
+// from physical pixels back to the local space, used by the fragment shaders.
+fn local_position(position: vec2<f32>) -> vec2<f32> {
+    var p = vec4<f32>(position / viewport.scale_factor, 1.0, 0.0);
+    return vec2<f32>(dot(state.inverse[0], p), dot(state.inverse[1], p));
+}
+
+// applies the transform of the draw call, then converts pixels to device coordinates.
+fn convert_pxl_dcm(x: f32, y: f32) -> vec2<f32> {
+    var point = transform_point(vec2<f32>(x, y));
+    var pos: vec2<f32>;
+    pos.x = (point.x - viewport.width / 2.0) / (viewport.width / 2.0);
+    pos.y = - (point.y - viewport.height / 2.0) / (viewport.height / 2.0);
+    return pos;
+}
+
+// coverage of the current clip at a physical pixel, using the signed distance of a rounded box.
+fn clip_alpha(position: vec2<f32>) -> f32 {
+    var half_size = state.clip_size * viewport.scale_factor / 2.0;
+    var p = position - state.clip_start * viewport.scale_factor - half_size;
+    var radius = select(
+        select(state.clip_radius.x, state.clip_radius.y, p.y > 0.0),
+        select(state.clip_radius.w, state.clip_radius.z, p.y > 0.0),
+        p.x > 0.0,
+    ) * viewport.scale_factor;
+    radius = min(radius, min(half_size.x, half_size.y));
+    var q = abs(p) - half_size + radius;
+    var distance = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+    return clamp(0.5 - distance, 0.0, 1.0);
+}
+
+
+// color of a paint at a local position inside the box of an instance. paint 0 is the flat color.
+fn paint_color(paint: u32, color: vec4<f32>, position: vec2<f32>, start: vec2<f32>, size: vec2<f32>) -> vec4<f32> {
+    if paint == 0u {
+        return color;
+    }
+    var index = state.paint_offset + paint - 1u;
+    var geometry = gradients[index].geometry;
+    var t: f32;
+    if gradients[index].kind == GRADIENT_LINEAR {
+        var half_length = abs(geometry.x) * size.x / 2.0 + abs(geometry.y) * size.y / 2.0;
+        t = dot(position - start - size / 2.0, geometry.xy) / max(half_length * 2.0, 0.0001) + 0.5;
+    } else {
+        var radius = geometry.z * min(size.x, size.y) / 2.0;
+        t = length(position - start - geometry.xy * size) / max(radius, 0.0001);
+    }
+
+    var result = gradients[index].colors[0];
+    for (var i = 1u; i < gradients[index].count; i++) {
+        var previous = gradients[index].offsets[(i - 1u) / 4u][(i - 1u) % 4u];
+        var offset = gradients[index].offsets[i / 4u][i % 4u];
+        result = mix(result, gradients[index].colors[i], clamp((t - previous) / max(offset - previous, 0.0001), 0.0, 1.0));
+    }
+    return result;
+}
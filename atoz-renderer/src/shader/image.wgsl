@@ -1,32 +1,23 @@
-struct Viewport {
-    width: f32,
-    height: f32,
-}
-
-@group(0) @binding(0) var<uniform> viewport : Viewport;
-
-fn convert_pxl_dcm(x: f32, y: f32) -> vec2<f32> {
-    var pos: vec2<f32>;
-    pos.x = (x - viewport.width / 2.0) / (viewport.width / 2.0);
-    pos.y = - (y - viewport.height / 2.0) / (viewport.height / 2.0);
-    return pos;
-}
-
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) start: vec2<f32>,
     @location(2) size: vec2<f32>,
+    @location(3) uv_start: vec2<f32>,
+    @location(4) uv_size: vec2<f32>,
+    @location(5) opacity: f32,
 }
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) opacity: f32,
 }
 
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
-    output.tex_coords = input.position;
+    output.tex_coords = input.uv_start + input.position * input.uv_size;
+    output.opacity = input.opacity;
     output.position = vec4<f32>(
         convert_pxl_dcm(
             input.start.x + input.position.x * input.size.x,
@@ -43,5 +34,14 @@ var texture_sampler: sampler;
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(texture_image, texture_sampler, input.tex_coords);
+    var color = textureSample(texture_image, texture_sampler, input.tex_coords);
+    color.a *= input.opacity * clip_alpha(input.position.xy);
+    return color;
+}
+
+// for textures rendered by the renderer, which hold premultiplied colors.
+@fragment
+fn fs_premultiplied(input: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(texture_image, texture_sampler, input.tex_coords);
+    return color * input.opacity * clip_alpha(input.position.xy);
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+const MITER_LIMIT = 4.0;
+
+const END_BUTT = 0u;
+const END_ROUND = 1u;
+const END_SQUARE = 2u;
+const END_ROUND_JOIN = 4u;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) start: vec2<f32>,
+    @location(2) end: vec2<f32>,
+    @location(3) start_normal: vec2<f32>,
+    @location(4) end_normal: vec2<f32>,
+    @location(5) thickness: f32,
+    @location(6) kind: vec2<u32>,
+    @location(7) dash: vec2<f32>,
+    @location(8) dash_offset: f32,
+    @location(9) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) start: vec2<f32>,
+    @location(1) end: vec2<f32>,
+    @location(2) start_normal: vec2<f32>,
+    @location(3) end_normal: vec2<f32>,
+    @location(4) thickness: f32,
+    @location(5) @interpolate(flat) kind: vec2<u32>,
+    @location(6) dash: vec2<f32>,
+    @location(7) dash_offset: f32,
+    @location(8) color: vec4<f32>,
+}
+
+fn line_direction(start: vec2<f32>, end: vec2<f32>) -> vec2<f32> {
+    if distance(start, end) == 0.0 {
+        return vec2<f32>(1.0, 0.0);
+    }
+    return normalize(end - start);
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    var direction = line_direction(input.start, input.end);
+    var normal = vec2<f32>(-direction.y, direction.x);
+    var half_thickness = input.thickness / 2.0;
+
+    // long enough for caps and miters, wide enough for anti-aliasing.
+    var extension = half_thickness * MITER_LIMIT + 1.0;
+    var along = mix(-extension, distance(input.start, input.end) + extension, input.position.x);
+    var across = mix(-half_thickness - 1.0, half_thickness + 1.0, input.position.y);
+    var point = input.start + direction * along + normal * across;
+
+    output.position = vec4<f32>(convert_pxl_dcm(point.x, point.y), 0.0, 1.0);
+    output.start = input.start;
+    output.end = input.end;
+    output.start_normal = input.start_normal;
+    output.end_normal = input.end_normal;
+    output.thickness = input.thickness;
+    output.kind = input.kind;
+    output.dash = input.dash;
+    output.dash_offset = input.dash_offset;
+    output.color = input.color;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var position = local_position(input.position.xy);
+    var direction = line_direction(input.start, input.end);
+    var normal = vec2<f32>(-direction.y, direction.x);
+    var segment_length = distance(input.start, input.end);
+    var half_thickness = input.thickness / 2.0;
+
+    var from_start = position - input.start;
+    var from_end = position - input.end;
+    var along = dot(from_start, direction);
+    var edge_distance = abs(dot(from_start, normal)) - half_thickness;
+
+    // joins are cut without anti-aliasing, so that neighbouring segments do not leave a seam.
+    // past the joint a round join is the disc around it, on the side of the cut of this segment.
+    var inside = 1.0;
+    if input.kind.x == END_ROUND {
+        if along < 0.0 {
+            edge_distance = length(from_start) - half_thickness;
+        }
+    } else if input.kind.x == END_BUTT {
+        edge_distance = max(edge_distance, -along);
+    } else if input.kind.x == END_SQUARE {
+        edge_distance = max(edge_distance, -along - half_thickness);
+    } else {
+        inside *= step(0.0, dot(from_start, input.start_normal));
+        if input.kind.x == END_ROUND_JOIN && along < 0.0 {
+            edge_distance = length(from_start) - half_thickness;
+        }
+    }
+
+    if input.kind.y == END_ROUND {
+        if along > segment_length {
+            edge_distance = length(from_end) - half_thickness;
+        }
+    } else if input.kind.y == END_BUTT {
+        edge_distance = max(edge_distance, along - segment_length);
+    } else if input.kind.y == END_SQUARE {
+        edge_distance = max(edge_distance, along - segment_length - half_thickness);
+    } else {
+        inside *= 1.0 - step(0.0, dot(from_end, input.end_normal));
+        if input.kind.y == END_ROUND_JOIN && along > segment_length {
+            edge_distance = length(from_end) - half_thickness;
+        }
+    }
+
+    if input.dash.x > 0.0 {
+        var period = input.dash.x + input.dash.y;
+        var phase = along + input.dash_offset;
+        phase = phase - period * floor(phase / period);
+        var dash_distance = select(
+            min(phase - input.dash.x, period - phase),
+            max(-phase, phase - input.dash.x),
+            phase < input.dash.x,
+        );
+        edge_distance = max(edge_distance, dash_distance);
+    }
+
+    var color = input.color;
+    color.a *= (1.0 - smoothstep(-viewport.aa_width, viewport.aa_width, edge_distance)) * inside;
+    color.a *= clip_alpha(input.position.xy);
+    return color;
+}
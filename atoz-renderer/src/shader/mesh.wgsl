@@ -0,0 +1,28 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) start: vec2<f32>,
+    @location(3) scale: vec2<f32>,
+    @location(4) tint: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    var point = input.start + input.position * input.scale;
+    output.position = vec4<f32>(convert_pxl_dcm(point.x, point.y), 0.0, 1.0);
+    output.color = input.color * input.tint;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var color = input.color;
+    color.a *= clip_alpha(input.position.xy);
+    return color;
+}
@@ -1,23 +1,7 @@
-struct Viewport {
-    width: f32,
-    height: f32,
-}
-
-@group(0) @binding(0) var<uniform> viewport : Viewport;
-
-fn convert_pxl_dcm(x: f32, y: f32) -> vec2<f32> {
-    var pos: vec2<f32>;
-    pos.x = (x - viewport.width / 2.0) / (viewport.width / 2.0);
-    pos.y = - (y - viewport.height / 2.0) / (viewport.height / 2.0);
-    return pos;
-}
-
 fn smoothstep_x2(n1: f32, n2: f32, n3: f32, n4: f32, x: f32) -> f32 {
     return smoothstep(n1, n2, x) * (1.0 - smoothstep(n3, n4, x));
 }
 
-const ANTI_ALIASING_WIDTH = 0.5;
-
 // radius: [top-left, bottom-left, bottom-right, top-left]
 
 struct VertexInput {
@@ -28,6 +12,7 @@ struct VertexInput {
     @location(4) radius: vec4<f32>,
     @location(5) fill_color: vec4<f32>,
     @location(6) line_color: vec4<f32>,
+    @location(7) paint: vec2<u32>,
 }
 
 struct VertexOutput {
@@ -38,6 +23,7 @@ struct VertexOutput {
     @location(3) radius: vec4<f32>,
     @location(4) fill_color: vec4<f32>,
     @location(5) line_color: vec4<f32>,
+    @location(6) @interpolate(flat) paint: vec2<u32>,
 }
 
 @vertex
@@ -55,12 +41,16 @@ fn vs_main(input: VertexInput) -> VertexOutput {
     output.radius = input.radius;
     output.fill_color = input.fill_color;
     output.line_color = input.line_color;
+    output.paint = input.paint;
     return output;
 }
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var position = local_position(input.position.xy);
     var color: vec4<f32>;
+    var fill_color = paint_color(input.paint.x, input.fill_color, position, input.start, input.size);
+    var line_color = paint_color(input.paint.y, input.line_color, position, input.start, input.size);
 
     var min_radius = min(input.size.x, input.size.y) / 2.0;
     var radius = vec4<f32>(
@@ -77,51 +67,52 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
         input.start.x + input.size.x - radius.w, input.start.y + radius.w,
     );
 
-    var in_top_left = step(input.position.x, round_centers[0][0]) * step(input.position.y, round_centers[0][1]);
-    var in_btm_left = step(input.position.x, round_centers[1][0]) * step(round_centers[1][1], input.position.y);
-    var in_btm_right = step(round_centers[2][0], input.position.x) * step(round_centers[2][1], input.position.y);
-    var in_top_right = step(round_centers[3][0], input.position.x) * step(input.position.y, round_centers[3][1]);
+    var in_top_left = step(position.x, round_centers[0][0]) * step(position.y, round_centers[0][1]);
+    var in_btm_left = step(position.x, round_centers[1][0]) * step(round_centers[1][1], position.y);
+    var in_btm_right = step(round_centers[2][0], position.x) * step(round_centers[2][1], position.y);
+    var in_top_right = step(round_centers[3][0], position.x) * step(position.y, round_centers[3][1]);
 
     if in_top_left + in_btm_left + in_btm_right + in_top_right > 0.0 { // rounded corner
         var selected_center = round_centers[0] * in_top_left + round_centers[1] * in_btm_left + round_centers[2] * in_btm_right + round_centers[3] * in_top_right;
         var selected_radius = radius.x * in_top_left + radius.y * in_btm_left + radius.z * in_btm_right + radius.w * in_top_right;
-        var diff_x = selected_center.x - input.position.x;
-        var diff_y = selected_center.y - input.position.y;
+        var diff_x = selected_center.x - position.x;
+        var diff_y = selected_center.y - position.y;
         var current_radius = sqrt(
             diff_x * diff_x + diff_y * diff_y
         );
 
         var fill_alpha = 1.0 - smoothstep(
-            selected_radius - input.thickness - ANTI_ALIASING_WIDTH,
+            selected_radius - input.thickness - viewport.aa_width,
             selected_radius - input.thickness,
             current_radius,
         );
         var line_alpha = smoothstep_x2(
-            selected_radius - input.thickness - ANTI_ALIASING_WIDTH,
+            selected_radius - input.thickness - viewport.aa_width,
             selected_radius - input.thickness,
-            selected_radius - ANTI_ALIASING_WIDTH,
+            selected_radius - viewport.aa_width,
             selected_radius,
             current_radius,
         );
-        color = fill_alpha * input.fill_color + line_alpha * input.line_color;
+        color = fill_alpha * fill_color + line_alpha * line_color;
     } else {
         var fill_alpha = smoothstep_x2(
             input.start.x + input.thickness,
-            input.start.x + input.thickness + ANTI_ALIASING_WIDTH,
-            input.start.x + input.size.x - input.thickness - ANTI_ALIASING_WIDTH,
+            input.start.x + input.thickness + viewport.aa_width,
+            input.start.x + input.size.x - input.thickness - viewport.aa_width,
             input.start.x + input.size.x - input.thickness,
-            input.position.x,
+            position.x,
         ) * smoothstep_x2(
             input.start.y + input.thickness,
-            input.start.y + input.thickness + ANTI_ALIASING_WIDTH,
-            input.start.y + input.size.y - input.thickness - ANTI_ALIASING_WIDTH,
+            input.start.y + input.thickness + viewport.aa_width,
+            input.start.y + input.size.y - input.thickness - viewport.aa_width,
             input.start.y + input.size.y - input.thickness,
-            input.position.y,
+            position.y,
         );
         var line_alpha = 1.0 - fill_alpha;
-        color = fill_alpha * input.fill_color + line_alpha * input.line_color;
+        color = fill_alpha * fill_color + line_alpha * line_color;
     }
 
+    color.a *= clip_alpha(input.position.xy);
     return color;
 }
 
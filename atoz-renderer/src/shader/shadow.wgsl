@@ -0,0 +1,90 @@
+// radius: [top-left, bottom-left, bottom-right, top-right]
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) start: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) radius: vec4<f32>,
+    @location(4) offset: vec2<f32>,
+    @location(5) blur: f32,
+    @location(6) spread: f32,
+    @location(7) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) start: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) radius: vec4<f32>,
+    @location(3) sigma: f32,
+    @location(4) color: vec4<f32>,
+}
+
+// abramowitz and stegun approximation, accurate enough for 8 bit alpha.
+fn erf(x: f32) -> f32 {
+    var a = abs(x);
+    var t = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    t *= t;
+    return sign(x) * (1.0 - 1.0 / (t * t));
+}
+
+// corner radii grown like css box-shadow : a sharp corner stays sharp, and radii smaller than
+// the spread grow less than it.
+fn spread_radius(radius: vec4<f32>, spread: f32) -> vec4<f32> {
+    if spread <= 0.0 {
+        return max(radius + spread, vec4<f32>(0.0));
+    }
+    var ratio = radius / spread - 1.0;
+    var small = radius + spread * (1.0 + ratio * ratio * ratio);
+    return select(radius + spread, small, radius < vec4<f32>(spread));
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    var sigma = input.blur / 2.0;
+    // the shadow box, grown by the spread and moved by the offset.
+    var start = input.start + input.offset - input.spread;
+    var size = max(input.size + input.spread * 2.0, vec2<f32>(0.0));
+    // the quad covers the fading edge of the shadow.
+    var extent = sigma * 3.0 + 1.0;
+    output.position = vec4<f32>(
+        convert_pxl_dcm(
+            start.x - extent + input.position.x * (size.x + extent * 2.0),
+            start.y - extent + input.position.y * (size.y + extent * 2.0),
+        ), 0.0, 1.0,
+    );
+    output.start = start;
+    output.size = size;
+    output.radius = spread_radius(input.radius, input.spread);
+    output.sigma = sigma;
+    output.color = input.color;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var position = local_position(input.position.xy);
+
+    var half_size = input.size / 2.0;
+    var p = position - input.start - half_size;
+    var radius = select(
+        select(input.radius.x, input.radius.y, p.y > 0.0),
+        select(input.radius.w, input.radius.z, p.y > 0.0),
+        p.x > 0.0,
+    );
+    radius = min(radius, min(half_size.x, half_size.y));
+    var q = abs(p) - half_size + radius;
+    var distance = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+
+    var alpha: f32;
+    if input.sigma > 0.0 {
+        alpha = 0.5 - 0.5 * erf(distance / (input.sigma * sqrt(2.0)));
+    } else {
+        alpha = clamp(0.5 - distance, 0.0, 1.0);
+    }
+
+    var color = input.color;
+    color.a *= alpha * clip_alpha(input.position.xy);
+    return color;
+}
@@ -0,0 +1,40 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) start: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) uv_start: vec2<f32>,
+    @location(4) uv_size: vec2<f32>,
+    @location(5) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.tex_coords = input.uv_start + input.position * input.uv_size;
+    output.position = vec4<f32>(
+        convert_pxl_dcm(
+            input.start.x + input.position.x * input.size.x,
+            input.start.y + input.position.y * input.size.y,
+        ), 0.0, 1.0,
+    );
+    output.color = input.color;
+    return output;
+}
+
+@group(1) @binding(0)
+var glyph_atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var glyph_sampler: sampler;
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var atlas_size = vec2<f32>(textureDimensions(glyph_atlas));
+    var coverage = textureSample(glyph_atlas, glyph_sampler, input.tex_coords / atlas_size).r;
+    return vec4<f32>(input.color.rgb, input.color.a * coverage * clip_alpha(input.position.xy));
+}
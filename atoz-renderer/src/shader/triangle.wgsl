@@ -1,17 +1,3 @@
-struct Viewport {
-    width: f32,
-    height: f32,
-}
-
-@group(0) @binding(0) var<uniform> viewport : Viewport;
-
-fn convert_pxl_dcm(x: f32, y: f32) -> vec2<f32> {
-    var pos: vec2<f32>;
-    pos.x = (x - viewport.width / 2.0) / (viewport.width / 2.0);
-    pos.y = - (y - viewport.height / 2.0) / (viewport.height / 2.0);
-    return pos;
-}
-
 fn smoothstep_x2(n1: f32, n2: f32, n3: f32, n4: f32, x: f32) -> f32 {
     return smoothstep(n1, n2, x) * (1.0 - smoothstep(n3, n4, x));
 }
@@ -28,8 +14,6 @@ fn pnt_to_func(p1: vec2<f32>, p2: vec2<f32>) -> vec3<f32> {
     return vec3<f32>(a, -1.0, b);
 }
 
-const ANTI_ALIASING_WIDTH = 0.5;
-
 struct VertexInput {
     @location(0) index: u32,
     @location(1) point1: vec2<f32>,
@@ -74,29 +58,32 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    var position = local_position(input.position.xy);
     var color: vec4<f32>;
 
     var func_1_2 = pnt_to_func(input.point1, input.point2);
     var func_2_3 = pnt_to_func(input.point2, input.point3);
     var func_3_1 = pnt_to_func(input.point3, input.point1);
 
-    var dst_1_2 = dst_pos_to_line(func_1_2, input.position.x, input.position.y);
-    var dst_2_3 = dst_pos_to_line(func_2_3, input.position.x, input.position.y);
-    var dst_3_1 = dst_pos_to_line(func_3_1, input.position.x, input.position.y);
+    var dst_1_2 = dst_pos_to_line(func_1_2, position.x, position.y);
+    var dst_2_3 = dst_pos_to_line(func_2_3, position.x, position.y);
+    var dst_3_1 = dst_pos_to_line(func_3_1, position.x, position.y);
 
     var fill_alpha = smoothstep(
-        input.thickness - ANTI_ALIASING_WIDTH,
+        input.thickness - viewport.aa_width,
         input.thickness,
         dst_1_2
     ) * smoothstep(
-        input.thickness - ANTI_ALIASING_WIDTH,
+        input.thickness - viewport.aa_width,
         input.thickness,
         dst_2_3
     ) * smoothstep(
-        input.thickness - ANTI_ALIASING_WIDTH,
+        input.thickness - viewport.aa_width,
         input.thickness,
         dst_3_1
     );
 
-    return input.fill_color * fill_alpha + input.line_color * (1.0 - fill_alpha);
+    color = input.fill_color * fill_alpha + input.line_color * (1.0 - fill_alpha);
+    color.a *= clip_alpha(input.position.xy);
+    return color;
 }
\ No newline at end of file
@@ -0,0 +1,211 @@
+// # Snapshot Testing
+// ## description
+//     renders layers at a fixed size and compares the result with a checked-in png.
+// ## golden images
+//     set ATOZ_UPDATE_SNAPSHOTS=1 to write missing goldens and overwrite the existing ones.
+//     without it a missing golden fails the check.
+// ## on mismatch
+//     {name}.actual.png and {name}.diff.png are written next to the golden image.
+
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    layer::Layer,
+    renderer::{RenderError, Renderer},
+};
+
+const UPDATE_ENV: &str = "ATOZ_UPDATE_SNAPSHOTS";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    MissingGolden(PathBuf),
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    PixelMismatch {
+        count: usize,
+        diff: PathBuf,
+    },
+    Image(image::ImageError),
+    Render(RenderError),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SnapshotError::MissingGolden(path) => write!(
+                f,
+                "snapshot golden missing : {}, run with {}=1 to write it",
+                path.display(),
+                UPDATE_ENV
+            ),
+            SnapshotError::SizeMismatch { expected, actual } => write!(
+                f,
+                "snapshot size mismatch : expected {:?}, actual {:?}",
+                expected, actual
+            ),
+            SnapshotError::PixelMismatch { count, diff } => write!(
+                f,
+                "snapshot mismatch : {} pixels differ, see {}",
+                count,
+                diff.display()
+            ),
+            SnapshotError::Image(err) => write!(f, "snapshot image error : {}", err),
+            SnapshotError::Render(err) => write!(f, "snapshot render error : {}", err),
+        };
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<image::ImageError> for SnapshotError {
+    fn from(err: image::ImageError) -> Self {
+        return SnapshotError::Image(err);
+    }
+}
+
+impl From<RenderError> for SnapshotError {
+    fn from(err: RenderError) -> Self {
+        return SnapshotError::Render(err);
+    }
+}
+
+pub struct Snapshot {
+    dir: PathBuf,
+    tolerance: u8,
+}
+
+impl Snapshot {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        return Self {
+            dir: dir.into(),
+            tolerance: 2,
+        };
+    }
+
+    // maximum difference allowed per color channel.
+    pub fn set_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        return self;
+    }
+
+    pub fn render_layers(
+        renderer: &mut Renderer,
+        width: u32,
+        height: u32,
+        layers: &[Layer],
+    ) -> Result<RgbaImage, SnapshotError> {
+        let target = renderer.create_offscreen_target(width, height);
+        renderer.render(&target, layers)?;
+        return Ok(renderer.read_image(&target));
+    }
+
+    pub fn check_layers(
+        &self,
+        name: &str,
+        renderer: &mut Renderer,
+        width: u32,
+        height: u32,
+        layers: &[Layer],
+    ) -> Result<(), SnapshotError> {
+        let image = Self::render_layers(renderer, width, height, layers)?;
+        return self.check(name, &image);
+    }
+
+    pub fn check(&self, name: &str, actual: &RgbaImage) -> Result<(), SnapshotError> {
+        let golden = self.dir.join(format!("{}.png", name));
+        if env::var(UPDATE_ENV).is_ok_and(|v| v == "1") {
+            std::fs::create_dir_all(&self.dir).map_err(image::ImageError::IoError)?;
+            actual.save(&golden)?;
+            return Ok(());
+        }
+        if !golden.exists() {
+            return Err(SnapshotError::MissingGolden(golden));
+        }
+
+        let expected = image::open(&golden)?.to_rgba8();
+        if expected.dimensions() != actual.dimensions() {
+            return Err(SnapshotError::SizeMismatch {
+                expected: expected.dimensions(),
+                actual: actual.dimensions(),
+            });
+        }
+
+        let (count, diff) = compare(&expected, actual, self.tolerance);
+        if count == 0 {
+            return Ok(());
+        }
+        let diff_path = self.dir.join(format!("{}.diff.png", name));
+        diff.save(&diff_path)?;
+        actual.save(self.dir.join(format!("{}.actual.png", name)))?;
+        return Err(SnapshotError::PixelMismatch {
+            count,
+            diff: diff_path,
+        });
+    }
+
+    pub fn get_dir(&self) -> &Path {
+        return &self.dir;
+    }
+}
+
+// returns the number of pixels that differ by more than tolerance and an image marking them in red.
+pub fn compare(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (usize, RgbaImage) {
+    let mut count = 0;
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+
+    for (x, y, expected_pixel) in expected.enumerate_pixels() {
+        let actual_pixel = actual.get_pixel(x, y);
+        let mismatch = expected_pixel
+            .0
+            .iter()
+            .zip(actual_pixel.0.iter())
+            .any(|(e, a)| e.abs_diff(*a) > tolerance);
+
+        if mismatch {
+            count += 1;
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            let [r, g, b, _] = expected_pixel.0;
+            let luma = ((r as u32 + g as u32 + b as u32) / 3 / 4) as u8;
+            diff.put_pixel(x, y, Rgba([luma, luma, luma, 255]));
+        }
+    }
+    return (count, diff);
+}
+
+#[cfg(test)]
+mod test {
+    use image::{Rgba, RgbaImage};
+
+    use super::{compare, Snapshot, SnapshotError};
+
+    #[test]
+    fn compare_with_tolerance() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 1, Rgba([102, 100, 100, 255]));
+        actual.put_pixel(2, 2, Rgba([110, 100, 100, 255]));
+
+        let (count, diff) = compare(&expected, &actual, 2);
+        assert_eq!(count, 1);
+        assert_eq!(diff.get_pixel(2, 2).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn missing_golden() {
+        let dir = std::env::temp_dir().join("atoz_missing_golden");
+        let image = RgbaImage::new(4, 4);
+        let result = Snapshot::new(&dir).check("missing", &image);
+        assert!(
+            matches!(result, Err(SnapshotError::MissingGolden(path)) if path == dir.join("missing.png"))
+        );
+        assert!(!dir.join("missing.png").exists());
+    }
+}
@@ -0,0 +1,41 @@
+use bytemuck::{Pod, Zeroable};
+
+use crate::{clip::Clip, transform::Transform};
+
+// uniform bound with a dynamic offset for every draw call. must match DrawState in common.wgsl.
+// transform rows are padded to vec4 for the uniform layout.
+// paint_offset is the index of the first gradient of the layer in the paint buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct DrawState {
+    pub clip_start: [f32; 2],
+    pub clip_size: [f32; 2],
+    pub clip_radius: [f32; 4],
+    pub transform: [[f32; 4]; 2],
+    pub inverse: [[f32; 4]; 2],
+    pub paint_offset: u32,
+    pub _padding: [u32; 3],
+}
+
+impl DrawState {
+    pub fn new(clip: Clip, transform: Transform, paint_offset: u32) -> Self {
+        let inverse = transform.inverse();
+        return Self {
+            clip_start: clip.start,
+            clip_size: clip.size,
+            clip_radius: clip.radius,
+            transform: Self::pad(transform),
+            inverse: Self::pad(inverse),
+            paint_offset,
+            _padding: [0; 3],
+        };
+    }
+
+    fn pad(transform: Transform) -> [[f32; 4]; 2] {
+        let m = transform.matrix;
+        return [
+            [m[0][0], m[0][1], m[0][2], 0.0],
+            [m[1][0], m[1][1], m[1][2], 0.0],
+        ];
+    }
+}
@@ -0,0 +1,354 @@
+use image::RgbaImage;
+use wgpu::{Device, Queue, Surface, SurfaceConfiguration, SurfaceTexture, Texture, TextureView};
+
+use crate::{layouts::BindGroupLayouts, pipeline::image::ImageResource, renderer::RenderError};
+
+pub trait RenderTarget {
+    fn size(&self) -> (u32, u32);
+    fn get_frame(&self, device: &Device) -> Result<Frame<'_>, RenderError>;
+}
+
+// with multisampling, view is the multisampled attachment and resolve_target the frame itself.
+pub struct Frame<'a> {
+    pub view: TextureView,
+    pub resolve_target: Option<TextureView>,
+    pub sample_count: u32,
+    texture: Option<&'a Texture>,
+    surface_texture: Option<SurfaceTexture>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(
+        view: TextureView,
+        multisample: Option<&Texture>,
+        texture: Option<&'a Texture>,
+        surface_texture: Option<SurfaceTexture>,
+    ) -> Self {
+        return match multisample {
+            Some(multisample) => Self {
+                view: multisample.create_view(&wgpu::TextureViewDescriptor::default()),
+                resolve_target: Some(view),
+                sample_count: multisample.sample_count(),
+                texture,
+                surface_texture,
+            },
+            None => Self {
+                view,
+                resolve_target: None,
+                sample_count: 1,
+                texture,
+                surface_texture,
+            },
+        };
+    }
+
+    // the texture the frame ends up in. its usage tells whether it can be copied from or into.
+    pub fn get_texture(&self) -> &Texture {
+        return match &self.surface_texture {
+            Some(surface_texture) => &surface_texture.texture,
+            None => self
+                .texture
+                .expect("Frame : an offscreen frame always has a texture."),
+        };
+    }
+
+    pub fn present(self) {
+        if let Some(surface_texture) = self.surface_texture {
+            surface_texture.present();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SurfaceTarget {
+    surface: Surface,
+    config: SurfaceConfiguration,
+    sample_count: u32,
+    multisample: Option<Texture>,
+}
+
+impl SurfaceTarget {
+    // sample_count must match the renderer drawing into the target.
+    pub fn new(
+        surface: Surface,
+        config: SurfaceConfiguration,
+        device: &Device,
+        sample_count: u32,
+    ) -> Self {
+        surface.configure(device, &config);
+        let multisample = create_multisample_texture(
+            device,
+            config.width,
+            config.height,
+            config.format,
+            sample_count,
+        );
+        return Self {
+            surface,
+            config,
+            sample_count,
+            multisample,
+        };
+    }
+
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.config.width = width;
+            self.config.height = height;
+            self.surface.configure(device, &self.config);
+            self.multisample = create_multisample_texture(
+                device,
+                width,
+                height,
+                self.config.format,
+                self.sample_count,
+            );
+        }
+    }
+
+    pub fn get_config(&self) -> &SurfaceConfiguration {
+        return &self.config;
+    }
+}
+
+impl RenderTarget for SurfaceTarget {
+    fn size(&self) -> (u32, u32) {
+        return (self.config.width, self.config.height);
+    }
+
+    // a lost or outdated surface, e.g. after a suspend or a resize, is configured again once.
+    fn get_frame(&self, device: &Device) -> Result<Frame<'_>, RenderError> {
+        let surface_texture = match self.surface.get_current_texture() {
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(device, &self.config);
+                self.surface.get_current_texture()?
+            }
+            result => result?,
+        };
+        let view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        return Ok(Frame::new(
+            view,
+            self.multisample.as_ref(),
+            None,
+            Some(surface_texture),
+        ));
+    }
+}
+
+#[derive(Debug)]
+pub struct OffscreenTarget {
+    texture: Texture,
+    multisample: Option<Texture>,
+}
+
+impl OffscreenTarget {
+    // sample_count must match the renderer drawing into the target.
+    pub fn new(
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen_target.texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        return Self {
+            texture,
+            multisample: create_multisample_texture(device, width, height, format, sample_count),
+        };
+    }
+
+    pub fn get_texture(&self) -> &Texture {
+        return &self.texture;
+    }
+
+    // copies the texture into a mappable buffer and blocks until it can be read.
+    pub fn read_image(&self, device: &Device, queue: &Queue) -> RgbaImage {
+        let (width, height) = self.size();
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = (width * 4).div_ceil(align) * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("offscreen_target.readback"),
+            size: (padded_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("offscreen_target.readback.encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            self.texture.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::Maintain::Wait);
+
+        let bgra = matches!(
+            self.texture.format(),
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_row as usize) {
+                for pixel in row[..(width * 4) as usize].chunks(4) {
+                    if bgra {
+                        pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                    } else {
+                        pixels.extend_from_slice(pixel);
+                    }
+                }
+            }
+        }
+        buffer.unmap();
+
+        return RgbaImage::from_raw(width, height, pixels).unwrap();
+    }
+}
+
+impl RenderTarget for OffscreenTarget {
+    fn size(&self) -> (u32, u32) {
+        return (self.texture.width(), self.texture.height());
+    }
+
+    fn get_frame(&self, _device: &Device) -> Result<Frame<'_>, RenderError> {
+        return Ok(Frame::new(
+            self.texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            self.multisample.as_ref(),
+            Some(&self.texture),
+            None,
+        ));
+    }
+}
+
+// texture that layers are rendered into once and then drawn as an image, any number of times.
+// it starts transparent and keeps premultiplied colors, which the image pipeline blends as such.
+#[derive(Debug)]
+pub struct TextureTarget {
+    image: ImageResource,
+    multisample: Option<Texture>,
+}
+
+impl TextureTarget {
+    // sample_count must match the renderer drawing into the target.
+    pub fn new(
+        device: &Device,
+        layouts: &BindGroupLayouts,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("texture_target.texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        return Self {
+            image: ImageResource::from_texture(texture, true, device, layouts),
+            multisample: create_multisample_texture(device, width, height, format, sample_count),
+        };
+    }
+
+    // image of the texture without instances. it shares the texture, so it shows later renders too.
+    pub fn get_image(&self) -> ImageResource {
+        let mut image = self.image.clone();
+        image.clear_instances();
+        return image;
+    }
+
+    pub fn get_texture(&self) -> &Texture {
+        return &self.image.texture.texture;
+    }
+}
+
+impl RenderTarget for TextureTarget {
+    fn size(&self) -> (u32, u32) {
+        let texture = self.get_texture();
+        return (texture.width(), texture.height());
+    }
+
+    fn get_frame(&self, _device: &Device) -> Result<Frame<'_>, RenderError> {
+        let texture = self.get_texture();
+        return Ok(Frame::new(
+            texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            self.multisample.as_ref(),
+            Some(texture),
+            None,
+        ));
+    }
+}
+
+// attachment the pipelines draw into before it is resolved to the target, none without multisampling.
+fn create_multisample_texture(
+    device: &Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> Option<Texture> {
+    if sample_count <= 1 {
+        return None;
+    }
+    return Some(device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("target.multisample"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }));
+}
@@ -0,0 +1,175 @@
+// 2d affine transform in pixel space, stored as the first two rows of a 3x3 matrix.
+// x' = m[0][0] * x + m[0][1] * y + m[0][2]
+// y' = m[1][0] * x + m[1][1] * y + m[1][2]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub matrix: [[f32; 3]; 2],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        return Self::identity();
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        return Self {
+            matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        };
+    }
+
+    pub fn translate(x: f32, y: f32) -> Self {
+        return Self {
+            matrix: [[1.0, 0.0, x], [0.0, 1.0, y]],
+        };
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        return Self {
+            matrix: [[x, 0.0, 0.0], [0.0, y, 0.0]],
+        };
+    }
+
+    // clockwise on screen, because the y axis points down.
+    pub fn rotate(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        return Self {
+            matrix: [[cos, -sin, 0.0], [sin, cos, 0.0]],
+        };
+    }
+
+    // rotation or scale around a pivot point instead of the origin.
+    pub fn around(self, x: f32, y: f32) -> Self {
+        return Self::translate(x, y)
+            .multiply(&self)
+            .multiply(&Self::translate(-x, -y));
+    }
+
+    // self * other : other is applied first.
+    pub fn multiply(&self, other: &Transform) -> Transform {
+        let a = &self.matrix;
+        let b = &other.matrix;
+        return Transform {
+            matrix: [
+                [
+                    a[0][0] * b[0][0] + a[0][1] * b[1][0],
+                    a[0][0] * b[0][1] + a[0][1] * b[1][1],
+                    a[0][0] * b[0][2] + a[0][1] * b[1][2] + a[0][2],
+                ],
+                [
+                    a[1][0] * b[0][0] + a[1][1] * b[1][0],
+                    a[1][0] * b[0][1] + a[1][1] * b[1][1],
+                    a[1][0] * b[0][2] + a[1][1] * b[1][2] + a[1][2],
+                ],
+            ],
+        };
+    }
+
+    pub fn get_determinant(&self) -> f32 {
+        let m = &self.matrix;
+        return m[0][0] * m[1][1] - m[0][1] * m[1][0];
+    }
+
+    // a degenerate transform collapses everything, so its inverse maps every point far away.
+    pub fn inverse(&self) -> Transform {
+        let m = &self.matrix;
+        let det = self.get_determinant();
+        if det.abs() < f32::EPSILON {
+            return Self::translate(f32::MAX, f32::MAX).multiply(&Self::scale(0.0, 0.0));
+        }
+        let a = m[1][1] / det;
+        let b = -m[0][1] / det;
+        let c = -m[1][0] / det;
+        let d = m[0][0] / det;
+        return Transform {
+            matrix: [
+                [a, b, -(a * m[0][2] + b * m[1][2])],
+                [c, d, -(c * m[0][2] + d * m[1][2])],
+            ],
+        };
+    }
+
+    pub fn apply(&self, point: [f32; 2]) -> [f32; 2] {
+        let m = &self.matrix;
+        return [
+            m[0][0] * point[0] + m[0][1] * point[1] + m[0][2],
+            m[1][0] * point[0] + m[1][1] * point[1] + m[1][2],
+        ];
+    }
+
+    // axis aligned bounds of a transformed rect.
+    pub fn apply_rect(&self, start: [f32; 2], size: [f32; 2]) -> ([f32; 2], [f32; 2]) {
+        let corners = [
+            self.apply(start),
+            self.apply([start[0] + size[0], start[1]]),
+            self.apply([start[0], start[1] + size[1]]),
+            self.apply([start[0] + size[0], start[1] + size[1]]),
+        ];
+        let min_x = corners.iter().map(|p| p[0]).fold(f32::MAX, f32::min);
+        let min_y = corners.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+        let max_x = corners.iter().map(|p| p[0]).fold(f32::MIN, f32::max);
+        let max_y = corners.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+        return ([min_x, min_y], [max_x - min_x, max_y - min_y]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Transform;
+    use crate::{
+        layer::Layer,
+        pipeline::{circle::CircleInstance, rect::RectInstance},
+        tests::headless_renderer,
+    };
+
+    #[test]
+    fn transform_inverse() {
+        let transform = Transform::translate(10.0, 20.0)
+            .multiply(&Transform::rotate(0.5))
+            .multiply(&Transform::scale(2.0, 3.0));
+        let point = transform.apply([4.0, -7.0]);
+        let back = transform.inverse().apply(point);
+        assert!((back[0] - 4.0).abs() < 1.0e-4);
+        assert!((back[1] + 7.0).abs() < 1.0e-4);
+
+        let rotated = Transform::rotate(std::f32::consts::FRAC_PI_2).around(10.0, 10.0);
+        let point = rotated.apply([20.0, 10.0]);
+        assert!((point[0] - 10.0).abs() < 1.0e-4);
+        assert!((point[1] - 20.0).abs() < 1.0e-4);
+
+        assert_eq!(
+            Transform::scale(2.0, 2.0).apply_rect([1.0, 1.0], [2.0, 3.0]),
+            ([2.0, 2.0], [4.0, 6.0])
+        );
+    }
+
+    #[test]
+    fn mirrored_transform() {
+        let mut renderer = headless_renderer();
+        let target = renderer.create_offscreen_target(64, 64);
+
+        // mirrored quads wind the other way and are still drawn.
+        let mut layer =
+            Layer::default().set_transform(Transform::scale(-1.0, 1.0).around(32.0, 32.0));
+        layer.push_rect(RectInstance::fill(
+            [8.0, 8.0],
+            [16.0, 16.0],
+            [0.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 1.0],
+        ));
+        layer.push_transform(Transform::scale(1.0, -1.0).around(32.0, 32.0));
+        layer.push_circle(CircleInstance::fill(
+            [16.0, 16.0],
+            6.0,
+            [0.0, 1.0, 0.0, 1.0],
+        ));
+        layer.pop_transform();
+        renderer.render(&target, &[layer]).unwrap();
+
+        let image = renderer.read_image(&target);
+        assert_eq!(image.get_pixel(48, 16).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(16, 16).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(48, 48).0, [0, 255, 0, 255]);
+    }
+}